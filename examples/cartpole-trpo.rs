@@ -5,7 +5,7 @@ use relearn::agents::{ActorMode, Agent, BuildAgent};
 use relearn::envs::{
     CartPole, EnvStructure, Environment, VisibleStepLimit, WithVisibleStepLimit, Wrap,
 };
-use relearn::logging::{ByCounter, DisplayLogger, TensorBoardLogger};
+use relearn::logging::{ByCounter, DisplayLogger, LogFilter, StatsLogger, TensorBoardLogger};
 use relearn::simulation::{train_parallel, SimSeed, StepsIter, TrainParallelConfig};
 use relearn::torch::agents::{critics::ValuesOptConfig, policies::TrpoConfig, ActorCriticConfig};
 use relearn::torch::modules::MlpConfig;
@@ -51,8 +51,10 @@ fn main() {
 
             println!("Logging to {:?}", output_dir);
             let log_on_name = ["agent_update", "count"];
+            // Keep per-optimization-step statistics out of the display but not tensorboard
+            let display_filter = LogFilter::new().exclude("agent_update/**/step/**");
             let mut logger = (
-                DisplayLogger::new(ByCounter::of_path(log_on_name, 2)),
+                DisplayLogger::new(ByCounter::of_path(log_on_name, 2)).filtered(display_filter),
                 TensorBoardLogger::new(ByCounter::of_path(log_on_name, 1), &output_dir),
             );
 
@@ -416,6 +416,7 @@ impl TrainConfig {
             target: StepValueTarget::default(),
             opt_steps_per_update: 50,
             max_discount_factor: 0.99,
+            clip_distance: None,
         };
         let agent_config = ActorCriticConfig {
             policy_config,
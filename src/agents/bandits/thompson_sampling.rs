@@ -1,7 +1,7 @@
 //! Thompson sampling bandit agent
 use super::super::{
-    buffers::VecBuffer, finite::FiniteSpaceAgent, Actor, ActorMode, Agent, BatchUpdate, BuildAgent,
-    BuildAgentError, HistoryDataBound,
+    buffers::VecBuffer, finite::FiniteSpaceAgent, Actor, ActorMode, Agent, BatchActor, BatchUpdate,
+    BuildAgent, BuildAgentError, HistoryDataBound,
 };
 use crate::envs::EnvStructure;
 use crate::feedback::Reward;
@@ -214,6 +214,8 @@ impl Actor<usize, usize> for BaseBetaThompsonSamplingActor {
     }
 }
 
+impl BatchActor<usize, usize> for BaseBetaThompsonSamplingActor {}
+
 #[cfg(test)]
 mod beta_thompson_sampling {
     use super::super::super::testing;
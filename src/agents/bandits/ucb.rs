@@ -1,7 +1,7 @@
 //! Upper confidence bound bandit agent.
 use super::super::{
-    buffers::VecBuffer, finite::FiniteSpaceAgent, Actor, ActorMode, Agent, BatchUpdate, BuildAgent,
-    BuildAgentError, HistoryDataBound,
+    buffers::VecBuffer, finite::FiniteSpaceAgent, Actor, ActorMode, Agent, BatchActor, BatchUpdate,
+    BuildAgent, BuildAgentError, HistoryDataBound,
 };
 use crate::envs::EnvStructure;
 use crate::feedback::Reward;
@@ -245,6 +245,8 @@ impl Actor<usize, usize> for BaseUCB1Actor {
     }
 }
 
+impl BatchActor<usize, usize> for BaseUCB1Actor {}
+
 #[cfg(test)]
 mod ucb1_agent {
     use super::super::super::testing;
@@ -7,11 +7,14 @@
 //! 2. A trait is not necessary because each agent uses a single specific buffer type.
 //!    (Although a trait might be nice for organization).
 mod null;
+mod prioritized;
 mod replay;
+mod sum_tree;
 mod vec;
 
 pub use null::NullBuffer;
-pub use replay::ReplayBuffer;
+pub use prioritized::{sample_prioritized, PrioritizedEpisodes, PrioritizedReplayBuffer};
+pub use replay::{Episodes, ReplayBuffer};
 pub use vec::VecBuffer;
 
 use crate::envs::Successor;
@@ -153,14 +156,45 @@ pub trait WriteExperience<O, A, F>: WriteExperienceIncremental<O, A, F> {
         self.end_experience();
         Ok(())
     }
+
+    /// Write a thread of experience along with the behaviour policy log probabilities.
+    ///
+    /// `log_probs` contains the log probability with which the actor selected the action of
+    /// each step (see [`Actor::act_with_log_prob`][1]), one per step in order.
+    /// The default implementation discards the log probabilities.
+    ///
+    /// [1]: crate::agents::Actor::act_with_log_prob
+    fn write_experience_with_log_probs<I>(
+        &mut self,
+        steps: I,
+        log_probs: Vec<f64>,
+    ) -> Result<(), WriteExperienceError>
+    where
+        I: IntoIterator<Item = PartialStep<O, A, F>>,
+        Self: Sized,
+    {
+        drop(log_probs);
+        self.write_experience(steps)
+    }
 }
 
 /// Implement `WriteExperience<O, A, F>` for a deref-able generic wrapper type.
 macro_rules! impl_wrapped_write_experience {
     ($wrapper:ty) => {
-        impl<T, O, A, F> WriteExperience<O, A, F> for $wrapper where
-            T: WriteExperience<O, A, F> + ?Sized
+        impl<T, O, A, F> WriteExperience<O, A, F> for $wrapper
+        where
+            T: WriteExperience<O, A, F>,
         {
+            fn write_experience_with_log_probs<I>(
+                &mut self,
+                steps: I,
+                log_probs: Vec<f64>,
+            ) -> Result<(), WriteExperienceError>
+            where
+                I: IntoIterator<Item = PartialStep<O, A, F>>,
+            {
+                T::write_experience_with_log_probs(self, steps, log_probs)
+            }
         }
     };
 }
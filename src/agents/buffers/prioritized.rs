@@ -0,0 +1,443 @@
+use super::replay::{Episodes, ReplayBuffer};
+use super::sum_tree::SumTree;
+use super::{WriteExperience, WriteExperienceError, WriteExperienceIncremental};
+use crate::feedback::Reward;
+use crate::simulation::PartialStep;
+use crate::utils::sequence::Sequence;
+use crate::utils::slice::SplitSlice;
+use rand::Rng;
+use std::collections::vec_deque;
+
+/// A [`ReplayBuffer`] with prioritized episode sampling.
+///
+/// Each stored episode has a priority `p` and is sampled with probability proportional to
+/// `p ^ alpha`. New episodes are assigned the maximum priority seen so far so that they are
+/// likely to be sampled at least once.
+///
+/// # Reference
+/// "[Prioritized Experience Replay][per]" by Schaul et al. (2015)
+///
+/// [per]: https://arxiv.org/abs/1511.05952
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrioritizedReplayBuffer<O, A, F = Reward> {
+    buffer: ReplayBuffer<O, A, F>,
+    /// Sum tree of `priority ^ alpha` for each episode slot.
+    ///
+    /// The episode with lifetime index `i` is stored in slot `i % tree.len()`.
+    /// Each episode has at least one step so there are never more episodes than slots.
+    tree: SumTree,
+    /// Priority of each episode slot. Zero for unused slots.
+    priorities: Vec<f64>,
+    /// Priority exponent.
+    alpha: f64,
+    /// Priority assigned to new episodes. The largest priority seen so far.
+    max_priority: f64,
+    /// Lifetime index of the first stored episode as of the last priority sync.
+    synced_episodes_start: u64,
+    /// Lifetime index one past the last stored episode as of the last priority sync.
+    synced_episodes_end: u64,
+}
+
+impl<O, A, F> PrioritizedReplayBuffer<O, A, F> {
+    /// Create a new `PrioritizedReplayBuffer` with space for at least `capacity` steps.
+    ///
+    /// # Args
+    /// * `capacity` - Minimum step capacity. See [`ReplayBuffer::with_capacity`].
+    /// * `alpha` - Priority exponent. `0` is uniform sampling, `1` is fully prioritized.
+    #[must_use]
+    pub fn with_capacity(capacity: usize, alpha: f64) -> Self {
+        let buffer = ReplayBuffer::with_capacity(capacity);
+        let num_slots = buffer.capacity();
+        Self {
+            buffer,
+            tree: SumTree::new(num_slots),
+            priorities: vec![0.0; num_slots],
+            alpha,
+            max_priority: 1.0,
+            synced_episodes_start: 0,
+            synced_episodes_end: 0,
+        }
+    }
+
+    /// The step capacity of the buffer. Will not grow beyond this capacity.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// The number of steps stored in the buffer.
+    #[must_use]
+    pub fn num_steps(&self) -> usize {
+        self.buffer.num_steps()
+    }
+
+    /// The number of episodes stored in the buffer.
+    #[must_use]
+    pub fn num_episodes(&self) -> usize {
+        self.buffer.num_episodes()
+    }
+
+    /// Iterator over all steps stored in the buffer.
+    #[must_use]
+    pub fn steps(&self) -> vec_deque::Iter<'_, PartialStep<O, A, F>> {
+        self.buffer.steps()
+    }
+
+    /// View all episodes stored in the buffer.
+    #[must_use]
+    pub fn episodes(&self) -> Episodes<'_, O, A, F> {
+        self.buffer.episodes()
+    }
+
+    /// Total number of steps consumed by the buffer over its lifetime.
+    #[must_use]
+    pub const fn total_step_count(&self) -> u64 {
+        self.buffer.total_step_count()
+    }
+
+    /// The priority exponent.
+    #[must_use]
+    pub const fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Set the priority exponent. Takes time linear in the buffer capacity if changed.
+    pub fn set_alpha(&mut self, alpha: f64) {
+        #[allow(clippy::float_cmp)] // Exact comparison is intended; avoids an unnecessary rebuild
+        if alpha == self.alpha {
+            return;
+        }
+        self.alpha = alpha;
+        for (slot, priority) in self.priorities.iter().enumerate() {
+            self.tree.set(slot, scaled_priority(*priority, alpha));
+        }
+    }
+
+    /// Sum over all stored episodes of `priority ^ alpha`.
+    #[must_use]
+    pub fn total_scaled_priority(&self) -> f64 {
+        self.tree.total()
+    }
+
+    /// The priority of the episode at the given index of [`episodes`](Self::episodes).
+    ///
+    /// # Panics
+    /// If `index >= self.num_episodes()`.
+    #[must_use]
+    pub fn episode_priority(&self, index: usize) -> f64 {
+        self.priorities[self.slot(index)]
+    }
+
+    /// The probability of sampling the episode at the given index of [`episodes`](Self::episodes).
+    ///
+    /// # Panics
+    /// If `index >= self.num_episodes()`.
+    #[must_use]
+    pub fn episode_probability(&self, index: usize) -> f64 {
+        self.tree.get(self.slot(index)) / self.tree.total()
+    }
+
+    /// Set the priority of the episode at the given index of [`episodes`](Self::episodes).
+    ///
+    /// # Panics
+    /// If `index >= self.num_episodes()` or if `priority` is negative or not finite.
+    pub fn set_episode_priority(&mut self, index: usize, priority: f64) {
+        assert!(
+            priority.is_finite() && priority >= 0.0,
+            "priority must be finite and non-negative; got {priority}"
+        );
+        let slot = self.slot(index);
+        self.set_slot_priority(slot, priority);
+        self.max_priority = self.max_priority.max(priority);
+    }
+
+    /// Sample the index of an episode with probability proportional to `priority ^ alpha`.
+    ///
+    /// Returns `None` if the buffer is empty or all priorities are zero.
+    pub fn sample_episode<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<usize> {
+        let mass = rng.gen::<f64>() * self.tree.total();
+        let slot = self.tree.find_prefix_sum(mass)?;
+        let num_slots = self.tree.len() as u64;
+        let start_slot = self.synced_episodes_start % num_slots;
+        // Bounded by num_slots, which is a usize.
+        #[allow(clippy::cast_possible_truncation)]
+        let index = ((slot as u64 + num_slots - start_slot) % num_slots) as usize;
+        Some(index)
+    }
+
+    /// The slot of the episode at the given index of `episodes()`.
+    fn slot(&self, index: usize) -> usize {
+        assert!(index < self.num_episodes(), "episode index out of bounds");
+        self.lifetime_slot(self.synced_episodes_start + index as u64)
+    }
+
+    /// The slot of an episode given its lifetime index.
+    // The remainder is less than tree.len(), which is a usize.
+    #[allow(clippy::cast_possible_truncation)]
+    const fn lifetime_slot(&self, lifetime_index: u64) -> usize {
+        (lifetime_index % self.tree.len() as u64) as usize
+    }
+
+    fn set_slot_priority(&mut self, slot: usize, priority: f64) {
+        self.priorities[slot] = priority;
+        self.tree.set(slot, scaled_priority(priority, self.alpha));
+    }
+
+    /// Update the slot priorities to match the episodes stored in `buffer`.
+    ///
+    /// Dropped episodes are cleared and new episodes are given the maximum priority.
+    fn sync_priorities(&mut self) {
+        let end = self.buffer.total_episode_count();
+        let start = end - self.buffer.num_episodes() as u64;
+        for lifetime_index in self.synced_episodes_start..start.min(self.synced_episodes_end) {
+            self.set_slot_priority(self.lifetime_slot(lifetime_index), 0.0);
+        }
+        for lifetime_index in self.synced_episodes_end.max(start)..end {
+            self.set_slot_priority(self.lifetime_slot(lifetime_index), self.max_priority);
+        }
+        self.synced_episodes_start = start;
+        self.synced_episodes_end = end;
+    }
+}
+
+/// Apply the priority exponent.
+fn scaled_priority(priority: f64, alpha: f64) -> f64 {
+    // Treat 0^0 as 0 so that unused slots are never sampled.
+    if priority > 0.0 {
+        priority.powf(alpha)
+    } else {
+        0.0
+    }
+}
+
+impl<O, A, F> WriteExperienceIncremental<O, A, F> for PrioritizedReplayBuffer<O, A, F> {
+    fn write_step(&mut self, step: PartialStep<O, A, F>) -> Result<(), WriteExperienceError> {
+        let result = self.buffer.write_step(step);
+        self.sync_priorities();
+        result
+    }
+
+    fn end_experience(&mut self) {
+        self.buffer.end_experience();
+        self.sync_priorities();
+    }
+}
+
+impl<O, A, F> WriteExperience<O, A, F> for PrioritizedReplayBuffer<O, A, F> {}
+
+/// Episodes sampled from [`PrioritizedReplayBuffer`]s along with importance-sampling weights.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrioritizedEpisodes<'a, O, A, F = Reward> {
+    /// The sampled episodes.
+    pub episodes: Vec<SplitSlice<'a, PartialStep<O, A, F>>>,
+    /// Location of each episode as `(buffer_index, episode_index)`.
+    ///
+    /// Use with [`PrioritizedReplayBuffer::set_episode_priority`] to update the priorities.
+    pub locations: Vec<(usize, usize)>,
+    /// Importance-sampling weight of each episode.
+    ///
+    /// The weights are `(N * P(i)) ^ -beta` normalized to have a maximum value of `1`,
+    /// where `N` is the total number of episodes and `P(i)` is the sampling probability of
+    /// episode `i`.
+    pub weights: Vec<f64>,
+}
+
+/// Sample episodes with prioritization across a collection of buffers.
+///
+/// Episodes are sampled with replacement with probability proportional to `priority ^ alpha`
+/// relative to all episodes in all buffers until at least `min_steps` steps have been sampled.
+/// The result is empty if there are no episodes with non-zero priority.
+///
+/// # Args
+/// * `buffers` - Buffers from which to sample.
+/// * `min_steps` - Minimum total number of steps in the sampled episodes.
+/// * `beta` - Importance sampling exponent. `1` fully corrects for the prioritization bias.
+/// * `rng` - Random number generator.
+#[allow(clippy::cast_precision_loss)]
+pub fn sample_prioritized<'a, O, A, F, R>(
+    buffers: &[&'a PrioritizedReplayBuffer<O, A, F>],
+    min_steps: usize,
+    beta: f64,
+    rng: &mut R,
+) -> PrioritizedEpisodes<'a, O, A, F>
+where
+    R: Rng + ?Sized,
+{
+    let total_scaled_priority: f64 = buffers.iter().map(|b| b.total_scaled_priority()).sum();
+    let total_episodes: usize = buffers.iter().map(|b| b.num_episodes()).sum();
+
+    let mut episodes = Vec::new();
+    let mut locations = Vec::new();
+    let mut weights = Vec::new();
+    if total_scaled_priority <= 0.0 {
+        return PrioritizedEpisodes {
+            episodes,
+            locations,
+            weights,
+        };
+    }
+
+    let mut num_steps = 0;
+    while num_steps < min_steps {
+        // Select a buffer with probability proportional to its total priority
+        let mut mass = rng.gen::<f64>() * total_scaled_priority;
+        let buffer_index = buffers
+            .iter()
+            .position(|b| {
+                mass -= b.total_scaled_priority();
+                mass < 0.0
+            })
+            // Guard against rounding errors
+            .unwrap_or_else(|| {
+                buffers
+                    .iter()
+                    .rposition(|b| b.total_scaled_priority() > 0.0)
+                    .unwrap()
+            });
+        let buffer = buffers[buffer_index];
+        let episode_index = buffer.sample_episode(rng).unwrap();
+        let episode = buffer.episodes().get(episode_index).unwrap();
+
+        let probability = buffer.episode_probability(episode_index)
+            * buffer.total_scaled_priority()
+            / total_scaled_priority;
+        num_steps += episode.len();
+        episodes.push(episode);
+        locations.push((buffer_index, episode_index));
+        weights.push((total_episodes as f64 * probability).powf(-beta));
+    }
+
+    let max_weight = weights.iter().copied().fold(0.0, f64::max);
+    for weight in &mut weights {
+        *weight /= max_weight;
+    }
+
+    PrioritizedEpisodes {
+        episodes,
+        locations,
+        weights,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)] // expecting exact values
+mod tests {
+    use super::*;
+    use crate::envs::Successor::{self, Continue, Terminate};
+    use crate::Prng;
+    use rand::SeedableRng;
+
+    const fn step(observation: usize, next: Successor<usize, ()>) -> PartialStep<usize, bool> {
+        PartialStep {
+            observation,
+            action: false,
+            feedback: Reward(0.0),
+            next,
+        }
+    }
+
+    /// Buffer of capacity 7 containing the episodes `[0, 1, 2]`, `[3]`, and `[4, 5]`.
+    fn buffer(alpha: f64) -> PrioritizedReplayBuffer<usize, bool> {
+        let mut buffer = PrioritizedReplayBuffer::with_capacity(7, alpha);
+        assert_eq!(
+            buffer.capacity(),
+            7,
+            "Implementation detail; rework test if this fails"
+        );
+        buffer
+            .write_experience([
+                step(0, Continue(())),
+                step(1, Continue(())),
+                step(2, Terminate),
+                step(3, Terminate),
+                step(4, Continue(())),
+                step(5, Terminate),
+            ])
+            .unwrap();
+        buffer
+    }
+
+    #[test]
+    fn new_episodes_have_max_priority() {
+        let mut buffer = buffer(1.0);
+        assert_eq!(buffer.episode_priority(0), 1.0);
+        buffer.set_episode_priority(1, 3.0);
+        buffer.write_experience([step(6, Terminate)]).unwrap();
+        assert_eq!(buffer.num_episodes(), 4);
+        assert_eq!(buffer.episode_priority(3), 3.0);
+        assert_eq!(buffer.total_scaled_priority(), 8.0);
+    }
+
+    #[test]
+    fn dropped_episodes_are_cleared() {
+        let mut buffer = buffer(1.0);
+        buffer.set_episode_priority(0, 5.0);
+        buffer.set_episode_priority(2, 0.5);
+        // Drops the first episode
+        buffer
+            .write_experience([step(6, Continue(())), step(7, Terminate)])
+            .unwrap();
+        assert_eq!(buffer.num_episodes(), 3);
+        assert_eq!(buffer.episode_priority(0), 1.0);
+        assert_eq!(buffer.episode_priority(1), 0.5);
+        assert_eq!(buffer.episode_priority(2), 5.0);
+        assert_eq!(buffer.total_scaled_priority(), 6.5);
+    }
+
+    #[test]
+    fn episode_probability() {
+        let mut buffer = buffer(2.0);
+        buffer.set_episode_priority(0, 2.0);
+        assert_eq!(buffer.total_scaled_priority(), 6.0);
+        assert_eq!(buffer.episode_probability(0), 4.0 / 6.0);
+        buffer.set_alpha(0.0);
+        assert_eq!(buffer.episode_probability(0), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn sample_episode_proportional() {
+        let mut buffer = buffer(1.0);
+        buffer.set_episode_priority(0, 0.0);
+        buffer.set_episode_priority(1, 1.0);
+        buffer.set_episode_priority(2, 3.0);
+        let mut rng = Prng::seed_from_u64(0);
+        let mut counts = [0; 3];
+        for _ in 0..4000 {
+            counts[buffer.sample_episode(&mut rng).unwrap()] += 1;
+        }
+        assert_eq!(counts[0], 0);
+        assert!((900..1100).contains(&counts[1]), "{counts:?}");
+        assert!((2900..3100).contains(&counts[2]), "{counts:?}");
+    }
+
+    #[test]
+    fn sample_prioritized_weights() {
+        let mut buffer1 = buffer(1.0);
+        buffer1.set_episode_priority(0, 4.0);
+        let buffer2 = buffer(1.0);
+        let mut rng = Prng::seed_from_u64(1);
+
+        let sample = sample_prioritized(&[&buffer1, &buffer2], 100, 1.0, &mut rng);
+        assert!(sample.episodes.iter().map(Sequence::len).sum::<usize>() >= 100);
+        assert_eq!(sample.locations.len(), sample.episodes.len());
+        for (&(buffer_index, episode_index), &weight) in
+            sample.locations.iter().zip(&sample.weights)
+        {
+            let expected = if (buffer_index, episode_index) == (0, 0) {
+                0.25
+            } else {
+                1.0
+            };
+            assert!((weight - expected).abs() < 1e-12);
+        }
+        assert!(sample.locations.contains(&(0, 0)));
+    }
+
+    #[test]
+    fn sample_prioritized_empty() {
+        let buffer = PrioritizedReplayBuffer::<usize, bool>::with_capacity(7, 1.0);
+        let mut rng = Prng::seed_from_u64(2);
+        let sample = sample_prioritized(&[&buffer], 10, 1.0, &mut rng);
+        assert!(sample.episodes.is_empty());
+    }
+}
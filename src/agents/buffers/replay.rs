@@ -24,6 +24,10 @@ pub struct ReplayBuffer<O, A, F = Reward> {
     /// Excludes steps dropped for being the last of an incomplete episode.
     /// Includes steps from old episodes that are dropped to make room in the buffer.
     total_step_count: u64,
+    /// Total number of episodes collected by this buffer over its lifetime.
+    ///
+    /// Includes episodes that are dropped to make room in the buffer.
+    total_episode_count: u64,
 }
 
 impl<O, A, F> ReplayBuffer<O, A, F> {
@@ -38,6 +42,7 @@ impl<O, A, F> ReplayBuffer<O, A, F> {
             episode_ends: VecDeque::new(),
             index_offset: 0,
             total_step_count: 0,
+            total_episode_count: 0,
         }
     }
 
@@ -83,6 +88,15 @@ impl<O, A, F> ReplayBuffer<O, A, F> {
     pub const fn total_step_count(&self) -> u64 {
         self.total_step_count
     }
+
+    /// Total number of episodes completed by the buffer over its lifetime.
+    ///
+    /// Includes episodes that have since been dropped to make room in the buffer.
+    /// The stored episodes are the most recent [`num_episodes`](Self::num_episodes) of these.
+    #[must_use]
+    pub const fn total_episode_count(&self) -> u64 {
+        self.total_episode_count
+    }
 }
 
 impl<O, A, F> WriteExperienceIncremental<O, A, F> for ReplayBuffer<O, A, F> {
@@ -109,6 +123,7 @@ impl<O, A, F> WriteExperienceIncremental<O, A, F> for ReplayBuffer<O, A, F> {
         self.total_step_count += 1;
         if episode_done {
             self.episode_ends.push_back(self.total_step_count);
+            self.total_episode_count += 1;
         }
         Ok(())
     }
@@ -117,6 +132,7 @@ impl<O, A, F> WriteExperienceIncremental<O, A, F> for ReplayBuffer<O, A, F> {
         if super::finalize_last_episode(&mut self.steps) {
             self.total_step_count -= 1; // The last step was dropped.
             self.episode_ends.push_back(self.total_step_count);
+            self.total_episode_count += 1;
             assert_eq!(
                 self.total_step_count,
                 self.index_offset + self.steps.len() as u64
@@ -264,6 +280,7 @@ mod tests {
         buffer.write_experience(ep45).unwrap();
         assert_eq!(buffer.num_steps(), 7);
         assert_eq!(buffer.num_episodes(), 4);
+        assert_eq!(buffer.total_episode_count(), 5);
         assert!(buffer
             .steps()
             .eq(ep2_finalized.iter().chain(&ep3).chain(&ep45)));
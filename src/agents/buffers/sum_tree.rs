@@ -0,0 +1,145 @@
+//! Sum tree data structure
+
+/// A fixed-size array of non-negative values supporting fast prefix-sum search.
+///
+/// Implemented as a complete binary tree in which each internal node stores the sum of its
+/// children. Updating a value and finding the element containing a given prefix sum both take
+/// `O(log n)` time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SumTree {
+    /// Number of elements.
+    len: usize,
+    /// Tree nodes in breadth-first order starting from index `1` (the root).
+    ///
+    /// The leaves are stored in `nodes[num_leaves..]` where `num_leaves = nodes.len() / 2`.
+    /// `nodes[0]` is unused.
+    nodes: Vec<f64>,
+}
+
+impl SumTree {
+    /// Create a new tree of `len` zero-valued elements.
+    pub fn new(len: usize) -> Self {
+        let num_leaves = len.next_power_of_two();
+        Self {
+            len,
+            nodes: vec![0.0; 2 * num_leaves],
+        }
+    }
+
+    /// Number of elements.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    const fn num_leaves(&self) -> usize {
+        self.nodes.len() / 2
+    }
+
+    /// Sum of all element values.
+    pub fn total(&self) -> f64 {
+        self.nodes[1]
+    }
+
+    /// Get the value of an element.
+    ///
+    /// # Panics
+    /// If `index >= self.len()`.
+    pub fn get(&self, index: usize) -> f64 {
+        assert!(index < self.len, "index out of bounds");
+        self.nodes[self.num_leaves() + index]
+    }
+
+    /// Set the value of an element.
+    ///
+    /// # Panics
+    /// If `index >= self.len()` or if `value` is negative or NaN.
+    pub fn set(&mut self, index: usize, value: f64) {
+        assert!(index < self.len, "index out of bounds");
+        assert!(value >= 0.0, "value must be non-negative; got {value}");
+        let mut node = self.num_leaves() + index;
+        self.nodes[node] = value;
+        while node > 1 {
+            node /= 2;
+            self.nodes[node] = self.nodes[2 * node] + self.nodes[2 * node + 1];
+        }
+    }
+
+    /// Find the element at which the cumulative sum of values exceeds `mass`.
+    ///
+    /// Sampling `mass` uniformly from `[0, self.total())` selects each element with probability
+    /// proportional to its value. Never returns an element with zero value.
+    ///
+    /// Returns `None` if all values are zero.
+    pub fn find_prefix_sum(&self, mut mass: f64) -> Option<usize> {
+        if self.total() <= 0.0 {
+            return None;
+        }
+        let num_leaves = self.num_leaves();
+        let mut node = 1;
+        while node < num_leaves {
+            let left = 2 * node;
+            // Go left if the mass is in the left subtree or if the right subtree is empty.
+            // The latter guards against floating-point rounding of the subtree sums.
+            if mass < self.nodes[left] || self.nodes[left + 1] <= 0.0 {
+                node = left;
+            } else {
+                mass -= self.nodes[left];
+                node = left + 1;
+            }
+        }
+        Some(node - num_leaves)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)] // expecting exact values
+mod tests {
+    use super::*;
+
+    #[test]
+    fn total() {
+        let mut tree = SumTree::new(5);
+        tree.set(0, 1.0);
+        tree.set(3, 2.5);
+        tree.set(4, 0.5);
+        assert_eq!(tree.total(), 4.0);
+        tree.set(3, 0.0);
+        assert_eq!(tree.total(), 1.5);
+        assert_eq!(tree.get(4), 0.5);
+    }
+
+    #[test]
+    fn find_prefix_sum() {
+        let mut tree = SumTree::new(5);
+        tree.set(0, 1.0);
+        tree.set(2, 2.0);
+        tree.set(4, 3.0);
+        assert_eq!(tree.find_prefix_sum(0.0), Some(0));
+        assert_eq!(tree.find_prefix_sum(0.99), Some(0));
+        assert_eq!(tree.find_prefix_sum(1.0), Some(2));
+        assert_eq!(tree.find_prefix_sum(2.99), Some(2));
+        assert_eq!(tree.find_prefix_sum(3.0), Some(4));
+        assert_eq!(tree.find_prefix_sum(5.99), Some(4));
+    }
+
+    #[test]
+    fn find_prefix_sum_past_total_is_nonzero() {
+        let mut tree = SumTree::new(5);
+        tree.set(1, 1.0);
+        tree.set(2, 2.0);
+        assert_eq!(tree.find_prefix_sum(100.0), Some(2));
+    }
+
+    #[test]
+    fn find_prefix_sum_empty() {
+        let tree = SumTree::new(5);
+        assert_eq!(tree.find_prefix_sum(0.0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn set_out_of_bounds() {
+        let mut tree = SumTree::new(5);
+        tree.set(5, 1.0);
+    }
+}
@@ -17,6 +17,8 @@ pub struct VecBuffer<O, A, F = Reward> {
     steps: Vec<PartialStep<O, A, F>>,
     /// One past the end index of each episode within `steps`.
     episode_ends: Vec<usize>,
+    /// Behaviour policy log probability of each step, if known for all steps.
+    behaviour_log_probs: Option<Vec<f64>>,
 }
 
 impl<O, A, F> VecBuffer<O, A, F> {
@@ -26,6 +28,7 @@ impl<O, A, F> VecBuffer<O, A, F> {
         Self {
             steps: Vec::new(),
             episode_ends: Vec::new(),
+            behaviour_log_probs: Some(Vec::new()),
         }
     }
 
@@ -35,6 +38,7 @@ impl<O, A, F> VecBuffer<O, A, F> {
         Self {
             steps: Vec::with_capacity(bound.min_steps.saturating_add(bound.slack_steps)),
             episode_ends: Vec::new(),
+            behaviour_log_probs: Some(Vec::new()),
         }
     }
 
@@ -42,6 +46,7 @@ impl<O, A, F> VecBuffer<O, A, F> {
     pub fn clear(&mut self) {
         self.steps.clear();
         self.episode_ends.clear();
+        self.behaviour_log_probs = Some(Vec::new());
     }
 
     /// The number of steps stored in the buffer.
@@ -63,9 +68,19 @@ impl<O, A, F> VecBuffer<O, A, F> {
 
     /// Draining iterator over all steps stored in the buffer.
     pub fn drain_steps(&mut self) -> vec::Drain<PartialStep<O, A, F>> {
+        self.behaviour_log_probs = Some(Vec::new());
         self.steps.drain(..)
     }
 
+    /// Behaviour policy log probability of each stored step, in the same order as `steps()`.
+    ///
+    /// Only available if all stored steps were written with
+    /// [`WriteExperience::write_experience_with_log_probs`].
+    #[must_use]
+    pub fn behaviour_log_probs(&self) -> Option<&[f64]> {
+        self.behaviour_log_probs.as_deref()
+    }
+
     /// Iterator over all episode slices stored in the buffer.
     #[must_use]
     pub fn episodes(&self) -> EpisodesIter<O, A, F> {
@@ -74,6 +89,20 @@ impl<O, A, F> VecBuffer<O, A, F> {
             Differences::new(self.episode_ends.iter().copied(), 0),
         )
     }
+
+    /// Append steps and record the end of any completed episodes.
+    fn write_experience_steps<I>(&mut self, steps: I)
+    where
+        I: IntoIterator<Item = PartialStep<O, A, F>>,
+    {
+        let offset = self.steps.len();
+        self.steps.extend(steps);
+        for (i, step) in self.steps[offset..].iter().enumerate() {
+            if step.episode_done() {
+                self.episode_ends.push(offset + i + 1)
+            }
+        }
+    }
 }
 
 impl<O, A, F> From<Vec<PartialStep<O, A, F>>> for VecBuffer<O, A, F> {
@@ -92,6 +121,7 @@ impl<O, A, F> From<Vec<PartialStep<O, A, F>>> for VecBuffer<O, A, F> {
         let mut buffer = Self {
             steps,
             episode_ends,
+            behaviour_log_probs: None,
         };
         buffer.end_experience();
         buffer
@@ -111,15 +141,36 @@ impl<O, A, F> FromIterator<PartialStep<O, A, F>> for VecBuffer<O, A, F> {
 
 impl<O, A, F> WriteExperience<O, A, F> for VecBuffer<O, A, F> {
     fn write_experience<I>(&mut self, steps: I) -> Result<(), WriteExperienceError>
+    where
+        I: IntoIterator<Item = PartialStep<O, A, F>>,
+    {
+        self.write_experience_steps(steps);
+        self.behaviour_log_probs = None;
+        self.end_experience();
+        Ok(())
+    }
+
+    /// Write a thread of experience along with the behaviour policy log probabilities.
+    ///
+    /// # Panics
+    /// If the number of log probabilities differs from the number of steps.
+    fn write_experience_with_log_probs<I>(
+        &mut self,
+        steps: I,
+        log_probs: Vec<f64>,
+    ) -> Result<(), WriteExperienceError>
     where
         I: IntoIterator<Item = PartialStep<O, A, F>>,
     {
         let offset = self.steps.len();
-        self.steps.extend(steps);
-        for (i, step) in self.steps[offset..].iter().enumerate() {
-            if step.episode_done() {
-                self.episode_ends.push(offset + i + 1)
-            }
+        self.write_experience_steps(steps);
+        assert_eq!(
+            self.steps.len() - offset,
+            log_probs.len(),
+            "number of log probabilities does not match the number of steps"
+        );
+        if let Some(behaviour_log_probs) = &mut self.behaviour_log_probs {
+            behaviour_log_probs.extend(log_probs);
         }
         self.end_experience();
         Ok(())
@@ -128,6 +179,7 @@ impl<O, A, F> WriteExperience<O, A, F> for VecBuffer<O, A, F> {
 
 impl<O, A, F> WriteExperienceIncremental<O, A, F> for VecBuffer<O, A, F> {
     fn write_step(&mut self, step: PartialStep<O, A, F>) -> Result<(), WriteExperienceError> {
+        self.behaviour_log_probs = None;
         let episode_done = step.episode_done();
         self.steps.push(step);
         if episode_done {
@@ -140,6 +192,10 @@ impl<O, A, F> WriteExperienceIncremental<O, A, F> for VecBuffer<O, A, F> {
         if super::finalize_last_episode(&mut self.steps) {
             self.episode_ends.push(self.steps.len())
         }
+        // Finalization may pop the last step
+        if let Some(behaviour_log_probs) = &mut self.behaviour_log_probs {
+            behaviour_log_probs.truncate(self.steps.len());
+        }
     }
 }
 
@@ -274,4 +330,40 @@ mod tests {
             buffer.num_steps()
         );
     }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn behaviour_log_probs() {
+        let mut buffer = VecBuffer::new();
+        assert_eq!(buffer.behaviour_log_probs(), Some(&[][..]));
+        buffer
+            .write_experience_with_log_probs(
+                [
+                    step(0, Terminate),
+                    step(1, Continue(())),
+                    step(2, Continue(())),
+                ],
+                vec![-1.0, -2.0, -3.0],
+            )
+            .unwrap();
+        // The last step is dropped in finalization
+        assert_eq!(buffer.behaviour_log_probs(), Some(&[-1.0, -2.0][..]));
+
+        buffer.write_experience([step(3, Terminate)]).unwrap();
+        assert_eq!(buffer.behaviour_log_probs(), None);
+
+        buffer.clear();
+        assert_eq!(buffer.behaviour_log_probs(), Some(&[][..]));
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn behaviour_log_probs_through_wrapper() {
+        let mut buffer = Box::new(VecBuffer::new());
+        // Resolves to the Box<VecBuffer> implementation
+        buffer
+            .write_experience_with_log_probs([step(0, Terminate)], vec![-1.0])
+            .unwrap();
+        assert_eq!(buffer.behaviour_log_probs(), Some(&[-1.0][..]));
+    }
 }
@@ -1,6 +1,6 @@
 use super::{
-    Actor, ActorMode, Agent, BatchUpdate, HistoryDataBound, WriteExperience, WriteExperienceError,
-    WriteExperienceIncremental,
+    Actor, ActorMode, Agent, BatchActor, BatchUpdate, HistoryDataBound, WriteExperience,
+    WriteExperienceError, WriteExperienceIncremental,
 };
 use crate::envs::Successor;
 use crate::logging::StatsLogger;
@@ -75,6 +75,52 @@ where
             .from_index(action_index)
             .expect("invalid action index")
     }
+
+    fn act_with_log_prob(
+        &self,
+        episode_state: &mut Self::EpisodeState,
+        observation: &OS::Element,
+        rng: &mut Prng,
+    ) -> (AS::Element, Option<f64>) {
+        let observation_index = self.observation_space.to_index(observation);
+        let (action_index, log_prob) =
+            self.actor
+                .act_with_log_prob(episode_state, &observation_index, rng);
+        let action = self
+            .action_space
+            .from_index(action_index)
+            .expect("invalid action index");
+        (action, log_prob)
+    }
+}
+
+impl<T, OS, AS> BatchActor<OS::Element, AS::Element> for FiniteSpaceActor<T, OS, AS>
+where
+    T: BatchActor<usize, usize>,
+    OS: FiniteSpace,
+    AS: FiniteSpace,
+{
+    fn batch_act(
+        &self,
+        episode_states: &mut [&mut Self::EpisodeState],
+        observations: &[&OS::Element],
+        rng: &mut Prng,
+    ) -> Vec<AS::Element> {
+        let observation_indices: Vec<_> = observations
+            .iter()
+            .map(|observation| self.observation_space.to_index(observation))
+            .collect();
+        let observation_refs: Vec<_> = observation_indices.iter().collect();
+        self.actor
+            .batch_act(episode_states, &observation_refs, rng)
+            .into_iter()
+            .map(|action_index| {
+                self.action_space
+                    .from_index(action_index)
+                    .expect("invalid action index")
+            })
+            .collect()
+    }
 }
 
 impl<T, OS, AS> BatchUpdate<OS::Element, AS::Element> for FiniteSpaceAgent<T, OS, AS>
@@ -135,6 +181,22 @@ where
             }),
         )
     }
+
+    fn write_experience_with_log_probs<I>(
+        &mut self,
+        steps: I,
+        log_probs: Vec<f64>,
+    ) -> Result<(), WriteExperienceError>
+    where
+        I: IntoIterator<Item = PartialStep<OS::Element, AS::Element, F>>,
+    {
+        self.buffer.write_experience_with_log_probs(
+            steps.into_iter().map(|step| {
+                indexed_partial_step(&step, &self.observation_space, &self.action_space)
+            }),
+            log_probs,
+        )
+    }
 }
 
 impl<B, OS, AS, F> WriteExperienceIncremental<OS::Element, AS::Element, F>
@@ -111,6 +111,19 @@ pub trait Actor<O: ?Sized, A> {
     /// The observation, the selected action, and any other internal state may be stored into
     /// `episode_state`.
     fn act(&self, episode_state: &mut Self::EpisodeState, observation: &O, rng: &mut Prng) -> A;
+
+    /// Select an action and report the log probability with which it was selected.
+    ///
+    /// The log probability is `None` if unknown.
+    /// The default implementation calls [`Actor::act`] and reports `None`.
+    fn act_with_log_prob(
+        &self,
+        episode_state: &mut Self::EpisodeState,
+        observation: &O,
+        rng: &mut Prng,
+    ) -> (A, Option<f64>) {
+        (self.act(episode_state, observation, rng), None)
+    }
 }
 /// Implement `Actor<O, A>` for a deref-able wrapper type generic over `T: Actor<O, A> + ?Sized`.
 macro_rules! impl_wrapped_actor {
@@ -132,6 +145,14 @@ macro_rules! impl_wrapped_actor {
             ) -> A {
                 T::act(self, episode_state, observation, rng)
             }
+            fn act_with_log_prob(
+                &self,
+                episode_state: &mut Self::EpisodeState,
+                observation: &O,
+                rng: &mut Prng,
+            ) -> (A, Option<f64>) {
+                T::act_with_log_prob(self, episode_state, observation, rng)
+            }
         }
     };
 }
@@ -139,6 +160,62 @@ impl_wrapped_actor!(&'_ T);
 impl_wrapped_actor!(Box<T>);
 impl_wrapped_actor!(Arc<T>);
 
+/// Take actions in a batch of independent environment episodes.
+///
+/// The default implementation calls [`Actor::act`] on each episode in turn.
+/// Actors backed by neural network models should override [`BatchActor::batch_act`]
+/// to evaluate the whole batch with a single forward pass.
+pub trait BatchActor<O: ?Sized, A>: Actor<O, A> {
+    /// Select an action in response to each of a batch of observations.
+    ///
+    /// `episode_states[i]` is the episode state of the episode that produced `observations[i]`.
+    /// Returns one action per observation, in order.
+    ///
+    /// # Panics
+    /// May panic if `episode_states` and `observations` have different lengths.
+    fn batch_act(
+        &self,
+        episode_states: &mut [&mut Self::EpisodeState],
+        observations: &[&O],
+        rng: &mut Prng,
+    ) -> Vec<A> {
+        assert_eq!(
+            episode_states.len(),
+            observations.len(),
+            "mismatched number of episode states and observations"
+        );
+        episode_states
+            .iter_mut()
+            .zip(observations)
+            .map(|(episode_state, observation)| self.act(episode_state, observation, rng))
+            .collect()
+    }
+}
+
+/// Implement `BatchActor<O, A>` for a deref-able wrapper type generic over
+/// `T: BatchActor<O, A> + ?Sized`.
+macro_rules! impl_wrapped_batch_actor {
+    ($wrapper:ty) => {
+        impl<T, O, A> BatchActor<O, A> for $wrapper
+        where
+            T: BatchActor<O, A> + ?Sized,
+            O: ?Sized,
+        {
+            fn batch_act(
+                &self,
+                episode_states: &mut [&mut Self::EpisodeState],
+                observations: &[&O],
+                rng: &mut Prng,
+            ) -> Vec<A> {
+                T::batch_act(self, episode_states, observations, rng)
+            }
+        }
+    };
+}
+impl_wrapped_batch_actor!(&'_ T);
+impl_wrapped_batch_actor!(Box<T>);
+impl_wrapped_batch_actor!(Arc<T>);
+
 /// Behaviour mode of an actor.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ActorMode {
@@ -1,6 +1,6 @@
 use super::{
-    buffers::NullBuffer, Actor, ActorMode, Agent, BatchUpdate, BuildAgent, BuildAgentError,
-    HistoryDataBound,
+    buffers::NullBuffer, Actor, ActorMode, Agent, BatchActor, BatchUpdate, BuildAgent,
+    BuildAgentError, HistoryDataBound,
 };
 use crate::envs::EnvStructure;
 use crate::feedback::Reward;
@@ -73,6 +73,8 @@ impl<O, AS: SampleSpace + Clone> Actor<O, AS::Element> for RandomAgent<AS> {
     }
 }
 
+impl<O, AS: SampleSpace + Clone> BatchActor<O, AS::Element> for RandomAgent<AS> {}
+
 impl<O, AS: Space> BatchUpdate<O, AS::Element> for RandomAgent<AS> {
     type Feedback = Reward;
     type HistoryBuffer = NullBuffer;
@@ -1,7 +1,7 @@
 //! Tabular agents
 use super::{
-    buffers::VecBuffer, finite::FiniteSpaceAgent, Actor, ActorMode, Agent, BatchUpdate, BuildAgent,
-    BuildAgentError, HistoryDataBound,
+    buffers::VecBuffer, finite::FiniteSpaceAgent, Actor, ActorMode, Agent, BatchActor, BatchUpdate,
+    BuildAgent, BuildAgentError, HistoryDataBound,
 };
 use crate::envs::EnvStructure;
 use crate::feedback::Reward;
@@ -84,6 +84,10 @@ pub type TabularQLearningAgent<OS, AS> = FiniteSpaceAgent<BaseTabularQLearningAg
 /// Base epsilon-greedy tabular Q learning agent.
 ///
 /// Implemented only for index observation and action spaces.
+///
+/// Actors share the action value table with the agent.
+/// The agent may be updated while actors exist, in which case the table is copied on write:
+/// existing actors keep acting with the values from when they were created.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BaseTabularQLearningAgent {
     pub discount_factor: f64,
@@ -172,8 +176,8 @@ impl BaseTabularQLearningAgent {
 
         let value = step.feedback.unwrap() + discounted_next_value;
         let weight = (self.state_action_counts[idx] as f64).recip();
-        let state_action_values = Arc::get_mut(&mut self.state_action_values)
-            .expect("cannot update agent while actors exist");
+        // Copies the table if any actors exist (see the type documentation)
+        let state_action_values = Arc::make_mut(&mut self.state_action_values);
         state_action_values[idx] *= 1.0 - weight;
         state_action_values[idx] += weight * value;
     }
@@ -232,11 +236,13 @@ impl Actor<usize, usize> for BaseTabularQLearningActor {
     }
 }
 
+impl BatchActor<usize, usize> for BaseTabularQLearningActor {}
+
 #[cfg(test)]
 mod tabular_q_learning {
     use super::super::{testing, BuildAgent};
     use super::*;
-    use crate::envs::{DeterministicBandit, Environment};
+    use crate::envs::{DeterministicBandit, Environment, ExplicitTabularMdp};
     use crate::simulation::{self, SimSeed};
     use rand::SeedableRng;
 
@@ -281,4 +287,76 @@ mod tabular_q_learning {
         }
         assert!(eval_action_1_count > 900);
     }
+
+    #[test]
+    #[allow(clippy::float_cmp)] // expecting exact values
+    fn update_with_existing_actor_copies_values() {
+        use crate::agents::buffers::WriteExperience;
+        use crate::envs::Successor;
+        use crate::feedback::Reward;
+        use crate::simulation::PartialStep;
+
+        let mut agent = BaseTabularQLearningAgent::new(1, 2, 0.9, 0.0);
+        let actor = agent.actor(ActorMode::Evaluation);
+
+        let mut buffer = agent.buffer();
+        buffer
+            .write_experience([PartialStep::new(0, 1, Reward(1.0), Successor::Terminate)])
+            .unwrap();
+        agent.batch_update([&mut buffer], &mut ());
+
+        assert_eq!(actor.state_action_values[(0, 1)], 0.0);
+        assert_eq!(agent.state_action_values[(0, 1)], 1.0);
+        assert!(!Arc::ptr_eq(
+            &actor.state_action_values,
+            &agent.state_action_values
+        ));
+        assert_eq!(
+            agent.actor(ActorMode::Evaluation).state_action_values[(0, 1)],
+            1.0
+        );
+    }
+
+    /// Learns the optimal policy of an MDP with known optimal action values.
+    #[test]
+    fn learns_optimal_mdp_policy() {
+        let env: ExplicitTabularMdp = "
+discount: 0.5
+states: low high
+actions: stay switch
+T: stay identity
+T: switch : low : high 1.0
+T: switch : high : low 1.0
+R: stay : low : * : * 1
+R: stay : high : * : * 3
+"
+        .parse()
+        .unwrap();
+        let optimal_values = env.optimal_action_values(1e-12);
+
+        let mut env_rng = Prng::seed_from_u64(230);
+        let mut agent_rng = Prng::seed_from_u64(231);
+        let mut agent = TabularQLearningAgentConfig::new(0.5)
+            .build_agent(&env, &mut agent_rng)
+            .unwrap();
+        simulation::train_serial(
+            &mut agent,
+            &env,
+            1000,
+            &mut env_rng,
+            &mut agent_rng,
+            &mut (),
+        );
+
+        for step in (&env)
+            .run(agent.actor(ActorMode::Evaluation), SimSeed::Root(232), ())
+            .take(100)
+        {
+            let optimal_action = optimal_values
+                .index_axis(Axis(0), step.observation)
+                .argmax()
+                .unwrap();
+            assert_eq!(step.action, optimal_action);
+        }
+    }
 }
@@ -1,5 +1,6 @@
 //! Agent testing utilities
 use crate::agents::{Actor, ActorMode, Agent, BatchUpdate, BuildAgent};
+use crate::envs::testing::ContinuousTargetBandit;
 use crate::envs::{DeterministicBandit, Environment};
 use crate::feedback::Reward;
 use crate::simulation::{self, SimSeed};
@@ -62,3 +63,43 @@ where
         num_eval_steps
     );
 }
+
+/// Check that the agent can be trained to select a continuous action close to a target.
+///
+/// The environment is a [`ContinuousTargetBandit`] with actions in `[-1, 1]` and
+/// reward `-|action - 0.5|`. After training, the mean absolute error of evaluation actions must
+/// be at most `max_error`.
+pub fn train_continuous_target_bandit<TC>(agent_config: &TC, num_periods: usize, max_error: f64)
+where
+    TC: BuildAgent<SingletonSpace, IntervalSpace<f64>, IntervalSpace<Reward>>,
+    TC::Agent: BatchUpdate<(), f64, Feedback = Reward>,
+{
+    let mut env_rng = Prng::seed_from_u64(18);
+    let mut agent_rng = Prng::seed_from_u64(19);
+
+    let env = ContinuousTargetBandit::new(0.5);
+    let mut agent = agent_config
+        .build_agent(&env, &mut agent_rng)
+        .expect("failed to build agent");
+
+    simulation::train_serial(
+        &mut agent,
+        &env,
+        num_periods,
+        &mut env_rng,
+        &mut agent_rng,
+        &mut (),
+    );
+
+    let num_eval_steps = 1000;
+    let total_error: f64 = env
+        .run(agent.actor(ActorMode::Evaluation), SimSeed::Root(44), ())
+        .take(num_eval_steps)
+        .map(|step| (step.action - env.target).abs())
+        .sum();
+    let mean_error = total_error / num_eval_steps as f64;
+    assert!(
+        mean_error <= max_error,
+        "mean error ({mean_error}) > max error ({max_error})"
+    );
+}
@@ -0,0 +1,441 @@
+use super::{CloneBuild, EnvStructure, Environment, Successor};
+use crate::feedback::Reward;
+use crate::logging::StatsLogger;
+use crate::spaces::{Indexed, IndexedTypeSpace, IntervalSpace};
+use crate::Prng;
+use rand::distributions::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Acrobot environment
+///
+/// Consists of two links connected in a chain, hanging down from a fixed pivot.
+/// Torque can only be applied to the joint between the two links.
+/// The goal is to swing the end of the lower link up to a height of at least one link length
+/// above the pivot. Each step has a reward of -1 until the goal is reached.
+/// Episodes only terminate at the goal so the environment should be wrapped with a step limit.
+///
+/// The environment is based on [Sutton (1996)][sutton1996] with the dynamics from
+/// [Sutton & Barto (1998)][sutton_barto].
+/// The default constants are from the [OpenAI Gym Acrobot-v1][acrobot_source] environment.
+///
+/// [sutton1996]: https://papers.nips.cc/paper/1995/hash/8f1d43620bc6bb580df6e80b0dc05c48-Abstract.html
+/// [sutton_barto]: http://incompleteideas.net/book/first/ebook/node110.html
+/// [acrobot_source]: https://github.com/openai/gym/blob/master/gym/envs/classic_control/acrobot.py
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Acrobot {
+    /// Physics configuration
+    pub physics: AcrobotPhysics,
+    /// Discount factor
+    pub discount_factor: f64,
+}
+
+impl CloneBuild for Acrobot {}
+
+impl Acrobot {
+    #[must_use]
+    pub const fn new(physics: AcrobotPhysics, discount_factor: f64) -> Self {
+        Self {
+            physics,
+            discount_factor,
+        }
+    }
+}
+
+impl Default for Acrobot {
+    fn default() -> Self {
+        Self {
+            physics: AcrobotPhysics::default(),
+            discount_factor: 0.99,
+        }
+    }
+}
+
+/// Physical constants for the [`Acrobot`] environment.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AcrobotPhysics {
+    /// Downward acceleration of gravity (m/s^2)
+    pub gravity: f64,
+    /// Length of the upper link (m)
+    pub link_length_1: f64,
+    /// Length of the lower link (m)
+    pub link_length_2: f64,
+    /// Mass of the upper link (kg)
+    pub link_mass_1: f64,
+    /// Mass of the lower link (kg)
+    pub link_mass_2: f64,
+    /// Distance from the pivot to the center of mass of the upper link (m)
+    pub link_com_pos_1: f64,
+    /// Distance from the joint to the center of mass of the lower link (m)
+    pub link_com_pos_2: f64,
+    /// Moment of inertia of each link (kg m^2)
+    pub link_moi: f64,
+    /// Maximum absolute angular velocity of the upper link (radians / s)
+    pub max_vel_1: f64,
+    /// Maximum absolute angular velocity of the lower link (radians / s)
+    pub max_vel_2: f64,
+    /// Magnitude of the torque (N m) applied by actions
+    pub torque: f64,
+    /// Simulation time step (s)
+    pub time_step: f64,
+}
+
+impl Default for AcrobotPhysics {
+    fn default() -> Self {
+        // Defaults from the OpenAI Acrobot-v1 environment
+        Self {
+            gravity: 9.8,
+            link_length_1: 1.0,
+            link_length_2: 1.0,
+            link_mass_1: 1.0,
+            link_mass_2: 1.0,
+            link_com_pos_1: 0.5,
+            link_com_pos_2: 0.5,
+            link_moi: 1.0,
+            max_vel_1: 4.0 * PI,
+            max_vel_2: 9.0 * PI,
+            torque: 1.0,
+            time_step: 0.2,
+        }
+    }
+}
+
+/// [`Acrobot`] action: the torque applied at the joint between the links.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Indexed, Serialize, Deserialize)]
+pub enum Torque {
+    Negative,
+    Zero,
+    Positive,
+}
+
+impl Torque {
+    /// Sign of the torque.
+    const fn sign(self) -> f64 {
+        match self {
+            Self::Negative => -1.0,
+            Self::Zero => 0.0,
+            Self::Positive => 1.0,
+        }
+    }
+}
+
+impl EnvStructure for Acrobot {
+    type ObservationSpace = AcrobotObservationSpace;
+    type ActionSpace = IndexedTypeSpace<Torque>;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        let max_vel_1 = self.physics.max_vel_1;
+        let max_vel_2 = self.physics.max_vel_2;
+        AcrobotObservationSpace {
+            cos_angle_1: IntervalSpace::new(-1.0, 1.0),
+            sin_angle_1: IntervalSpace::new(-1.0, 1.0),
+            cos_angle_2: IntervalSpace::new(-1.0, 1.0),
+            sin_angle_2: IntervalSpace::new(-1.0, 1.0),
+            angular_velocity_1: IntervalSpace::new(-max_vel_1, max_vel_1),
+            angular_velocity_2: IntervalSpace::new(-max_vel_2, max_vel_2),
+        }
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        IndexedTypeSpace::new()
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        IntervalSpace::new(Reward(-1.0), Reward(0.0))
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.discount_factor
+    }
+}
+
+impl Environment for Acrobot {
+    type State = AcrobotState;
+    type Observation = AcrobotObservation;
+    type Action = Torque;
+    type Feedback = Reward;
+
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        let dist = Uniform::new_inclusive(-0.1, 0.1);
+        AcrobotState {
+            angle_1: dist.sample(rng),
+            angle_2: dist.sample(rng),
+            angular_velocity_1: dist.sample(rng),
+            angular_velocity_2: dist.sample(rng),
+        }
+    }
+
+    fn observe(&self, state: &Self::State, _: &mut Prng) -> Self::Observation {
+        let (sin_angle_1, cos_angle_1) = state.angle_1.sin_cos();
+        let (sin_angle_2, cos_angle_2) = state.angle_2.sin_cos();
+        AcrobotObservation {
+            cos_angle_1,
+            sin_angle_1,
+            cos_angle_2,
+            sin_angle_2,
+            angular_velocity_1: state.angular_velocity_1,
+            angular_velocity_2: state.angular_velocity_2,
+        }
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        _: &mut Prng,
+        _: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        let next_state = self
+            .physics
+            .next_state(&state, action.sign() * self.physics.torque);
+        if self.physics.at_goal(&next_state) {
+            (Successor::Terminate, Reward(0.0))
+        } else {
+            (Successor::Continue(next_state), Reward(-1.0))
+        }
+    }
+}
+
+impl AcrobotPhysics {
+    /// Simulate the state for one time step with an applied torque (N m) at the joint.
+    ///
+    /// Integrates the dynamics with a single step of the fourth-order Runge-Kutta method.
+    #[must_use]
+    pub fn next_state(&self, state: &AcrobotState, torque: f64) -> AcrobotState {
+        let y0 = state.to_array();
+        let dt = self.time_step;
+        let k1 = self.derivatives(&y0, torque);
+        let k2 = self.derivatives(&add_scaled(&y0, &k1, dt / 2.0), torque);
+        let k3 = self.derivatives(&add_scaled(&y0, &k2, dt / 2.0), torque);
+        let k4 = self.derivatives(&add_scaled(&y0, &k3, dt), torque);
+        let y: [f64; 4] = array_init::array_init(|i| {
+            y0[i] + dt / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i])
+        });
+        AcrobotState {
+            angle_1: wrap_angle(y[0]),
+            angle_2: wrap_angle(y[1]),
+            angular_velocity_1: y[2].clamp(-self.max_vel_1, self.max_vel_1),
+            angular_velocity_2: y[3].clamp(-self.max_vel_2, self.max_vel_2),
+        }
+    }
+
+    /// Whether the end of the lower link is at least one upper link length above the pivot.
+    #[must_use]
+    pub fn at_goal(&self, state: &AcrobotState) -> bool {
+        let height = -self.link_length_1 * state.angle_1.cos()
+            - self.link_length_2 * (state.angle_1 + state.angle_2).cos();
+        height > self.link_length_1
+    }
+
+    /// Time derivative of `[angle_1, angle_2, angular_velocity_1, angular_velocity_2]`.
+    fn derivatives(&self, y: &[f64; 4], torque: f64) -> [f64; 4] {
+        let [angle_1, angle_2, angular_velocity_1, angular_velocity_2] = *y;
+        let m1 = self.link_mass_1;
+        let m2 = self.link_mass_2;
+        let l1 = self.link_length_1;
+        let lc1 = self.link_com_pos_1;
+        let lc2 = self.link_com_pos_2;
+        let i1 = self.link_moi;
+        let i2 = self.link_moi;
+        let g = self.gravity;
+
+        let (sin_angle_2, cos_angle_2) = angle_2.sin_cos();
+        let d1 =
+            m1 * lc1 * lc1 + m2 * (l1 * l1 + lc2 * lc2 + 2.0 * l1 * lc2 * cos_angle_2) + i1 + i2;
+        let d2 = m2 * (lc2 * lc2 + l1 * lc2 * cos_angle_2) + i2;
+        let phi2 = m2 * lc2 * g * (angle_1 + angle_2 - PI / 2.0).cos();
+        let phi1 = -m2 * l1 * lc2 * angular_velocity_2 * angular_velocity_2 * sin_angle_2
+            - 2.0 * m2 * l1 * lc2 * angular_velocity_2 * angular_velocity_1 * sin_angle_2
+            + (m1 * lc1 + m2 * l1) * g * (angle_1 - PI / 2.0).cos()
+            + phi2;
+        let angular_acceleration_2 = (torque + d2 / d1 * phi1
+            - m2 * l1 * lc2 * angular_velocity_1 * angular_velocity_1 * sin_angle_2
+            - phi2)
+            / (m2 * lc2 * lc2 + i2 - d2 * d2 / d1);
+        let angular_acceleration_1 = -(d2 * angular_acceleration_2 + phi1) / d1;
+        [
+            angular_velocity_1,
+            angular_velocity_2,
+            angular_acceleration_1,
+            angular_acceleration_2,
+        ]
+    }
+}
+
+/// Compute `x + scale * dx` elementwise.
+fn add_scaled(x: &[f64; 4], dx: &[f64; 4], scale: f64) -> [f64; 4] {
+    array_init::array_init(|i| x[i] + scale * dx[i])
+}
+
+/// Wrap an angle into the interval `[-pi, pi]`.
+fn wrap_angle(mut angle: f64) -> f64 {
+    while angle > PI {
+        angle -= 2.0 * PI;
+    }
+    while angle < -PI {
+        angle += 2.0 * PI;
+    }
+    angle
+}
+
+/// State of the [`Acrobot`] environment.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AcrobotState {
+    /// Angle of the upper link from hanging straight down (radians).
+    pub angle_1: f64,
+    /// Angle of the lower link relative to the upper link (radians).
+    pub angle_2: f64,
+    /// Angular velocity of the upper link (radians / s).
+    pub angular_velocity_1: f64,
+    /// Angular velocity of the lower link relative to the upper link (radians / s).
+    pub angular_velocity_2: f64,
+}
+
+impl AcrobotState {
+    const fn to_array(self) -> [f64; 4] {
+        [
+            self.angle_1,
+            self.angle_2,
+            self.angular_velocity_1,
+            self.angular_velocity_2,
+        ]
+    }
+}
+
+/// Observation of the [`Acrobot`] environment.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AcrobotObservation {
+    /// Cosine of the upper link angle.
+    pub cos_angle_1: f64,
+    /// Sine of the upper link angle.
+    pub sin_angle_1: f64,
+    /// Cosine of the lower link angle relative to the upper link.
+    pub cos_angle_2: f64,
+    /// Sine of the lower link angle relative to the upper link.
+    pub sin_angle_2: f64,
+    /// Angular velocity of the upper link (radians / s).
+    pub angular_velocity_1: f64,
+    /// Angular velocity of the lower link relative to the upper link (radians / s).
+    pub angular_velocity_2: f64,
+}
+
+/// [`Acrobot`] observation space.
+#[derive(Debug, Copy, Clone, PartialEq, ProductSpace, Serialize, Deserialize)]
+#[element(AcrobotObservation)]
+pub struct AcrobotObservationSpace {
+    /// Cosine of the upper link angle.
+    pub cos_angle_1: IntervalSpace<f64>,
+    /// Sine of the upper link angle.
+    pub sin_angle_1: IntervalSpace<f64>,
+    /// Cosine of the lower link angle relative to the upper link.
+    pub cos_angle_2: IntervalSpace<f64>,
+    /// Sine of the lower link angle relative to the upper link.
+    pub sin_angle_2: IntervalSpace<f64>,
+    /// Angular velocity of the upper link (radians / s).
+    pub angular_velocity_1: IntervalSpace<f64>,
+    /// Angular velocity of the lower link relative to the upper link (radians / s).
+    pub angular_velocity_2: IntervalSpace<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing;
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn run_default() {
+        testing::check_structured_env(&Acrobot::default(), 1000, 0);
+    }
+
+    /// Compare against a trajectory generated by the OpenAI Gym Acrobot-v1 dynamics.
+    #[test]
+    fn reference_trajectory() {
+        let env = Acrobot::default();
+        let mut rng = Prng::seed_from_u64(1);
+        let mut state = AcrobotState {
+            angle_1: 0.05,
+            angle_2: -0.03,
+            angular_velocity_1: 0.02,
+            angular_velocity_2: 0.01,
+        };
+        let expected = [
+            (
+                Torque::Positive,
+                [
+                    0.033_964_268_077_167_06,
+                    0.014_028_356_136_744_108,
+                    -0.175_464_650_251_537,
+                    0.419_715_602_265_269_1,
+                ],
+            ),
+            (
+                Torque::Negative,
+                [
+                    0.010_256_406_751_987_378,
+                    0.061_304_367_014_462_395,
+                    -0.055_584_146_076_503_76,
+                    0.041_737_111_311_264_4,
+                ],
+            ),
+            (
+                Torque::Zero,
+                [
+                    -0.000_467_511_240_820_284_17,
+                    0.063_639_685_627_065_48,
+                    -0.049_426_499_407_036_02,
+                    -0.020_389_087_054_447_33,
+                ],
+            ),
+            (
+                Torque::Positive,
+                [
+                    -0.021_993_874_432_321_085,
+                    0.087_050_412_281_957_31,
+                    -0.160_560_458_282_586_55,
+                    0.246_770_612_331_387_1,
+                ],
+            ),
+            (
+                Torque::Positive,
+                [
+                    -0.061_416_255_719_784_985,
+                    0.156_855_413_023_111_65,
+                    -0.223_335_288_315_969_22,
+                    0.432_826_078_063_853_54,
+                ],
+            ),
+        ];
+        for (action, expected_state) in expected {
+            let (successor, feedback) = env.step(state, &action, &mut rng, &mut ());
+            state = successor.into_continue().unwrap();
+            for (x, expected_x) in state.to_array().into_iter().zip(expected_state) {
+                assert!((x - expected_x).abs() < 1e-12, "{x} != {expected_x}");
+            }
+            assert_eq!(feedback, Reward(-1.0));
+        }
+    }
+
+    #[test]
+    fn terminates_upright() {
+        let env = Acrobot::default();
+        let mut rng = Prng::seed_from_u64(2);
+        let state = AcrobotState {
+            angle_1: PI - 0.01,
+            angle_2: 0.0,
+            angular_velocity_1: 0.0,
+            angular_velocity_2: 0.0,
+        };
+        let (successor, feedback) = env.step(state, &Torque::Zero, &mut rng, &mut ());
+        assert!(matches!(successor, Successor::Terminate));
+        assert_eq!(feedback, Reward(0.0));
+    }
+
+    #[test]
+    fn wrap_angle_range() {
+        assert!((wrap_angle(3.0 * PI / 2.0) + PI / 2.0).abs() < 1e-12);
+        assert!((wrap_angle(-5.0 * PI / 2.0) + PI / 2.0).abs() < 1e-12);
+        assert!((wrap_angle(0.5) - 0.5).abs() < 1e-12);
+    }
+}
@@ -1,10 +1,15 @@
-use super::{BuildEnv, BuildEnvError, EnvStructure, Environment, Successor};
+use super::wrappers::{Noise, NoisySpace};
+use super::{
+    BuildEnv, BuildEnvError, CloneBuild, EnvDistribution, EnvStructure, Environment, Successor,
+};
 use crate::feedback::Reward;
 use crate::logging::StatsLogger;
 use crate::spaces::{Indexed, IndexedTypeSpace, IntervalSpace};
 use crate::Prng;
 use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
 
 /// Configuration for the [`CartPole`] environment.
 #[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
@@ -71,14 +76,7 @@ impl EnvStructure for CartPole {
     type FeedbackSpace = IntervalSpace<Reward>;
 
     fn observation_space(&self) -> Self::ObservationSpace {
-        let max_pos = self.env.max_pos;
-        let max_angle = self.env.max_angle;
-        CartPolePhysicalStateSpace {
-            cart_position: IntervalSpace::new(-max_pos, max_pos),
-            cart_velocity: IntervalSpace::default(),
-            pole_angle: IntervalSpace::new(-max_angle, max_angle),
-            pole_angular_velocity: IntervalSpace::default(),
-        }
+        self.env.observation_space()
     }
 
     fn action_space(&self) -> Self::ActionSpace {
@@ -136,7 +134,18 @@ impl Environment for CartPole {
             Push::Left => -self.env.action_force,
             Push::Right => self.env.action_force,
         };
-        let next_state = self.phys.next_state(&state, applied_force);
+        self.step_with_force(&state, applied_force)
+    }
+}
+
+impl CartPole {
+    /// Take a step with the given horizontal force (N) applied to the cart.
+    fn step_with_force(
+        &self,
+        state: &CartPoleInternalState,
+        applied_force: f64,
+    ) -> (Successor<CartPoleInternalState>, Reward) {
+        let next_state = self.phys.next_state(state, applied_force);
         let reward = 1.0;
         let terminal = next_state.physical.cart_position.abs() > self.env.max_pos
             || next_state.physical.pole_angle.abs() > self.env.max_angle;
@@ -153,6 +162,188 @@ impl Environment for CartPole {
     }
 }
 
+/// Configuration for the [`ContinuousCartPole`] environment.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContinuousCartPoleConfig {
+    /// Physics configuration
+    pub physics_config: PhysicalConstants,
+    /// Environment environment configuration
+    pub env_config: EnvironmentParams,
+}
+
+impl BuildEnv for ContinuousCartPoleConfig {
+    type Observation = CartPolePhysicalState;
+    type Action = f64;
+    type Feedback = Reward;
+    type ObservationSpace = CartPolePhysicalStateSpace;
+    type ActionSpace = IntervalSpace<f64>;
+    type FeedbackSpace = IntervalSpace<Reward>;
+    type Environment = ContinuousCartPole;
+
+    fn build_env(&self, _: &mut Prng) -> Result<Self::Environment, BuildEnvError> {
+        Ok(ContinuousCartPole::new(
+            self.physics_config,
+            self.env_config,
+        ))
+    }
+}
+
+/// Cart-Pole environment with continuous actions.
+///
+/// The same as [`CartPole`] except that the action is a number between -1 and 1
+/// (values outside this range are clipped) that is multiplied by
+/// [`action_force`](EnvironmentParams::action_force) to get the force applied to the cart.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContinuousCartPole {
+    cartpole: CartPole,
+}
+
+impl ContinuousCartPole {
+    #[must_use]
+    pub fn new(phys: PhysicalConstants, env: EnvironmentParams) -> Self {
+        Self {
+            cartpole: CartPole::new(phys, env),
+        }
+    }
+}
+
+impl EnvStructure for ContinuousCartPole {
+    type ObservationSpace = CartPolePhysicalStateSpace;
+    type ActionSpace = IntervalSpace<f64>;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        self.cartpole.observation_space()
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        IntervalSpace::new(-1.0, 1.0)
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        self.cartpole.feedback_space()
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.cartpole.discount_factor()
+    }
+}
+
+impl Environment for ContinuousCartPole {
+    type State = CartPoleInternalState;
+    type Observation = CartPolePhysicalState;
+    type Action = f64;
+    type Feedback = Reward;
+
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        self.cartpole.initial_state(rng)
+    }
+
+    fn observe(&self, state: &Self::State, rng: &mut Prng) -> Self::Observation {
+        self.cartpole.observe(state, rng)
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        _: &mut Prng,
+        _: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        let applied_force = action.clamp(-1.0, 1.0) * self.cartpole.env.action_force;
+        self.cartpole.step_with_force(&state, applied_force)
+    }
+}
+
+/// Distribution over [`CartPole`] environments with randomized physical constants.
+///
+/// Gravity, the cart and pole masses, the pole length and the action force magnitude are each
+/// sampled independently and uniformly from an inclusive range.
+/// The remaining physical constants and environment parameters are fixed.
+///
+/// Can be used for domain randomization or as the environment distribution of a
+/// [`MetaEnv`](super::MetaEnv).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CartPoleDist {
+    /// Range of the downward force of gravity (m/s^2)
+    pub gravity: RangeInclusive<f64>,
+    /// Range of the mass of the cart (kg)
+    pub mass_cart: RangeInclusive<f64>,
+    /// Range of the mass of the pole (kg)
+    pub mass_pole: RangeInclusive<f64>,
+    /// Range of half the length of the pole (m)
+    pub length_half_pole: RangeInclusive<f64>,
+    /// Range of the magnitude of the force (N) applied by actions.
+    pub action_force: RangeInclusive<f64>,
+    /// Physical constants that are not randomized.
+    ///
+    /// The randomized fields of this structure are ignored.
+    pub physics_config: PhysicalConstants,
+    /// Environment parameters other than the action force.
+    pub env_config: EnvironmentParams,
+}
+
+impl Default for CartPoleDist {
+    fn default() -> Self {
+        Self {
+            gravity: 8.0..=12.0,
+            mass_cart: 0.5..=1.5,
+            mass_pole: 0.05..=0.15,
+            length_half_pole: 0.25..=0.75,
+            action_force: 5.0..=15.0,
+            physics_config: PhysicalConstants::default(),
+            env_config: EnvironmentParams::default(),
+        }
+    }
+}
+
+impl CloneBuild for CartPoleDist {}
+
+impl EnvStructure for CartPoleDist {
+    type ObservationSpace = CartPolePhysicalStateSpace;
+    type ActionSpace = IndexedTypeSpace<Push>;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        self.env_config.observation_space()
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        IndexedTypeSpace::new()
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        IntervalSpace::new(Reward(0.0), Reward(1.0))
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.env_config.discount_factor
+    }
+}
+
+impl EnvDistribution for CartPoleDist {
+    type State = <Self::Environment as Environment>::State;
+    type Observation = <Self::Environment as Environment>::Observation;
+    type Action = <Self::Environment as Environment>::Action;
+    type Feedback = <Self::Environment as Environment>::Feedback;
+    type Environment = CartPole;
+
+    fn sample_environment(&self, rng: &mut Prng) -> Self::Environment {
+        let physics_config = PhysicalConstants {
+            gravity: rng.gen_range(self.gravity.clone()),
+            mass_cart: rng.gen_range(self.mass_cart.clone()),
+            mass_pole: rng.gen_range(self.mass_pole.clone()),
+            length_half_pole: rng.gen_range(self.length_half_pole.clone()),
+            ..self.physics_config
+        };
+        let env_config = EnvironmentParams {
+            action_force: rng.gen_range(self.action_force.clone()),
+            ..self.env_config
+        };
+        CartPole::new(physics_config, env_config)
+    }
+}
+
 /// Physical constants for the [`CartPole`] environment.
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PhysicalConstants {
@@ -203,6 +394,20 @@ pub struct EnvironmentParams {
     pub discount_factor: f64,
 }
 
+impl EnvironmentParams {
+    /// Observation space of a [`CartPole`] environment with these parameters.
+    fn observation_space(&self) -> CartPolePhysicalStateSpace {
+        let max_pos = self.max_pos;
+        let max_angle = self.max_angle;
+        CartPolePhysicalStateSpace {
+            cart_position: IntervalSpace::new(-max_pos, max_pos),
+            cart_velocity: IntervalSpace::default(),
+            pole_angle: IntervalSpace::new(-max_angle, max_angle),
+            pole_angular_velocity: IntervalSpace::default(),
+        }
+    }
+}
+
 impl Default for EnvironmentParams {
     fn default() -> Self {
         // Defaults (except discount factor) from the OpenAI CartPole-v1 environment
@@ -283,6 +488,18 @@ pub struct CartPolePhysicalStateSpace {
     pub pole_angular_velocity: IntervalSpace<f64>,
 }
 
+impl NoisySpace for CartPolePhysicalStateSpace {
+    fn perturb(&self, element: &mut Self::Element, noise: &Noise, rng: &mut Prng) {
+        self.cart_position
+            .perturb(&mut element.cart_position, noise, rng);
+        self.cart_velocity
+            .perturb(&mut element.cart_velocity, noise, rng);
+        self.pole_angle.perturb(&mut element.pole_angle, noise, rng);
+        self.pole_angular_velocity
+            .perturb(&mut element.pole_angular_velocity, noise, rng);
+    }
+}
+
 /// State of the [`CartPole`] environment.
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CartPoleInternalState {
@@ -456,3 +673,126 @@ mod tests {
         testing::check_structured_env(&CartPole::default(), 1000, 0);
     }
 }
+
+#[cfg(test)]
+mod continuous_cartpole {
+    use super::super::testing;
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn run_default() {
+        testing::check_structured_env(&ContinuousCartPole::default(), 1000, 0);
+    }
+
+    #[test]
+    fn build() {
+        let config = ContinuousCartPoleConfig::default();
+        let _env = config.build_env(&mut Prng::seed_from_u64(0)).unwrap();
+    }
+
+    /// Full-force actions match the discrete environment.
+    #[test]
+    fn matches_discrete() {
+        let discrete = CartPole::default();
+        let continuous = ContinuousCartPole::default();
+        let mut rng = Prng::seed_from_u64(1);
+        let mut state = discrete.initial_state(&mut rng);
+        for (push, force) in [
+            (Push::Left, -1.0),
+            (Push::Left, -2.0),
+            (Push::Right, 1.0),
+            (Push::Right, 1.0),
+        ] {
+            let (discrete_successor, discrete_reward) =
+                discrete.step(state, &push, &mut rng, &mut ());
+            let (continuous_successor, continuous_reward) =
+                continuous.step(state, &force, &mut rng, &mut ());
+            assert_eq!(discrete_successor, continuous_successor);
+            assert_eq!(discrete_reward, continuous_reward);
+            state = discrete_successor.into_continue().unwrap();
+        }
+    }
+
+    #[test]
+    fn zero_force_differs() {
+        let discrete = CartPole::default();
+        let continuous = ContinuousCartPole::default();
+        let mut rng = Prng::seed_from_u64(2);
+        let state = discrete.initial_state(&mut rng);
+        let (left, _) = discrete.step(state, &Push::Left, &mut rng, &mut ());
+        let (right, _) = discrete.step(state, &Push::Right, &mut rng, &mut ());
+        let (zero, _) = continuous.step(state, &0.0, &mut rng, &mut ());
+        let velocity =
+            |s: Successor<CartPoleInternalState>| s.into_continue().unwrap().physical.cart_velocity;
+        let zero_velocity = velocity(zero);
+        assert!(velocity(left) < zero_velocity);
+        assert!(zero_velocity < velocity(right));
+    }
+}
+
+#[cfg(test)]
+mod cartpole_dist {
+    use super::super::meta::TrialEpisodeLimit;
+    use super::super::{testing, BuildEnvDist, MetaEnv, Wrap};
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn run_sample() {
+        let env_dist = CartPoleDist::default();
+        let mut rng = Prng::seed_from_u64(1);
+        let env = env_dist.sample_environment(&mut rng);
+        testing::check_structured_env(&env, 1000, 2);
+    }
+
+    #[test]
+    fn subset_env_structure() {
+        testing::check_env_distribution_structure(&CartPoleDist::default(), 5);
+    }
+
+    #[test]
+    fn samples_in_range() {
+        let env_dist = CartPoleDist::default();
+        let mut rng = Prng::seed_from_u64(3);
+        for _ in 0..20 {
+            let env = env_dist.sample_environment(&mut rng);
+            let phys = PhysicalConstants::from(env.phys);
+            assert!(env_dist.gravity.contains(&phys.gravity));
+            assert!(env_dist.mass_cart.contains(&phys.mass_cart));
+            assert!(env_dist.mass_pole.contains(&phys.mass_pole));
+            assert!(env_dist.length_half_pole.contains(&phys.length_half_pole));
+            assert!(env_dist.action_force.contains(&env.env.action_force));
+            #[allow(clippy::float_cmp)] // copied without modification
+            {
+                assert_eq!(phys.time_step, env_dist.physics_config.time_step);
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_ranges() {
+        let env_dist = CartPoleDist {
+            gravity: 9.8..=9.8,
+            mass_cart: 1.0..=1.0,
+            mass_pole: 0.1..=0.1,
+            length_half_pole: 0.5..=0.5,
+            action_force: 10.0..=10.0,
+            ..CartPoleDist::default()
+        };
+        let env = env_dist.sample_environment(&mut Prng::seed_from_u64(4));
+        assert_eq!(env, CartPole::default());
+    }
+
+    #[test]
+    fn build_env_dist() {
+        let env_dist = CartPoleDist::default().build_env_dist();
+        let _env = env_dist.sample_environment(&mut Prng::seed_from_u64(5));
+    }
+
+    #[test]
+    fn run_meta_env() {
+        let env = MetaEnv::new(CartPoleDist::default()).wrap(TrialEpisodeLimit::new(2));
+        testing::check_structured_env(&env, 1000, 6);
+    }
+}
@@ -0,0 +1,327 @@
+//! Gridworld layouts
+use super::GridVec;
+use crate::utils::coord_vector::CoordVector;
+use relearn_derive::Indexed;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Static contents of a gridworld cell.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Indexed)]
+pub enum Tile {
+    /// An open cell.
+    Empty,
+    /// An impassable cell.
+    Wall,
+    /// Entering the cell ends the episode successfully.
+    Goal,
+    /// Entering the cell ends the episode unsuccessfully.
+    Trap,
+}
+
+impl Tile {
+    /// ASCII map character for the tile.
+    #[must_use]
+    pub const fn to_char(self) -> char {
+        match self {
+            Self::Empty => '.',
+            Self::Wall => '#',
+            Self::Goal => 'G',
+            Self::Trap => 'T',
+        }
+    }
+}
+
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+/// A rectangular gridworld layout with a start position.
+///
+/// Can be parsed from an ASCII map with one line per row using the characters
+///
+/// | Char       | Meaning                 |
+/// |------------|-------------------------|
+/// | `.` or ` ` | [`Empty`](Tile::Empty)  |
+/// | `#`        | [`Wall`](Tile::Wall)    |
+/// | `G`        | [`Goal`](Tile::Goal)    |
+/// | `T`        | [`Trap`](Tile::Trap)    |
+/// | `S`        | Empty start cell        |
+///
+/// Leading and trailing blank lines are ignored.
+/// There must be exactly one start cell and all rows must have the same length.
+/// Positions outside of the layout are treated as walls.
+///
+/// The layout is serialized as its ASCII map.
+///
+/// # Example
+/// ```
+/// use relearn::envs::gridworld::GridLayout;
+///
+/// let layout: GridLayout = "#####\n#S.G#\n#.#T#\n#####".parse().unwrap();
+/// assert_eq!(layout.width(), 5);
+/// assert_eq!(layout.height(), 4);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct GridLayout {
+    /// Grid cells in row-major order.
+    tiles: Vec<Tile>,
+    width: usize,
+    start: GridVec,
+}
+
+impl GridLayout {
+    /// Create a layout from a grid of tiles in row-major order.
+    ///
+    /// # Panics
+    /// If the tiles do not form a nonempty rectangle or if `start` is not an empty cell.
+    #[must_use]
+    pub fn new(tiles: Vec<Tile>, width: usize, start: GridVec) -> Self {
+        assert!(width > 0, "layout must be nonempty");
+        assert!(
+            !tiles.is_empty() && tiles.len().is_multiple_of(width),
+            "tiles must form a nonempty rectangle"
+        );
+        let layout = Self {
+            tiles,
+            width,
+            start,
+        };
+        assert_eq!(
+            layout.get(start),
+            Tile::Empty,
+            "start must be an empty cell"
+        );
+        layout
+    }
+
+    /// Grid width (number of columns).
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Grid height (number of rows).
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.tiles.len() / self.width
+    }
+
+    /// Start position as `[row, column]`.
+    #[must_use]
+    pub const fn start(&self) -> GridVec {
+        self.start
+    }
+
+    /// The tile at a position (`[row, column]`). Positions outside the grid are walls.
+    #[must_use]
+    pub fn get(&self, position: GridVec) -> Tile {
+        let CoordVector([i, j]) = position;
+        if j < self.width {
+            self.tiles
+                .get(i * self.width + j)
+                .copied()
+                .unwrap_or(Tile::Wall)
+        } else {
+            Tile::Wall
+        }
+    }
+
+    /// Iterator over the positions of all tiles of the given type.
+    pub fn positions(&self, tile: Tile) -> impl Iterator<Item = GridVec> + '_ {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(move |(_, &t)| t == tile)
+            .map(|(index, _)| CoordVector([index / self.width, index % self.width]))
+    }
+}
+
+impl fmt::Display for GridLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, row) in self.tiles.chunks(self.width).enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                if CoordVector([i, j]) == self.start {
+                    write!(f, "S")?;
+                } else {
+                    write!(f, "{tile}")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error parsing a [`GridLayout`] from an ASCII map.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Error)]
+pub enum ParseLayoutError {
+    #[error("layout is empty")]
+    Empty,
+    #[error("row {row} has length {length} but expected {expected}")]
+    RaggedRow {
+        row: usize,
+        length: usize,
+        expected: usize,
+    },
+    #[error("invalid character {0:?}")]
+    InvalidChar(char),
+    #[error("expected exactly one start cell but found {0}")]
+    StartCount(usize),
+}
+
+impl FromStr for GridLayout {
+    type Err = ParseLayoutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s
+            .lines()
+            .skip_while(|line| line.trim().is_empty())
+            .collect();
+        let num_rows = lines.len()
+            - lines
+                .iter()
+                .rev()
+                .take_while(|l| l.trim().is_empty())
+                .count();
+        let lines = &lines[..num_rows];
+
+        let width = lines
+            .first()
+            .ok_or(ParseLayoutError::Empty)?
+            .chars()
+            .count();
+        if width == 0 {
+            return Err(ParseLayoutError::Empty);
+        }
+        let mut tiles = Vec::with_capacity(width * lines.len());
+        let mut starts = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let length = line.chars().count();
+            if length != width {
+                return Err(ParseLayoutError::RaggedRow {
+                    row: i,
+                    length,
+                    expected: width,
+                });
+            }
+            for (j, c) in line.chars().enumerate() {
+                let tile = match c {
+                    '.' | ' ' => Tile::Empty,
+                    '#' => Tile::Wall,
+                    'G' => Tile::Goal,
+                    'T' => Tile::Trap,
+                    'S' => {
+                        starts.push(CoordVector([i, j]));
+                        Tile::Empty
+                    }
+                    _ => return Err(ParseLayoutError::InvalidChar(c)),
+                };
+                tiles.push(tile);
+            }
+        }
+        if starts.len() != 1 {
+            return Err(ParseLayoutError::StartCount(starts.len()));
+        }
+        Ok(Self {
+            tiles,
+            width,
+            start: starts[0],
+        })
+    }
+}
+
+impl TryFrom<String> for GridLayout {
+    type Error = ParseLayoutError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<GridLayout> for String {
+    fn from(layout: GridLayout) -> Self {
+        layout.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = "
+#####
+#S.G#
+#.#T#
+#####
+";
+
+    #[test]
+    fn parse() {
+        let layout: GridLayout = MAP.parse().unwrap();
+        assert_eq!(layout.width(), 5);
+        assert_eq!(layout.height(), 4);
+        assert_eq!(layout.start(), CoordVector([1, 1]));
+        assert_eq!(layout.get(CoordVector([0, 0])), Tile::Wall);
+        assert_eq!(layout.get(CoordVector([1, 2])), Tile::Empty);
+        assert_eq!(layout.get(CoordVector([1, 3])), Tile::Goal);
+        assert_eq!(layout.get(CoordVector([2, 3])), Tile::Trap);
+    }
+
+    #[test]
+    fn out_of_bounds_is_wall() {
+        let layout: GridLayout = "S.".parse().unwrap();
+        assert_eq!(layout.get(CoordVector([0, 2])), Tile::Wall);
+        assert_eq!(layout.get(CoordVector([1, 0])), Tile::Wall);
+    }
+
+    #[test]
+    fn positions() {
+        let layout: GridLayout = "SG.\nG#T".parse().unwrap();
+        let goals: Vec<_> = layout.positions(Tile::Goal).collect();
+        assert_eq!(goals, vec![CoordVector([0, 1]), CoordVector([1, 0])]);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let layout: GridLayout = MAP.parse().unwrap();
+        assert_eq!(layout.to_string(), MAP.trim_start());
+        assert_eq!(layout.to_string().parse::<GridLayout>().unwrap(), layout);
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let layout: GridLayout = MAP.parse().unwrap();
+        let json = serde_json::to_string(&layout).unwrap();
+        assert_eq!(serde_json::from_str::<GridLayout>(&json).unwrap(), layout);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("\n\n".parse::<GridLayout>(), Err(ParseLayoutError::Empty));
+        assert_eq!(
+            "S..\n..".parse::<GridLayout>(),
+            Err(ParseLayoutError::RaggedRow {
+                row: 1,
+                length: 2,
+                expected: 3
+            })
+        );
+        assert_eq!(
+            "S.x".parse::<GridLayout>(),
+            Err(ParseLayoutError::InvalidChar('x'))
+        );
+        assert_eq!(
+            "...".parse::<GridLayout>(),
+            Err(ParseLayoutError::StartCount(0))
+        );
+        assert_eq!(
+            "S.S".parse::<GridLayout>(),
+            Err(ParseLayoutError::StartCount(2))
+        );
+    }
+}
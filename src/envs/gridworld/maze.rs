@@ -0,0 +1,206 @@
+use super::super::{CloneBuild, EnvDistribution, EnvStructure, Environment};
+use super::{feedback_space, GridLayout, GridWorld, Move, Tile};
+use crate::feedback::Reward;
+use crate::spaces::{ArraySpace, IndexSpace, IndexedTypeSpace, IntervalSpace};
+use crate::utils::coord_vector::CoordVector;
+use crate::Prng;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Random distribution over [`GridWorld`] mazes.
+///
+/// Each maze is a perfect maze (exactly one path between any two open cells) generated by a
+/// randomized depth-first search. The outer border is wall.
+/// The agent starts in the top left open cell and the goal is the bottom right open cell.
+///
+/// # Panics
+/// Sampling an environment panics if `width` or `height` is even or less than 3,
+/// or if both are equal to 3 (in which case the start and goal coincide).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MazeDist {
+    /// Maze width including the outer walls. Must be odd.
+    pub width: usize,
+    /// Maze height including the outer walls. Must be odd.
+    pub height: usize,
+    /// Probability of moving perpendicular to the chosen direction.
+    pub slip_prob: f64,
+    /// Reward for reaching the goal.
+    pub goal_reward: f64,
+    /// Reward for any other step.
+    pub step_reward: f64,
+    /// Discount factor
+    pub discount_factor: f64,
+}
+
+impl Default for MazeDist {
+    fn default() -> Self {
+        Self {
+            width: 9,
+            height: 9,
+            slip_prob: 0.0,
+            goal_reward: 1.0,
+            step_reward: 0.0,
+            discount_factor: 0.95,
+        }
+    }
+}
+
+impl CloneBuild for MazeDist {}
+
+impl EnvStructure for MazeDist {
+    type ObservationSpace = ArraySpace<IndexSpace, 2>;
+    type ActionSpace = IndexedTypeSpace<Move>;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        ArraySpace::new([IndexSpace::new(self.height), IndexSpace::new(self.width)])
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        IndexedTypeSpace::new()
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        // Mazes do not contain traps so use the goal reward in place of the trap reward.
+        feedback_space(self.goal_reward, self.goal_reward, self.step_reward)
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.discount_factor
+    }
+}
+
+impl EnvDistribution for MazeDist {
+    type State = <Self::Environment as Environment>::State;
+    type Observation = <Self::Environment as Environment>::Observation;
+    type Action = <Self::Environment as Environment>::Action;
+    type Feedback = <Self::Environment as Environment>::Feedback;
+    type Environment = GridWorld;
+
+    fn sample_environment(&self, rng: &mut Prng) -> Self::Environment {
+        let layout = sample_maze(self.width, self.height, rng);
+        GridWorld {
+            layout,
+            slip_prob: self.slip_prob,
+            goal_reward: self.goal_reward,
+            trap_reward: self.goal_reward,
+            step_reward: self.step_reward,
+            discount_factor: self.discount_factor,
+        }
+    }
+}
+
+/// Generate a random maze layout with a randomized depth-first search.
+fn sample_maze(width: usize, height: usize, rng: &mut Prng) -> GridLayout {
+    assert!(
+        width >= 3 && width % 2 == 1,
+        "maze width must be odd and at least 3"
+    );
+    assert!(
+        height >= 3 && height % 2 == 1,
+        "maze height must be odd and at least 3"
+    );
+    assert!(width > 3 || height > 3, "maze must have at least two cells");
+
+    // Maze cells are at odd positions; the even positions between them are walls or passages.
+    let num_rows = height / 2;
+    let num_cols = width / 2;
+    let mut tiles = vec![Tile::Wall; width * height];
+    let mut visited = vec![false; num_rows * num_cols];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    tiles[width + 1] = Tile::Empty;
+    while let Some(&(r, c)) = stack.last() {
+        let mut neighbours = Vec::with_capacity(4);
+        if r > 0 {
+            neighbours.push((r - 1, c));
+        }
+        if r + 1 < num_rows {
+            neighbours.push((r + 1, c));
+        }
+        if c > 0 {
+            neighbours.push((r, c - 1));
+        }
+        if c + 1 < num_cols {
+            neighbours.push((r, c + 1));
+        }
+        neighbours.retain(|&(nr, nc)| !visited[nr * num_cols + nc]);
+
+        let Some(&(nr, nc)) = neighbours.choose(rng) else {
+            stack.pop();
+            continue;
+        };
+        visited[nr * num_cols + nc] = true;
+        // Open the passage between the cells and the new cell itself
+        tiles[(r + nr + 1) * width + (c + nc + 1)] = Tile::Empty;
+        tiles[(2 * nr + 1) * width + (2 * nc + 1)] = Tile::Empty;
+        stack.push((nr, nc));
+    }
+    tiles[(height - 2) * width + (width - 2)] = Tile::Goal;
+    GridLayout::new(tiles, width, CoordVector([1, 1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{testing, MetaEnv};
+    use super::*;
+
+    #[test]
+    fn run_sample() {
+        let env_dist = MazeDist::default();
+        let mut rng = Prng::seed_from_u64(0);
+        let env = env_dist.sample_environment(&mut rng);
+        testing::check_structured_env(&env, 1000, 1);
+    }
+
+    #[test]
+    fn run_meta() {
+        let env = MetaEnv::new(MazeDist::default());
+        testing::check_structured_env(&env, 1000, 2);
+    }
+
+    #[test]
+    fn subset_env_structure() {
+        testing::check_env_distribution_structure(&MazeDist::default(), 5);
+    }
+
+    /// Check that every open cell of a sampled maze is reachable from the start.
+    #[test]
+    fn maze_is_connected() {
+        let env_dist = MazeDist {
+            width: 11,
+            height: 7,
+            ..MazeDist::default()
+        };
+        let mut rng = Prng::seed_from_u64(3);
+        for _ in 0..10 {
+            let layout = env_dist.sample_environment(&mut rng).layout;
+            let mut reached = vec![layout.start()];
+            let mut frontier = vec![layout.start()];
+            while let Some(CoordVector([i, j])) = frontier.pop() {
+                for next in [[i - 1, j], [i + 1, j], [i, j - 1], [i, j + 1]] {
+                    let next = CoordVector(next);
+                    if layout.get(next) != Tile::Wall && !reached.contains(&next) {
+                        reached.push(next);
+                        frontier.push(next);
+                    }
+                }
+            }
+            let num_open = layout.positions(Tile::Empty).count() + 1;
+            assert_eq!(reached.len(), num_open);
+            assert!(reached.contains(&CoordVector([5, 9])));
+            // A perfect maze on a 5x3 cell grid has 15 cells and 14 passages
+            assert_eq!(num_open, 15 + 14);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "maze width must be odd")]
+    fn even_width_panics() {
+        let env_dist = MazeDist {
+            width: 8,
+            ..MazeDist::default()
+        };
+        let _ = env_dist.sample_environment(&mut Prng::seed_from_u64(4));
+    }
+}
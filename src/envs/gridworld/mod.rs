@@ -0,0 +1,298 @@
+//! Single-agent gridworld navigation environments.
+mod layout;
+mod maze;
+mod view;
+
+pub use layout::{GridLayout, ParseLayoutError, Tile};
+pub use maze::MazeDist;
+pub use view::{LocalView, LocalViewSpace};
+
+use super::{CloneBuild, EnvStructure, Environment, Successor};
+use crate::feedback::Reward;
+use crate::logging::StatsLogger;
+use crate::spaces::{ArraySpace, IndexSpace, IndexedTypeSpace, IntervalSpace};
+use crate::utils::coord_vector::CoordVector;
+use crate::Prng;
+use rand::prelude::*;
+use relearn_derive::Indexed;
+use serde::{Deserialize, Serialize};
+
+/// Grid position as `[row, column]`.
+pub type GridVec = CoordVector<usize, 2>;
+
+/// Gridworld movement
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Indexed, Serialize, Deserialize)]
+pub enum Move {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Move {
+    /// Apply the move to a position. Moving off the top or left edge has no effect.
+    const fn apply(self, pos: GridVec) -> GridVec {
+        let CoordVector([i, j]) = pos;
+        match self {
+            Self::Up => CoordVector([i.saturating_sub(1), j]),
+            Self::Down => CoordVector([i + 1, j]),
+            Self::Left => CoordVector([i, j.saturating_sub(1)]),
+            Self::Right => CoordVector([i, j + 1]),
+        }
+    }
+
+    /// The two moves perpendicular to this one.
+    const fn perpendicular(self) -> [Self; 2] {
+        match self {
+            Self::Up | Self::Down => [Self::Left, Self::Right],
+            Self::Left | Self::Right => [Self::Up, Self::Down],
+        }
+    }
+}
+
+/// Single-agent gridworld navigation environment.
+///
+/// The agent starts each episode at the layout start position and moves one cell per step.
+/// Moves into walls or off the grid leave the agent in place.
+/// With probability `slip_prob`, the agent slips and instead moves in one of the two directions
+/// perpendicular to the chosen move (selected uniformly at random).
+///
+/// Entering a goal or trap cell ends the episode with `goal_reward` or `trap_reward`, respectively.
+/// All other steps have reward `step_reward`.
+///
+/// The agent observes its position as `[row, column]`.
+/// Use the [`LocalView`] wrapper for a partially observable version in which the agent only sees
+/// a window of cells around it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridWorld {
+    /// Grid layout
+    pub layout: GridLayout,
+    /// Probability of moving perpendicular to the chosen direction.
+    pub slip_prob: f64,
+    /// Reward for entering a goal cell.
+    pub goal_reward: f64,
+    /// Reward for entering a trap cell.
+    pub trap_reward: f64,
+    /// Reward for any other step.
+    pub step_reward: f64,
+    /// Discount factor
+    pub discount_factor: f64,
+}
+
+impl GridWorld {
+    /// Create a deterministic gridworld with the default rewards and discount factor.
+    #[must_use]
+    pub const fn new(layout: GridLayout) -> Self {
+        Self {
+            layout,
+            slip_prob: 0.0,
+            goal_reward: 1.0,
+            trap_reward: -1.0,
+            step_reward: 0.0,
+            discount_factor: 0.95,
+        }
+    }
+
+    /// Set the slip probability.
+    #[must_use]
+    pub fn with_slip_prob(mut self, slip_prob: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&slip_prob),
+            "slip_prob must be in [0, 1]"
+        );
+        self.slip_prob = slip_prob;
+        self
+    }
+}
+
+impl Default for GridWorld {
+    fn default() -> Self {
+        Self::new(
+            "
+######
+#S...#
+#.##T#
+#...G#
+######
+"
+            .parse()
+            .unwrap(),
+        )
+    }
+}
+
+impl CloneBuild for GridWorld {}
+
+impl EnvStructure for GridWorld {
+    type ObservationSpace = ArraySpace<IndexSpace, 2>;
+    type ActionSpace = IndexedTypeSpace<Move>;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        ArraySpace::new([
+            IndexSpace::new(self.layout.height()),
+            IndexSpace::new(self.layout.width()),
+        ])
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        IndexedTypeSpace::new()
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        feedback_space(self.goal_reward, self.trap_reward, self.step_reward)
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.discount_factor
+    }
+}
+
+/// Reward range for the given goal, trap and step rewards.
+fn feedback_space(goal_reward: f64, trap_reward: f64, step_reward: f64) -> IntervalSpace<Reward> {
+    IntervalSpace::new(
+        Reward(goal_reward.min(trap_reward).min(step_reward)),
+        Reward(goal_reward.max(trap_reward).max(step_reward)),
+    )
+}
+
+impl Environment for GridWorld {
+    type State = GridVec;
+    type Observation = [usize; 2];
+    type Action = Move;
+    type Feedback = Reward;
+
+    fn initial_state(&self, _: &mut Prng) -> Self::State {
+        self.layout.start()
+    }
+
+    fn observe(&self, state: &Self::State, _: &mut Prng) -> Self::Observation {
+        state.0
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        rng: &mut Prng,
+        _: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        let action = if self.slip_prob > 0.0 && rng.gen::<f64>() < self.slip_prob {
+            *action.perpendicular().choose(rng).unwrap()
+        } else {
+            *action
+        };
+        let next_state = action.apply(state);
+        match self.layout.get(next_state) {
+            Tile::Empty => (Successor::Continue(next_state), Reward(self.step_reward)),
+            Tile::Wall => (Successor::Continue(state), Reward(self.step_reward)),
+            Tile::Goal => (Successor::Terminate, Reward(self.goal_reward)),
+            Tile::Trap => (Successor::Terminate, Reward(self.trap_reward)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{testing, BuildEnv};
+    use super::*;
+    use crate::agents::buffers::WriteExperience;
+    use crate::agents::{ActorMode, Agent, BatchUpdate, BuildAgent, TabularQLearningAgentConfig};
+    use crate::simulation::{SimSeed, StepsIter};
+
+    #[test]
+    fn run_default() {
+        testing::check_structured_env(&GridWorld::default(), 1000, 0);
+    }
+
+    #[test]
+    fn run_slippery() {
+        testing::check_structured_env(&GridWorld::default().with_slip_prob(0.2), 1000, 1);
+    }
+
+    #[test]
+    fn build() {
+        let _env = GridWorld::default()
+            .build_env(&mut Prng::seed_from_u64(0))
+            .unwrap();
+    }
+
+    #[test]
+    fn walls_block() {
+        let env = GridWorld::default();
+        let mut rng = Prng::seed_from_u64(2);
+        let state = env.initial_state(&mut rng);
+        let (successor, reward) = env.step(state, &Move::Up, &mut rng, &mut ());
+        assert_eq!(successor.into_continue(), Some(state));
+        assert_eq!(reward, Reward(0.0));
+    }
+
+    #[test]
+    fn reach_goal() {
+        let env = GridWorld::default();
+        let mut rng = Prng::seed_from_u64(3);
+        let mut state = env.initial_state(&mut rng);
+        for action in [Move::Down, Move::Down, Move::Right, Move::Right] {
+            let (successor, _) = env.step(state, &action, &mut rng, &mut ());
+            state = successor.into_continue().unwrap();
+        }
+        assert_eq!(env.observe(&state, &mut rng), [3, 3]);
+        let (successor, reward) = env.step(state, &Move::Right, &mut rng, &mut ());
+        assert!(matches!(successor, Successor::Terminate));
+        assert_eq!(reward, Reward(1.0));
+    }
+
+    #[test]
+    fn reach_trap() {
+        let env = GridWorld::default();
+        let mut rng = Prng::seed_from_u64(4);
+        let mut state = env.initial_state(&mut rng);
+        for action in [Move::Right, Move::Right, Move::Right] {
+            let (successor, _) = env.step(state, &action, &mut rng, &mut ());
+            state = successor.into_continue().unwrap();
+        }
+        let (successor, reward) = env.step(state, &Move::Down, &mut rng, &mut ());
+        assert!(matches!(successor, Successor::Terminate));
+        assert_eq!(reward, Reward(-1.0));
+    }
+
+    #[test]
+    fn slip_moves_perpendicular() {
+        let env = GridWorld::new("...\n.S.\n...".parse().unwrap()).with_slip_prob(1.0);
+        let mut rng = Prng::seed_from_u64(5);
+        for _ in 0..20 {
+            let state = env.initial_state(&mut rng);
+            let (successor, _) = env.step(state, &Move::Up, &mut rng, &mut ());
+            let [i, _] = env.observe(&successor.into_continue().unwrap(), &mut rng);
+            assert_eq!(i, 1);
+        }
+    }
+
+    #[test]
+    fn tabular_q_learns() {
+        let env = GridWorld::default();
+        let mut rng_env = Prng::seed_from_u64(6);
+        let mut rng_agent = Prng::seed_from_u64(7);
+        // Q-learning is off-policy so a uniform random behaviour policy explores well
+        let mut agent = TabularQLearningAgentConfig::new(1.0)
+            .build_agent(&env, &mut rng_agent)
+            .unwrap();
+        for _ in 0..100 {
+            let steps = (&env)
+                .run(
+                    agent.actor(ActorMode::Training),
+                    SimSeed::Root(rng_env.gen()),
+                    (),
+                )
+                .take(100);
+            let mut buffer = agent.buffer();
+            buffer.write_experience(steps).unwrap();
+            agent.batch_update([&mut buffer], &mut ());
+        }
+        let summary = (&env)
+            .run(agent.actor(ActorMode::Evaluation), SimSeed::Root(8), ())
+            .take(100)
+            .summarize();
+        assert!(summary.episode_feedback.0.mean().unwrap() > 0.9);
+    }
+}
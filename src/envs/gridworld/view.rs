@@ -0,0 +1,138 @@
+use super::super::{EnvStructure, Environment, Successor, Wrapped};
+use super::{GridVec, GridWorld, Tile};
+use crate::logging::StatsLogger;
+use crate::spaces::{BoxSpace, IndexedTypeSpace, PowerSpace};
+use crate::utils::coord_vector::CoordVector;
+use crate::Prng;
+use serde::{Deserialize, Serialize};
+
+/// Gridworld wrapper that only observes a `VW` by `VH` window of tiles centered on the agent.
+///
+/// This makes the environment partially observable; the agent does not observe its position.
+/// Cells outside of the layout are observed as walls.
+/// The agent itself is not shown in the view but is always at `[VH / 2, VW / 2]`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LocalView<const VW: usize, const VH: usize>;
+
+impl<const VW: usize, const VH: usize> LocalView<VW, VH> {
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        assert!(VW > 0 && VH > 0, "view must be nonempty");
+        Self
+    }
+}
+
+/// Local view observation space of a gridworld.
+pub type LocalViewSpace<const VW: usize, const VH: usize> =
+    BoxSpace<PowerSpace<PowerSpace<IndexedTypeSpace<Tile>, VW>, VH>>;
+
+impl<E: EnvStructure, const VW: usize, const VH: usize> EnvStructure
+    for Wrapped<E, LocalView<VW, VH>>
+{
+    type ObservationSpace = LocalViewSpace<VW, VH>;
+    type ActionSpace = E::ActionSpace;
+    type FeedbackSpace = E::FeedbackSpace;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        LocalViewSpace::default()
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        self.inner.action_space()
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        self.inner.feedback_space()
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.inner.discount_factor()
+    }
+}
+
+impl<const VW: usize, const VH: usize> Environment for Wrapped<GridWorld, LocalView<VW, VH>> {
+    type State = <GridWorld as Environment>::State;
+    type Observation = Box<[[Tile; VW]; VH]>;
+    type Action = <GridWorld as Environment>::Action;
+    type Feedback = <GridWorld as Environment>::Feedback;
+
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        self.inner.initial_state(rng)
+    }
+
+    fn observe(&self, state: &Self::State, _: &mut Prng) -> Self::Observation {
+        Box::new(local_view(&self.inner, *state))
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        rng: &mut Prng,
+        logger: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        self.inner.step(state, action, rng, logger)
+    }
+}
+
+/// The window of tiles centered on `pos`.
+fn local_view<const VW: usize, const VH: usize>(env: &GridWorld, pos: GridVec) -> [[Tile; VW]; VH] {
+    let mut view = [[Tile::Wall; VW]; VH];
+    let CoordVector([pos_i, pos_j]) = pos;
+    for (vi, row) in view.iter_mut().enumerate() {
+        // Rows above the top of the grid are out of bounds
+        let Some(i) = (pos_i + vi).checked_sub(VH / 2) else {
+            continue;
+        };
+        for (vj, tile) in row.iter_mut().enumerate() {
+            if let Some(j) = (pos_j + vj).checked_sub(VW / 2) {
+                *tile = env.layout.get(CoordVector([i, j]));
+            }
+        }
+    }
+    view
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{testing, Wrap};
+    use super::super::Move;
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn run_default() {
+        let env = GridWorld::default().wrap(LocalView::<3, 3>::new());
+        testing::check_structured_env(&env, 1000, 0);
+    }
+
+    #[test]
+    fn run_wide() {
+        let env = GridWorld::default()
+            .with_slip_prob(0.1)
+            .wrap(LocalView::<5, 3>::new());
+        testing::check_structured_env(&env, 1000, 1);
+    }
+
+    #[test]
+    fn view_at_start() {
+        use Tile::{Empty as E, Wall as W};
+        let env = GridWorld::default().wrap(LocalView::<3, 3>::new());
+        let mut rng = Prng::seed_from_u64(2);
+        let state = env.initial_state(&mut rng);
+        let view = env.observe(&state, &mut rng);
+        assert_eq!(*view, [[W, W, W], [W, E, E], [W, E, W]]);
+    }
+
+    #[test]
+    fn view_beyond_edges_is_wall() {
+        use Tile::{Empty as E, Goal as G, Trap as T, Wall as W};
+        let env = GridWorld::new("S.\nGT".parse().unwrap()).wrap(LocalView::<5, 3>::new());
+        let mut rng = Prng::seed_from_u64(3);
+        let state = env.initial_state(&mut rng);
+        let (successor, _) = env.step(state, &Move::Right, &mut rng, &mut ());
+        let view = env.observe(&successor.into_continue().unwrap(), &mut rng);
+        assert_eq!(*view, [[W, W, W, W, W], [W, E, E, W, W], [W, G, T, W, W]]);
+    }
+}
@@ -0,0 +1,700 @@
+//! Text file format for tabular MDPs
+use super::{Categorical, Deterministic, ExplicitTabularMdp, TabularMdp};
+use ndarray::{s, Array2, Array3};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Maximum difference from 1 of the total probability of a distribution.
+const PROB_TOLERANCE: f64 = 1e-6;
+
+/// Tokens that start a new declaration or entry.
+const KEYWORDS: [&str; 10] = [
+    "discount",
+    "values",
+    "states",
+    "actions",
+    "observations",
+    "start",
+    "terminal",
+    "T",
+    "O",
+    "R",
+];
+
+/// Error parsing an [`ExplicitTabularMdp`] from text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
+pub enum ParseMdpError {
+    #[error("unexpected end of input")]
+    UnexpectedEnd,
+    #[error("unexpected token {0:?}")]
+    UnexpectedToken(String),
+    #[error("invalid number {0:?}")]
+    InvalidNumber(String),
+    #[error("unknown state or action {0:?}")]
+    UnknownName(String),
+    #[error("missing {0} declaration")]
+    MissingDeclaration(&'static str),
+    #[error("duplicate {0} declaration")]
+    DuplicateDeclaration(&'static str),
+    #[error("transition probabilities for action {action} in state {state} do not sum to 1")]
+    InvalidTransition { action: usize, state: usize },
+    #[error("start state probabilities do not sum to 1")]
+    InvalidStart,
+    #[error("partially observable MDPs are not supported")]
+    PartiallyObservable,
+}
+
+impl FromStr for ExplicitTabularMdp {
+    type Err = ParseMdpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::new(tokenize(s)).parse()
+    }
+}
+
+impl fmt::Display for ExplicitTabularMdp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (num_states, num_actions) = self.transitions.dim();
+        writeln!(f, "discount: {}", self.discount_factor)?;
+        writeln!(f, "values: reward")?;
+        writeln!(f, "states: {num_states}")?;
+        writeln!(f, "actions: {num_actions}")?;
+        match &self.initial_state {
+            Some(distribution) => {
+                write!(f, "start:")?;
+                for p in distribution.probs() {
+                    write!(f, " {p}")?;
+                }
+                writeln!(f)?;
+            }
+            None => writeln!(f, "start: 0")?,
+        }
+        if !self.terminal_states.is_empty() {
+            write!(f, "terminal:")?;
+            for state in &self.terminal_states {
+                write!(f, " {state}")?;
+            }
+            writeln!(f)?;
+        }
+        for ((state, action), (successors, Deterministic(reward))) in
+            self.transitions.indexed_iter()
+        {
+            writeln!(f)?;
+            writeln!(f, "T: {action} : {state}")?;
+            for (i, p) in successors.probs().iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{p}")?;
+            }
+            writeln!(f)?;
+            writeln!(f, "R: {action} : {state} : * : * {reward}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Split into tokens, removing comments. Colons are separate tokens.
+fn tokenize(s: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    for line in s.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        for word in line.split_whitespace() {
+            let mut rest = word;
+            while let Some(i) = rest.find(':') {
+                if i > 0 {
+                    tokens.push(&rest[..i]);
+                }
+                tokens.push(":");
+                rest = &rest[i + 1..];
+            }
+            if !rest.is_empty() {
+                tokens.push(rest);
+            }
+        }
+    }
+    tokens
+}
+
+/// Declared states or actions. Can be referred to by name or by index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Names<'a> {
+    size: usize,
+    names: Vec<&'a str>,
+}
+
+impl Names<'_> {
+    /// Resolve a token to a list of indices. `*` refers to all indices.
+    fn resolve(&self, token: &str) -> Result<Vec<usize>, ParseMdpError> {
+        if token == "*" {
+            return Ok((0..self.size).collect());
+        }
+        self.names
+            .iter()
+            .position(|&name| name == token)
+            .or_else(|| token.parse().ok().filter(|&i| i < self.size))
+            .map(|i| vec![i])
+            .ok_or_else(|| ParseMdpError::UnknownName(token.into()))
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+    discount: Option<f64>,
+    is_cost: bool,
+    states: Option<Names<'a>>,
+    actions: Option<Names<'a>>,
+    start: Option<Vec<f64>>,
+    terminal: BTreeSet<usize>,
+    /// Transition probabilities indexed by `[action, state, next_state]`.
+    transitions: Option<Array3<f64>>,
+    /// Rewards indexed by `[action, state, next_state]`.
+    rewards: Option<Array3<f64>>,
+}
+
+impl<'a> Parser<'a> {
+    const fn new(tokens: Vec<&'a str>) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            discount: None,
+            is_cost: false,
+            states: None,
+            actions: None,
+            start: None,
+            terminal: BTreeSet::new(),
+            transitions: None,
+            rewards: None,
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next_token(&mut self) -> Result<&'a str, ParseMdpError> {
+        let token = self.peek().ok_or(ParseMdpError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    /// Consume the next token if it is a colon.
+    fn try_colon(&mut self) -> bool {
+        let is_colon = self.peek() == Some(":");
+        if is_colon {
+            self.pos += 1;
+        }
+        is_colon
+    }
+
+    fn colon(&mut self) -> Result<(), ParseMdpError> {
+        match self.next_token()? {
+            ":" => Ok(()),
+            token => Err(ParseMdpError::UnexpectedToken(token.into())),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, ParseMdpError> {
+        let token = self.next_token()?;
+        token
+            .parse()
+            .map_err(|_| ParseMdpError::InvalidNumber(token.into()))
+    }
+
+    fn numbers(&mut self, n: usize) -> Result<Vec<f64>, ParseMdpError> {
+        (0..n).map(|_| self.number()).collect()
+    }
+
+    /// `n` probabilities, or `uniform`.
+    fn probabilities(&mut self, n: usize) -> Result<Vec<f64>, ParseMdpError> {
+        if self.peek() == Some("uniform") {
+            self.pos += 1;
+            #[allow(clippy::cast_precision_loss)]
+            Ok(vec![1.0 / n as f64; n])
+        } else {
+            self.numbers(n)
+        }
+    }
+
+    /// Whether the current token ends a list of names.
+    fn at_list_end(&self) -> bool {
+        !matches!(self.peek(), Some(token) if !KEYWORDS.contains(&token))
+    }
+
+    /// Either a count or a list of names.
+    fn names(&mut self) -> Result<Names<'a>, ParseMdpError> {
+        let mut names = Vec::new();
+        while !self.at_list_end() {
+            names.push(self.next_token()?);
+        }
+        match names[..] {
+            [] => Err(self.peek().map_or(ParseMdpError::UnexpectedEnd, |token| {
+                ParseMdpError::UnexpectedToken(token.into())
+            })),
+            [token] => match token.parse() {
+                Ok(size) => Ok(Names {
+                    size,
+                    names: Vec::new(),
+                }),
+                Err(_) => Ok(Names { size: 1, names }),
+            },
+            _ => Ok(Names {
+                size: names.len(),
+                names,
+            }),
+        }
+    }
+
+    fn state_names(&self) -> Result<&Names<'a>, ParseMdpError> {
+        self.states
+            .as_ref()
+            .ok_or(ParseMdpError::MissingDeclaration("states"))
+    }
+
+    fn num_states(&self) -> Result<usize, ParseMdpError> {
+        Ok(self.state_names()?.size)
+    }
+
+    fn states(&mut self) -> Result<Vec<usize>, ParseMdpError> {
+        let token = self.next_token()?;
+        self.state_names()?.resolve(token)
+    }
+
+    /// States until the end of the list.
+    fn state_list(&mut self) -> Result<Vec<usize>, ParseMdpError> {
+        let mut states = Vec::new();
+        while !self.at_list_end() {
+            states.extend(self.states()?);
+        }
+        Ok(states)
+    }
+
+    fn actions(&mut self) -> Result<Vec<usize>, ParseMdpError> {
+        let token = self.next_token()?;
+        self.actions
+            .as_ref()
+            .ok_or(ParseMdpError::MissingDeclaration("actions"))?
+            .resolve(token)
+    }
+
+    /// Zero-initialized `[action, state, next_state]` table.
+    fn new_table(&self) -> Result<Array3<f64>, ParseMdpError> {
+        let num_states = self.num_states()?;
+        let num_actions = self
+            .actions
+            .as_ref()
+            .ok_or(ParseMdpError::MissingDeclaration("actions"))?
+            .size;
+        Ok(Array3::zeros((num_actions, num_states, num_states)))
+    }
+
+    fn parse(mut self) -> Result<ExplicitTabularMdp, ParseMdpError> {
+        while let Some(token) = self.peek() {
+            self.pos += 1;
+            match token {
+                "discount" => {
+                    self.colon()?;
+                    self.discount = Some(self.number()?);
+                }
+                "values" => {
+                    self.colon()?;
+                    self.is_cost = match self.next_token()? {
+                        "reward" => false,
+                        "cost" => true,
+                        token => return Err(ParseMdpError::UnexpectedToken(token.into())),
+                    };
+                }
+                // Redeclaring would invalidate indices resolved by earlier entries
+                "states" => {
+                    if self.states.is_some() {
+                        return Err(ParseMdpError::DuplicateDeclaration("states"));
+                    }
+                    self.colon()?;
+                    self.states = Some(self.names()?);
+                }
+                "actions" => {
+                    if self.actions.is_some() {
+                        return Err(ParseMdpError::DuplicateDeclaration("actions"));
+                    }
+                    self.colon()?;
+                    self.actions = Some(self.names()?);
+                }
+                "observations" => {
+                    self.colon()?;
+                    if self.names()?.size > 1 {
+                        return Err(ParseMdpError::PartiallyObservable);
+                    }
+                }
+                "start" => self.start()?,
+                "terminal" => {
+                    self.colon()?;
+                    let states = self.state_list()?;
+                    self.terminal.extend(states);
+                }
+                "T" => self.transition()?,
+                "R" => self.reward()?,
+                "O" => return Err(ParseMdpError::PartiallyObservable),
+                token => return Err(ParseMdpError::UnexpectedToken(token.into())),
+            }
+        }
+        self.finish()
+    }
+
+    /// Start state distribution. Stored as unnormalized weights.
+    fn start(&mut self) -> Result<(), ParseMdpError> {
+        let num_states = self.num_states()?;
+        let mut probs = vec![0.0; num_states];
+        match self.next_token()? {
+            ":" => {
+                let num_numbers = self.tokens[self.pos..]
+                    .iter()
+                    .take(num_states)
+                    .take_while(|token| token.parse::<f64>().is_ok())
+                    .count();
+                if self.peek() == Some("uniform") || (num_numbers == num_states && num_states > 1) {
+                    probs = self.probabilities(num_states)?;
+                    if !is_distribution(&probs) {
+                        return Err(ParseMdpError::InvalidStart);
+                    }
+                } else {
+                    for state in self.states()? {
+                        probs[state] = 1.0;
+                    }
+                }
+            }
+            "include" => {
+                self.colon()?;
+                for state in self.state_list()? {
+                    probs[state] = 1.0;
+                }
+            }
+            "exclude" => {
+                self.colon()?;
+                probs = vec![1.0; num_states];
+                for state in self.state_list()? {
+                    probs[state] = 0.0;
+                }
+            }
+            token => return Err(ParseMdpError::UnexpectedToken(token.into())),
+        }
+        self.start = Some(probs);
+        Ok(())
+    }
+
+    fn transition(&mut self) -> Result<(), ParseMdpError> {
+        self.colon()?;
+        let mut table = match self.transitions.take() {
+            Some(table) => table,
+            None => self.new_table()?,
+        };
+        let num_states = self.num_states()?;
+        let actions = self.actions()?;
+        if self.try_colon() {
+            let states = self.states()?;
+            if self.try_colon() {
+                let next_states = self.states()?;
+                let p = self.number()?;
+                for &a in &actions {
+                    for &s in &states {
+                        for &s_ in &next_states {
+                            table[(a, s, s_)] = p;
+                        }
+                    }
+                }
+            } else {
+                let row = self.probabilities(num_states)?;
+                for &a in &actions {
+                    for &s in &states {
+                        table.slice_mut(s![a, s, ..]).assign(&ndarray::aview1(&row));
+                    }
+                }
+            }
+        } else {
+            let matrix = match self.peek() {
+                Some("identity") => {
+                    self.pos += 1;
+                    Array2::eye(num_states)
+                }
+                Some("uniform") => {
+                    self.pos += 1;
+                    #[allow(clippy::cast_precision_loss)]
+                    Array2::from_elem((num_states, num_states), 1.0 / num_states as f64)
+                }
+                _ => Array2::from_shape_vec(
+                    (num_states, num_states),
+                    self.numbers(num_states * num_states)?,
+                )
+                .unwrap(),
+            };
+            for &a in &actions {
+                table.slice_mut(s![a, .., ..]).assign(&matrix);
+            }
+        }
+        self.transitions = Some(table);
+        Ok(())
+    }
+
+    fn reward(&mut self) -> Result<(), ParseMdpError> {
+        self.colon()?;
+        let mut table = match self.rewards.take() {
+            Some(table) => table,
+            None => self.new_table()?,
+        };
+        let num_states = self.num_states()?;
+        let actions = self.actions()?;
+        self.colon()?;
+        let states = self.states()?;
+        if self.try_colon() {
+            let next_states = self.states()?;
+            if self.try_colon() {
+                // There is at most one observation so the observation does not matter.
+                self.next_token()?;
+            }
+            let reward = self.number()?;
+            for &a in &actions {
+                for &s in &states {
+                    for &s_ in &next_states {
+                        table[(a, s, s_)] = reward;
+                    }
+                }
+            }
+        } else {
+            let row = self.numbers(num_states)?;
+            for &a in &actions {
+                for &s in &states {
+                    table.slice_mut(s![a, s, ..]).assign(&ndarray::aview1(&row));
+                }
+            }
+        }
+        self.rewards = Some(table);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<ExplicitTabularMdp, ParseMdpError> {
+        let discount_factor = self
+            .discount
+            .ok_or(ParseMdpError::MissingDeclaration("discount"))?;
+        let zeros = self.new_table()?;
+        let (num_actions, num_states, _) = zeros.dim();
+        let transition_probs = self.transitions.as_ref().unwrap_or(&zeros);
+        let rewards = self.rewards.as_ref().unwrap_or(&zeros);
+        let sign = if self.is_cost { -1.0 } else { 1.0 };
+
+        let mut transitions = Vec::with_capacity(num_states * num_actions);
+        for state in 0..num_states {
+            for action in 0..num_actions {
+                let probs = transition_probs.slice(s![action, state, ..]);
+                let reward = sign * probs.dot(&rewards.slice(s![action, state, ..]));
+                let probs = probs.to_vec();
+                // Transitions from terminal states are never used and may be omitted
+                let successors =
+                    if self.terminal.contains(&state) && probs.iter().all(|&p| p == 0.0) {
+                        Categorical::point(state, num_states)
+                    } else if is_distribution(&probs) {
+                        Categorical::new(probs).unwrap()
+                    } else {
+                        return Err(ParseMdpError::InvalidTransition { action, state });
+                    };
+                transitions.push((successors, Deterministic(reward)));
+            }
+        }
+
+        // The default start distribution is uniform
+        let start = self.start.unwrap_or_else(|| vec![1.0; num_states]);
+        let initial_state = Categorical::new(start).map_err(|_| ParseMdpError::InvalidStart)?;
+
+        Ok(TabularMdp {
+            transitions: Array2::from_shape_vec((num_states, num_actions), transitions).unwrap(),
+            initial_state: Some(initial_state),
+            terminal_states: self.terminal,
+            discount_factor,
+        })
+    }
+}
+
+/// Whether `probs` is a valid probability distribution.
+fn is_distribution(probs: &[f64]) -> bool {
+    probs.iter().all(|p| p.is_finite() && *p >= 0.0)
+        && (probs.iter().sum::<f64>() - 1.0).abs() <= PROB_TOLERANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_STATE: &str = "
+# A two-state MDP
+discount: 0.5
+values: reward
+states: low high
+actions: stay switch
+
+T: stay identity
+T: switch : low : high 1.0
+T: switch : high : low 1.0
+R: stay : low : * : * 1
+R: stay : high : * : * 3
+";
+
+    #[test]
+    fn parse_named() {
+        let mdp: ExplicitTabularMdp = TWO_STATE.parse().unwrap();
+        assert_eq!(mdp.transitions.dim(), (2, 2));
+        assert_eq!(mdp.transitions[(0, 0)].0.probs(), &[1.0, 0.0]);
+        assert_eq!(mdp.transitions[(0, 1)].0.probs(), &[0.0, 1.0]);
+        assert_eq!(mdp.transitions[(1, 1)].0.probs(), &[1.0, 0.0]);
+        assert_eq!(mdp.transitions[(0, 0)].1, Deterministic(1.0));
+        assert_eq!(mdp.transitions[(1, 0)].1, Deterministic(3.0));
+        assert_eq!(mdp.transitions[(1, 1)].1, Deterministic(0.0));
+        // Default start is uniform
+        assert_eq!(mdp.initial_state.unwrap().probs(), &[0.5, 0.5]);
+        assert!(mdp.terminal_states.is_empty());
+        #[allow(clippy::float_cmp)]
+        {
+            assert_eq!(mdp.discount_factor, 0.5);
+        }
+    }
+
+    #[test]
+    fn parse_matrix_forms() {
+        let mdp: ExplicitTabularMdp = "
+discount: 1
+values: cost
+states: 3
+actions: 2
+observations: 1
+start: 0.25 0.25 0.5
+T: 0
+0.5 0.5 0
+0 1 0
+0 0 1
+T: 1 : 2
+uniform
+T: 1 : 0 : 1 1.0
+T: 1 : 1:0 1.0
+R: 0 : 0
+2 4 0
+R: 1 : * : 2 : * 3
+"
+        .parse()
+        .unwrap();
+        assert_eq!(mdp.transitions[(0, 0)].0.probs(), &[0.5, 0.5, 0.0]);
+        assert_eq!(mdp.transitions[(2, 0)].0.probs(), &[0.0, 0.0, 1.0]);
+        assert_eq!(mdp.transitions[(0, 1)].0.probs(), &[0.0, 1.0, 0.0]);
+        assert_eq!(mdp.transitions[(1, 1)].0.probs(), &[1.0, 0.0, 0.0]);
+        let third = 1.0 / 3.0;
+        assert_eq!(mdp.transitions[(2, 1)].0.probs(), &[third, third, third]);
+        // Costs are negated and rewards are averaged over successor states.
+        assert_eq!(mdp.transitions[(0, 0)].1, Deterministic(-3.0));
+        assert_eq!(mdp.transitions[(1, 0)].1, Deterministic(0.0));
+        assert!((mdp.transitions[(2, 1)].1 .0 + 1.0).abs() < 1e-12);
+        assert_eq!(mdp.initial_state.unwrap().probs(), &[0.25, 0.25, 0.5]);
+    }
+
+    #[test]
+    fn parse_start_forms() {
+        let header = "discount: 0.9 states: a b c actions: 1 T: 0 uniform\n";
+        let start_probs = |start: &str| {
+            let mdp: ExplicitTabularMdp = format!("{header}{start}").parse().unwrap();
+            mdp.initial_state.unwrap().probs().to_vec()
+        };
+        assert_eq!(start_probs("start: b"), [0.0, 1.0, 0.0]);
+        assert_eq!(start_probs("start: 2"), [0.0, 0.0, 1.0]);
+        assert_eq!(start_probs("start include: a c"), [0.5, 0.0, 0.5]);
+        assert_eq!(start_probs("start exclude: a"), [0.0, 0.5, 0.5]);
+        let third = 1.0 / 3.0;
+        assert_eq!(start_probs("start: uniform"), [third, third, third]);
+    }
+
+    #[test]
+    fn parse_terminal() {
+        let mdp: ExplicitTabularMdp = "
+discount: 1
+states: begin end
+actions: go
+start: begin
+terminal: end
+T: go : begin : end 1
+R: go : begin : end : * 5
+"
+        .parse()
+        .unwrap();
+        assert_eq!(mdp.terminal_states, [1].into_iter().collect());
+        assert_eq!(mdp.transitions[(0, 0)].1, Deterministic(5.0));
+        // Omitted transitions from the terminal state are filled in
+        assert_eq!(mdp.transitions[(1, 0)].0.probs(), &[0.0, 1.0]);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let mdp: ExplicitTabularMdp = TWO_STATE.parse().unwrap();
+        let text = mdp.to_string();
+        assert_eq!(text.parse::<ExplicitTabularMdp>().unwrap(), mdp);
+    }
+
+    #[test]
+    fn display_round_trip_terminal_no_start() {
+        let mut mdp: ExplicitTabularMdp = TWO_STATE.parse().unwrap();
+        mdp.initial_state = None;
+        mdp.terminal_states.insert(1);
+        let parsed: ExplicitTabularMdp = mdp.to_string().parse().unwrap();
+        assert_eq!(parsed.initial_state.unwrap().probs(), &[1.0, 0.0]);
+        assert_eq!(parsed.terminal_states, mdp.terminal_states);
+        assert_eq!(parsed.transitions, mdp.transitions);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let parse = |s: &str| s.parse::<ExplicitTabularMdp>().map(|_| ());
+        assert_eq!(
+            parse("states: 2 actions: 1 T: 0 identity"),
+            Err(ParseMdpError::MissingDeclaration("discount"))
+        );
+        assert_eq!(
+            parse("discount: 1 T: 0 identity"),
+            Err(ParseMdpError::MissingDeclaration("states"))
+        );
+        assert_eq!(
+            parse("discount: 1 states: 2 actions: 1 start: 1 states: 3 T: 0 identity"),
+            Err(ParseMdpError::DuplicateDeclaration("states"))
+        );
+        assert_eq!(
+            parse("discount: 1 states: 2 actions: 1 actions: 2 T: 0 identity"),
+            Err(ParseMdpError::DuplicateDeclaration("actions"))
+        );
+        assert_eq!(
+            parse("discount: 1 states: 2 actions: 1 T: 0 : 0 : 1 0.5 T: 0 : 1 : 1 1"),
+            Err(ParseMdpError::InvalidTransition {
+                action: 0,
+                state: 0
+            })
+        );
+        assert_eq!(
+            parse("discount: 1 states: a b actions: 1 T: 0 : c : a 1"),
+            Err(ParseMdpError::UnknownName("c".into()))
+        );
+        assert_eq!(
+            parse("discount: x"),
+            Err(ParseMdpError::InvalidNumber("x".into()))
+        );
+        assert_eq!(
+            parse("discount: 1 foo: 2"),
+            Err(ParseMdpError::UnexpectedToken("foo".into()))
+        );
+        assert_eq!(
+            parse("discount: 1 states: 2 actions: 1 T: 0"),
+            Err(ParseMdpError::UnexpectedEnd)
+        );
+        assert_eq!(
+            parse("discount: 1 states: 2 start: 0.2 0.2"),
+            Err(ParseMdpError::InvalidStart)
+        );
+        assert_eq!(
+            parse("discount: 1 states: 2 observations: 2"),
+            Err(ParseMdpError::PartiallyObservable)
+        );
+    }
+}
@@ -0,0 +1,490 @@
+//! Generic Markov Decision Processes
+mod format;
+
+pub use format::ParseMdpError;
+
+use super::{CloneBuild, EnvDistribution, EnvStructure, Environment, Successor};
+use crate::feedback::Reward;
+use crate::logging::StatsLogger;
+use crate::spaces::{IndexSpace, IntervalSpace};
+use crate::Prng;
+use ndarray::{Array2, Axis};
+use rand::distributions::{Distribution, WeightedError};
+use rand::Rng;
+use rand_distr::{Dirichlet, Normal, WeightedAliasIndex};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// An MDP with transition and reward functions stored in lookup tables.
+///
+/// The initial state is sampled from `initial_state`, or is always the state with index 0 if
+/// `initial_state` is `None`.
+/// Entering any of the `terminal_states` ends the episode. If there are no terminal states then
+/// episodes last forever.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TabularMdp<T = WeightedAliasIndex<f32>, D = Normal<f64>> {
+    /// Environment transitions table.
+    ///
+    /// Given state index `s` and action index `a`,
+    /// `transitions[s, a] = (successor_distribution, reward_distribution)` where
+    /// `successor_distribution` is the distribution of the successor state index `s'` and
+    /// `reward_distribution` is the reward distribution for the step.
+    pub transitions: Array2<(T, D)>,
+
+    /// Initial state distribution. Starts in state 0 if `None`.
+    // Not `#[serde(default)]` because that would require `T: Default`
+    #[serde(default = "Option::default")]
+    pub initial_state: Option<T>,
+
+    /// Terminal state indices.
+    #[serde(default)]
+    pub terminal_states: BTreeSet<usize>,
+
+    /// Environment discount factor.
+    pub discount_factor: f64,
+}
+
+impl<T, D> TabularMdp<T, D> {
+    /// Create an MDP that always starts in state 0 and has no terminal states.
+    #[must_use]
+    pub const fn new(transitions: Array2<(T, D)>, discount_factor: f64) -> Self {
+        Self {
+            transitions,
+            initial_state: None,
+            terminal_states: BTreeSet::new(),
+            discount_factor,
+        }
+    }
+}
+
+impl<T: Clone, D: Clone> CloneBuild for TabularMdp<T, D> {}
+
+impl<T, D> EnvStructure for TabularMdp<T, D> {
+    type ObservationSpace = IndexSpace;
+    type ActionSpace = IndexSpace;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        IndexSpace::new(self.transitions.len_of(Axis(0)))
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        IndexSpace::new(self.transitions.len_of(Axis(1)))
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        // TODO: Could get a tighter bound by requiring D: Bounded
+        IntervalSpace::default()
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.discount_factor
+    }
+}
+
+impl<T, D> Environment for TabularMdp<T, D>
+where
+    T: Distribution<usize>,
+    D: Distribution<f64>,
+{
+    type State = usize;
+    type Observation = usize;
+    type Action = usize;
+    type Feedback = Reward;
+
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        self.initial_state
+            .as_ref()
+            .map_or(0, |distribution| distribution.sample(rng))
+    }
+
+    fn observe(&self, state: &Self::State, _: &mut Prng) -> Self::Observation {
+        *state
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        rng: &mut Prng,
+        _: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        let (successor_distribution, reward_distribution) = &self.transitions[(state, *action)];
+        let next_state = successor_distribution.sample(rng);
+        let reward = reward_distribution.sample(rng);
+        let successor = if self.terminal_states.contains(&next_state) {
+            Successor::Terminate
+        } else {
+            Successor::Continue(next_state)
+        };
+        (successor, reward.into())
+    }
+}
+
+/// A tabular MDP with explicit transition probabilities and deterministic rewards.
+///
+/// Can be serialized with serde, or parsed from (with [`FromStr`](std::str::FromStr)) and written
+/// to (with [`Display`](std::fmt::Display)) the text format of Cassandra's
+/// [POMDP file format][pomdp_format] restricted to fully observable MDPs.
+///
+/// * States and actions may be declared as a count or a list of names
+///     and referred to by name, by index, or by `*` for all.
+///     Names must not be keywords of the format (such as `start` or `T`).
+/// * All forms of `T:` and `R:` entries are supported, as are `values: cost`
+///     and `start:`, `start include:` and `start exclude:`. The default start is uniform.
+/// * At most one observation may be declared and `O:` entries are not allowed.
+/// * As an extension, `terminal: <states>` declares terminal states.
+///     Transitions from terminal states may be omitted.
+///
+/// Rewards that depend on the successor state are replaced by their expected value given the
+/// state and action. This preserves the expected return of every policy.
+///
+/// [pomdp_format]: https://www.pomdp.org/code/pomdp-file-spec.html
+///
+/// # Example
+/// ```
+/// use relearn::envs::ExplicitTabularMdp;
+///
+/// let mdp: ExplicitTabularMdp = "
+/// discount: 0.9
+/// values: reward
+/// states: left right
+/// actions: stay move
+/// start: left
+/// T: stay identity
+/// T: move uniform
+/// R: move : * : * : * 1.0
+/// "
+/// .parse()
+/// .unwrap();
+/// assert_eq!(mdp.transitions.dim(), (2, 2));
+/// ```
+pub type ExplicitTabularMdp = TabularMdp<Categorical, Deterministic>;
+
+impl ExplicitTabularMdp {
+    /// Optimal action values `Q*(s, a)` computed by value iteration.
+    ///
+    /// Iterates until no value changes by more than `tolerance`.
+    /// The iteration does not converge if the discount factor is 1 and some policy avoids the
+    /// terminal states forever with nonzero reward.
+    #[must_use]
+    pub fn optimal_action_values(&self, tolerance: f64) -> Array2<f64> {
+        let mut values = Array2::zeros(self.transitions.raw_dim());
+        loop {
+            let state_values: Vec<f64> = values
+                .outer_iter()
+                .enumerate()
+                .map(|(state, action_values)| {
+                    if self.terminal_states.contains(&state) {
+                        0.0
+                    } else {
+                        action_values.fold(f64::NEG_INFINITY, |a: f64, &b| a.max(b))
+                    }
+                })
+                .collect();
+            let new_values = self.transitions.map(|(successors, Deterministic(reward))| {
+                let next_value: f64 = successors
+                    .probs()
+                    .iter()
+                    .zip(&state_values)
+                    .map(|(p, v)| p * v)
+                    .sum();
+                reward + self.discount_factor * next_value
+            });
+            let max_change = (&new_values - &values)
+                .iter()
+                .fold(0.0, |a: f64, b| a.max(b.abs()));
+            values = new_values;
+            if max_change <= tolerance {
+                return values;
+            }
+        }
+    }
+}
+
+/// Categorical distribution over indices `0..n` that stores its probabilities.
+///
+/// Unlike [`WeightedAliasIndex`], the probabilities can be read back with
+/// [`Categorical::probs`] and the distribution is serialized as its list of probabilities.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "Vec<f64>", try_from = "Vec<f64>")]
+pub struct Categorical {
+    probs: Vec<f64>,
+    sampler: WeightedAliasIndex<f64>,
+}
+
+impl Categorical {
+    /// Create a categorical distribution with probabilities proportional to the given weights.
+    ///
+    /// # Errors
+    /// If the weights are empty, contain a negative or non-finite value, or are all zero.
+    pub fn new(weights: Vec<f64>) -> Result<Self, WeightedError> {
+        let sampler = WeightedAliasIndex::new(weights.clone())?;
+        let total: f64 = weights.iter().sum();
+        let probs = weights.into_iter().map(|w| w / total).collect();
+        Ok(Self { probs, sampler })
+    }
+
+    /// Distribution that always produces `index`, with support `0..size`.
+    ///
+    /// # Panics
+    /// If `index >= size`.
+    #[must_use]
+    pub fn point(index: usize, size: usize) -> Self {
+        assert!(index < size, "index out of bounds");
+        let mut weights = vec![0.0; size];
+        weights[index] = 1.0;
+        Self::new(weights).unwrap()
+    }
+
+    /// The probability of each index.
+    #[must_use]
+    pub fn probs(&self) -> &[f64] {
+        &self.probs
+    }
+}
+
+impl PartialEq for Categorical {
+    fn eq(&self, other: &Self) -> bool {
+        self.probs == other.probs
+    }
+}
+
+impl Distribution<usize> for Categorical {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        self.sampler.sample(rng)
+    }
+}
+
+impl TryFrom<Vec<f64>> for Categorical {
+    type Error = WeightedError;
+
+    fn try_from(weights: Vec<f64>) -> Result<Self, Self::Error> {
+        Self::new(weights)
+    }
+}
+
+impl From<Categorical> for Vec<f64> {
+    fn from(distribution: Categorical) -> Self {
+        distribution.probs
+    }
+}
+
+/// Distribution that always produces the same value.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Deterministic(pub f64);
+
+impl Distribution<f64> for Deterministic {
+    fn sample<R: Rng + ?Sized>(&self, _: &mut R) -> f64 {
+        self.0
+    }
+}
+
+/// Random distribution over MDPs with Dirichlet sampled transition probabilities.
+///
+/// * Each state-action pair has a categorical successor state distribution sampled from
+///     a Dirichlet prior.
+/// * Step rewards are sampled from a normal distribution with variance 1 and mean sampled
+///     from a normal prior.
+///
+/// # Reference
+/// This environment appears in the paper
+/// "[RL^2: Fast Reinforcement Learning via Slow Reinforcement Learning][rl2]" by Duan et al.
+///
+/// [rl2]: https://arxiv.org/abs/1611.02779
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DirichletRandomMdps {
+    pub num_states: usize,
+    pub num_actions: usize,
+    pub transition_prior_dirichlet_alpha: f64,
+    pub reward_prior_mean: f64,
+    pub reward_prior_stddev: f64,
+    pub discount_factor: f64,
+}
+
+impl CloneBuild for DirichletRandomMdps {}
+
+impl Default for DirichletRandomMdps {
+    fn default() -> Self {
+        Self {
+            num_states: 10,
+            num_actions: 5,
+            transition_prior_dirichlet_alpha: 1.0,
+            reward_prior_mean: 1.0,
+            reward_prior_stddev: 1.0,
+            discount_factor: 1.0, // Assumes that a step limit will be placed on the episodes
+        }
+    }
+}
+
+impl EnvStructure for DirichletRandomMdps {
+    type ObservationSpace = IndexSpace;
+    type ActionSpace = IndexSpace;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        IndexSpace::new(self.num_states)
+    }
+    fn action_space(&self) -> Self::ActionSpace {
+        IndexSpace::new(self.num_actions)
+    }
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        IntervalSpace::new(Reward(f64::NEG_INFINITY), Reward(f64::INFINITY))
+    }
+    fn discount_factor(&self) -> f64 {
+        self.discount_factor
+    }
+}
+
+impl EnvDistribution for DirichletRandomMdps {
+    type State = <Self::Environment as Environment>::State;
+    type Observation = <Self::Environment as Environment>::Observation;
+    type Action = <Self::Environment as Environment>::Action;
+    type Feedback = <Self::Environment as Environment>::Feedback;
+    type Environment = TabularMdp;
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn sample_environment(&self, rng: &mut Prng) -> Self::Environment {
+        // Sample f32 values to save space since the precision of f64 shouldn't be necessary
+        let dynamics_prior = Dirichlet::new_with_size(
+            self.transition_prior_dirichlet_alpha as f32,
+            self.num_states,
+        )
+        .expect("Invalid Dirichlet distribution");
+        let reward_prior = Normal::new(self.reward_prior_mean, self.reward_prior_stddev)
+            .expect("Invalid Normal distribution");
+        let transitions = Array2::from_shape_simple_fn([self.num_states, self.num_actions], || {
+            (
+                WeightedAliasIndex::new(dynamics_prior.sample(rng)).unwrap(),
+                Normal::new(reward_prior.sample(rng), 1.0).unwrap(),
+            )
+        });
+        TabularMdp::new(transitions, self.discount_factor)
+    }
+}
+
+#[cfg(test)]
+mod tabular_mdp {
+    use super::super::testing;
+    use super::*;
+    use rand::SeedableRng;
+
+    /// Two states with actions "stay" and "switch".
+    ///
+    /// Staying has reward 1 in state 0 and reward 3 in state 1.
+    fn two_state() -> ExplicitTabularMdp {
+        let transitions = Array2::from_shape_vec(
+            (2, 2),
+            vec![
+                (Categorical::point(0, 2), Deterministic(1.0)),
+                (Categorical::point(1, 2), Deterministic(0.0)),
+                (Categorical::point(1, 2), Deterministic(3.0)),
+                (Categorical::point(0, 2), Deterministic(0.0)),
+            ],
+        )
+        .unwrap();
+        TabularMdp::new(transitions, 0.5)
+    }
+
+    #[test]
+    fn run_explicit() {
+        let mut env = two_state();
+        env.initial_state = Some(Categorical::new(vec![1.0, 1.0]).unwrap());
+        testing::check_structured_env(&env, 1000, 0);
+    }
+
+    #[test]
+    fn initial_state_distribution() {
+        let mut env = two_state();
+        let mut rng = Prng::seed_from_u64(1);
+        assert_eq!(env.initial_state(&mut rng), 0);
+        env.initial_state = Some(Categorical::point(1, 2));
+        assert_eq!(env.initial_state(&mut rng), 1);
+    }
+
+    #[test]
+    fn terminal_state_terminates() {
+        let mut env = two_state();
+        env.terminal_states.insert(1);
+        let mut rng = Prng::seed_from_u64(2);
+        let (successor, reward) = env.step(0, &0, &mut rng, &mut ());
+        assert_eq!(successor, Successor::Continue(0));
+        assert_eq!(reward, Reward(1.0));
+        let (successor, _) = env.step(0, &1, &mut rng, &mut ());
+        assert_eq!(successor, Successor::Terminate);
+    }
+
+    #[test]
+    fn optimal_action_values() {
+        let values = two_state().optimal_action_values(1e-12);
+        let expected = ndarray::arr2(&[[2.5, 3.0], [6.0, 1.5]]);
+        assert!(
+            values
+                .iter()
+                .zip(&expected)
+                .all(|(v, e)| (v - e).abs() < 1e-9),
+            "{values}"
+        );
+    }
+
+    #[test]
+    fn optimal_action_values_terminal() {
+        let mut env = two_state();
+        env.terminal_states.insert(1);
+        // Switching ends the episode so staying forever is best: 1 / (1 - 0.5)
+        let values = env.optimal_action_values(1e-12);
+        assert!((values[(0, 0)] - 2.0).abs() < 1e-9);
+        assert!(values[(0, 1)].abs() < 1e-9);
+    }
+
+    #[test]
+    fn serde_json_round_trip() {
+        let mut env = two_state();
+        env.initial_state = Some(Categorical::new(vec![1.0, 3.0]).unwrap());
+        env.terminal_states.insert(1);
+        let json = serde_json::to_string(&env).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ExplicitTabularMdp>(&json).unwrap(),
+            env
+        );
+    }
+
+    #[test]
+    fn serde_cbor_round_trip() {
+        let env = two_state();
+        let bytes = serde_cbor::to_vec(&env).unwrap();
+        assert_eq!(
+            serde_cbor::from_slice::<ExplicitTabularMdp>(&bytes).unwrap(),
+            env
+        );
+    }
+
+    #[test]
+    fn categorical_normalizes() {
+        let distribution = Categorical::new(vec![1.0, 3.0]).unwrap();
+        assert_eq!(distribution.probs(), &[0.25, 0.75]);
+        assert!(Categorical::new(vec![0.0, 0.0]).is_err());
+    }
+}
+
+#[cfg(test)]
+mod dirichlet_random_mdps {
+    use super::super::testing;
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn run_sample() {
+        let env_dist = DirichletRandomMdps::default();
+        let mut rng = Prng::seed_from_u64(168);
+        let env = env_dist.sample_environment(&mut rng);
+        testing::check_structured_env(&env, 1000, 170);
+    }
+
+    #[test]
+    fn subset_env_structure() {
+        let env_dist = DirichletRandomMdps::default();
+        testing::check_env_distribution_structure(&env_dist, 5);
+    }
+}
@@ -1,39 +1,64 @@
 //! Reinforcement learning environments
 #![allow(clippy::use_self)] // false positive with serde derives
+mod acrobot;
 mod bandits;
 mod builders;
 mod cartpole;
 mod chain;
+pub mod gridworld;
 mod mdps;
 mod memory;
 pub mod meta;
+mod mountain_car;
 mod multiagent;
 mod partition;
+mod pendulum;
 #[cfg(test)]
 pub mod testing;
 mod wrappers;
 
+pub use acrobot::{
+    Acrobot, AcrobotObservation, AcrobotObservationSpace, AcrobotPhysics, AcrobotState, Torque,
+};
 pub use bandits::{
     Bandit, BernoulliBandit, DeterministicBandit, OneHotBandits, UniformBernoulliBandits,
 };
 pub use builders::{BuildEnv, BuildEnvDist, BuildEnvError, CloneBuild};
-pub use cartpole::{CartPole, CartPoleConfig};
+pub use cartpole::{
+    CartPole, CartPoleConfig, CartPoleDist, ContinuousCartPole, ContinuousCartPoleConfig,
+};
 pub use chain::Chain;
-pub use mdps::DirichletRandomMdps;
+pub use gridworld::{GridWorld, MazeDist};
+pub use mdps::{
+    Categorical, Deterministic, DirichletRandomMdps, ExplicitTabularMdp, ParseMdpError, TabularMdp,
+};
 pub use memory::MemoryGame;
 pub use meta::MetaEnv;
+pub use mountain_car::{
+    Accelerate, ContinuousMountainCar, MountainCar, MountainCarPhysics, MountainCarState,
+    MountainCarStateSpace,
+};
 pub use multiagent::fruit::{self, FruitGame};
 pub use multiagent::views::{FirstPlayerView, SecondPlayerView};
 pub use partition::PartitionGame;
+pub use pendulum::{
+    Pendulum, PendulumObservation, PendulumObservationSpace, PendulumPhysics, PendulumState,
+};
 pub use wrappers::{
-    LatentStepLimit, StructurePreservingWrapper, VisibleStepLimit, WithLatentStepLimit,
-    WithVisibleStepLimit, Wrap, Wrapped,
+    ActionNoise, ClipReward, FeatureStats, LatentStepLimit, Noise, NoisySpace,
+    NormalizeObservations, NormalizeReward, NormalizedObsSpace, ObservationNoise, RepeatAction,
+    ScaleReward, SignReward, StackObservations, StepLimitObs, StepLimitObsSpace, StepLimited,
+    StickyActions, StructurePreservingWrapper, TimeRemaining, VisibleStepLimit, WithActionNoise,
+    WithClippedReward, WithLatentStepLimit, WithNormalizedObservations, WithNormalizedReward,
+    WithObservationNoise, WithRepeatedActions, WithScaledReward, WithSignReward,
+    WithStackedObservations, WithStickyActions, WithTimeRemaining, WithVisibleStepLimit, Wrap,
+    Wrapped,
 };
 
-use crate::agents::Actor;
+use crate::agents::{Actor, BatchActor};
 use crate::feedback::Reward;
 use crate::logging::StatsLogger;
-use crate::simulation::{SimSeed, Steps};
+use crate::simulation::{SimSeed, Steps, VecSteps};
 use crate::spaces::{IntervalSpace, Space};
 use crate::Prng;
 use serde::{Deserialize, Serialize};
@@ -124,6 +149,22 @@ pub trait Environment {
     {
         Steps::new_seeded(self, actor, seed, logger)
     }
+
+    /// Run `num_envs` copies of this environment in lockstep with the given batch actor.
+    fn run_vec<T, L>(
+        self,
+        actor: T,
+        num_envs: usize,
+        seed: SimSeed,
+        logger: L,
+    ) -> VecSteps<Self, T, Prng, L>
+    where
+        T: BatchActor<Self::Observation, Self::Action>,
+        L: StatsLogger,
+        Self: Sized,
+    {
+        VecSteps::new_seeded(self, actor, num_envs, seed, logger)
+    }
 }
 
 /// Implement `Environment` for a deref-able wrapper type generic over `T: Environment + ?Sized`.
@@ -0,0 +1,477 @@
+use super::{CloneBuild, EnvStructure, Environment, Successor};
+use crate::feedback::Reward;
+use crate::logging::StatsLogger;
+use crate::spaces::{Indexed, IndexedTypeSpace, IntervalSpace};
+use crate::Prng;
+use rand::distributions::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
+
+/// Mountain car environment with discrete actions.
+///
+/// Consists of an under-powered car in a valley between two hills.
+/// The goal is to drive the car to the top of the right hill.
+/// The engine is too weak to drive directly up the hill so the car must build momentum by driving
+/// back and forth. Each step has a reward of -1 until the goal is reached.
+/// Episodes only terminate at the goal so the environment should be wrapped with a step limit.
+///
+/// The environment is based on [Moore (1990)][moore1990] and the default constants are from the
+/// [OpenAI Gym MountainCar-v0][mountain_car_source] environment.
+///
+/// [moore1990]: https://www.cl.cam.ac.uk/techreports/UCAM-CL-TR-209.pdf
+/// [mountain_car_source]: https://github.com/openai/gym/blob/master/gym/envs/classic_control/mountain_car.py
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MountainCar {
+    /// Physics configuration
+    pub physics: MountainCarPhysics,
+    /// Discount factor
+    pub discount_factor: f64,
+}
+
+impl CloneBuild for MountainCar {}
+
+impl MountainCar {
+    #[must_use]
+    pub const fn new(physics: MountainCarPhysics, discount_factor: f64) -> Self {
+        Self {
+            physics,
+            discount_factor,
+        }
+    }
+}
+
+impl Default for MountainCar {
+    fn default() -> Self {
+        Self {
+            physics: MountainCarPhysics::default(),
+            discount_factor: 0.99,
+        }
+    }
+}
+
+/// Mountain car environment with continuous actions.
+///
+/// The same task as [`MountainCar`] except that the action is the engine force as a number
+/// between -1 and 1 (values outside this range are clipped).
+/// The reward is 100 for reaching the goal minus `0.1 * action^2` on each step.
+///
+/// The default constants are from the
+/// [OpenAI Gym MountainCarContinuous-v0][mountain_car_source] environment.
+///
+/// [mountain_car_source]: https://github.com/openai/gym/blob/master/gym/envs/classic_control/continuous_mountain_car.py
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContinuousMountainCar {
+    /// Physics configuration
+    pub physics: MountainCarPhysics,
+    /// Discount factor
+    pub discount_factor: f64,
+}
+
+impl CloneBuild for ContinuousMountainCar {}
+
+impl ContinuousMountainCar {
+    #[must_use]
+    pub const fn new(physics: MountainCarPhysics, discount_factor: f64) -> Self {
+        Self {
+            physics,
+            discount_factor,
+        }
+    }
+}
+
+impl Default for ContinuousMountainCar {
+    fn default() -> Self {
+        // Defaults from the OpenAI MountainCarContinuous-v0 environment
+        Self {
+            physics: MountainCarPhysics {
+                goal_position: 0.45,
+                force: 0.0015,
+                ..MountainCarPhysics::default()
+            },
+            discount_factor: 0.99,
+        }
+    }
+}
+
+/// Physical constants for the [`MountainCar`] and [`ContinuousMountainCar`] environments.
+///
+/// The height of the track at position `x` is proportional to `sin(3 x)`.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MountainCarPhysics {
+    /// Minimum position of the car. The car stops if it hits this left boundary.
+    pub min_position: f64,
+    /// Maximum position of the car.
+    pub max_position: f64,
+    /// Maximum absolute velocity of the car.
+    pub max_speed: f64,
+    /// The episode ends successfully once the car reaches this position...
+    pub goal_position: f64,
+    /// ...with at least this velocity.
+    pub goal_velocity: f64,
+    /// Change in velocity per step from the engine at full power.
+    pub force: f64,
+    /// Scale of the change in velocity per step from gravity.
+    pub gravity: f64,
+}
+
+impl Default for MountainCarPhysics {
+    fn default() -> Self {
+        // Defaults from the OpenAI MountainCar-v0 environment
+        Self {
+            min_position: -1.2,
+            max_position: 0.6,
+            max_speed: 0.07,
+            goal_position: 0.5,
+            goal_velocity: 0.0,
+            force: 0.001,
+            gravity: 0.0025,
+        }
+    }
+}
+
+impl MountainCarPhysics {
+    /// Simulate the state for one time step with an engine power between -1 and 1.
+    #[must_use]
+    pub fn next_state(&self, state: &MountainCarState, power: f64) -> MountainCarState {
+        let velocity = (state.velocity + power * self.force
+            - (3.0 * state.position).cos() * self.gravity)
+            .clamp(-self.max_speed, self.max_speed);
+        let position = (state.position + velocity).clamp(self.min_position, self.max_position);
+        #[allow(clippy::float_cmp)] // clamped to the exact boundary
+        let velocity = if position == self.min_position && velocity < 0.0 {
+            0.0
+        } else {
+            velocity
+        };
+        MountainCarState { position, velocity }
+    }
+
+    /// Whether the car has reached the goal.
+    #[must_use]
+    pub fn at_goal(&self, state: &MountainCarState) -> bool {
+        state.position >= self.goal_position && state.velocity >= self.goal_velocity
+    }
+
+    fn observation_space(&self) -> MountainCarStateSpace {
+        MountainCarStateSpace {
+            position: IntervalSpace::new(self.min_position, self.max_position),
+            velocity: IntervalSpace::new(-self.max_speed, self.max_speed),
+        }
+    }
+
+    fn initial_state(rng: &mut Prng) -> MountainCarState {
+        MountainCarState {
+            position: Uniform::new_inclusive(-0.6, -0.4).sample(rng),
+            velocity: 0.0,
+        }
+    }
+}
+
+/// [`MountainCar`] action.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Indexed, Serialize, Deserialize)]
+pub enum Accelerate {
+    Left,
+    Coast,
+    Right,
+}
+
+impl Accelerate {
+    /// Engine power between -1 and 1.
+    const fn power(self) -> f64 {
+        match self {
+            Self::Left => -1.0,
+            Self::Coast => 0.0,
+            Self::Right => 1.0,
+        }
+    }
+}
+
+impl EnvStructure for MountainCar {
+    type ObservationSpace = MountainCarStateSpace;
+    type ActionSpace = IndexedTypeSpace<Accelerate>;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        self.physics.observation_space()
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        IndexedTypeSpace::new()
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        IntervalSpace::new(Reward(-1.0), Reward(0.0))
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.discount_factor
+    }
+}
+
+impl Environment for MountainCar {
+    type State = MountainCarState;
+    type Observation = MountainCarState;
+    type Action = Accelerate;
+    type Feedback = Reward;
+
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        MountainCarPhysics::initial_state(rng)
+    }
+
+    fn observe(&self, state: &Self::State, _: &mut Prng) -> Self::Observation {
+        *state
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        _: &mut Prng,
+        _: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        let next_state = self.physics.next_state(&state, action.power());
+        let successor = if self.physics.at_goal(&next_state) {
+            Successor::Terminate
+        } else {
+            Successor::Continue(next_state)
+        };
+        (successor, Reward(-1.0))
+    }
+}
+
+impl EnvStructure for ContinuousMountainCar {
+    type ObservationSpace = MountainCarStateSpace;
+    type ActionSpace = IntervalSpace<f64>;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        self.physics.observation_space()
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        IntervalSpace::new(-1.0, 1.0)
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        IntervalSpace::new(Reward(-0.1), Reward(100.0))
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.discount_factor
+    }
+}
+
+impl Environment for ContinuousMountainCar {
+    type State = MountainCarState;
+    type Observation = MountainCarState;
+    type Action = f64;
+    type Feedback = Reward;
+
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        MountainCarPhysics::initial_state(rng)
+    }
+
+    fn observe(&self, state: &Self::State, _: &mut Prng) -> Self::Observation {
+        *state
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        _: &mut Prng,
+        _: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        let power = action.clamp(-1.0, 1.0);
+        let next_state = self.physics.next_state(&state, power);
+        let control_cost = 0.1 * power * power;
+        if self.physics.at_goal(&next_state) {
+            (Successor::Terminate, Reward(100.0 - control_cost))
+        } else {
+            (Successor::Continue(next_state), Reward(-control_cost))
+        }
+    }
+}
+
+/// State of the [`MountainCar`] and [`ContinuousMountainCar`] environments.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MountainCarState {
+    /// Horizontal position of the car.
+    pub position: f64,
+    /// Horizontal velocity of the car.
+    pub velocity: f64,
+}
+
+/// [`MountainCar`] state space.
+#[derive(Debug, Copy, Clone, PartialEq, ProductSpace, Serialize, Deserialize)]
+#[element(MountainCarState)]
+pub struct MountainCarStateSpace {
+    /// Horizontal position of the car.
+    pub position: IntervalSpace<f64>,
+    /// Horizontal velocity of the car.
+    pub velocity: IntervalSpace<f64>,
+}
+
+#[cfg(test)]
+mod discrete {
+    use super::super::testing;
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn run_default() {
+        testing::check_structured_env(&MountainCar::default(), 1000, 0);
+    }
+
+    /// Compare against a trajectory generated by the reference implementation.
+    #[test]
+    fn reference_trajectory() {
+        let env = MountainCar::default();
+        let mut rng = Prng::seed_from_u64(1);
+        let mut state = MountainCarState {
+            position: -0.5,
+            velocity: 0.0,
+        };
+        let expected = [
+            (
+                Accelerate::Right,
+                -0.499_176_843_004_169_26,
+                0.000_823_156_995_830_742_8,
+            ),
+            (
+                Accelerate::Right,
+                -0.497_536_686_679_353_25,
+                0.001_640_156_324_816_024_6,
+            ),
+            (
+                Accelerate::Left,
+                -0.497_091_796_932_347_4,
+                0.000_444_889_747_005_862_73,
+            ),
+            (
+                Accelerate::Coast,
+                -0.496_845_500_067_847_45,
+                0.000_246_296_864_499_942_7,
+            ),
+            (
+                Accelerate::Right,
+                -0.495_799_637_420_493_4,
+                0.001_045_862_647_354_071_2,
+            ),
+        ];
+        for (action, position, velocity) in expected {
+            let (successor, feedback) = env.step(state, &action, &mut rng, &mut ());
+            state = successor.into_continue().unwrap();
+            assert!((state.position - position).abs() < 1e-12);
+            assert!((state.velocity - velocity).abs() < 1e-12);
+            assert_eq!(feedback, Reward(-1.0));
+        }
+    }
+
+    #[test]
+    fn stops_at_left_boundary() {
+        let env = MountainCar::default();
+        let mut rng = Prng::seed_from_u64(2);
+        let state = MountainCarState {
+            position: -1.19,
+            velocity: -0.05,
+        };
+        let (successor, _) = env.step(state, &Accelerate::Left, &mut rng, &mut ());
+        assert_eq!(
+            successor.into_continue().unwrap(),
+            MountainCarState {
+                position: -1.2,
+                velocity: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn terminates_at_goal() {
+        let env = MountainCar::default();
+        let mut rng = Prng::seed_from_u64(3);
+        let state = MountainCarState {
+            position: 0.48,
+            velocity: 0.05,
+        };
+        let (successor, feedback) = env.step(state, &Accelerate::Right, &mut rng, &mut ());
+        assert!(matches!(successor, Successor::Terminate));
+        assert_eq!(feedback, Reward(-1.0));
+    }
+}
+
+#[cfg(test)]
+mod continuous {
+    use super::super::testing;
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn run_default() {
+        testing::check_structured_env(&ContinuousMountainCar::default(), 1000, 0);
+    }
+
+    /// Compare against a trajectory generated by the reference implementation.
+    ///
+    /// Unlike the reference implementation, the control cost uses the clipped action.
+    #[test]
+    fn reference_trajectory() {
+        let env = ContinuousMountainCar::default();
+        let mut rng = Prng::seed_from_u64(1);
+        let mut state = MountainCarState {
+            position: -0.5,
+            velocity: 0.0,
+        };
+        let expected = [
+            (
+                1.0,
+                -0.498_676_843_004_169_26,
+                0.001_323_156_995_830_742_8,
+                -0.1,
+            ),
+            (
+                0.5,
+                -0.496_790_426_411_806_8,
+                0.001_886_416_592_362_428_3,
+                -0.025,
+            ),
+            (
+                -2.0,
+                -0.496_604_855_756_619_5,
+                0.000_185_570_655_187_304_29,
+                -0.1,
+            ),
+            (
+                0.0,
+                -0.496_621_518_288_604_07,
+                -0.000_016_662_531_984_560_08,
+                0.0,
+            ),
+            (
+                0.25,
+                -0.496_465_289_448_070_3,
+                0.000_156_228_840_533_816_06,
+                -0.006_25,
+            ),
+        ];
+        for (action, position, velocity, reward) in expected {
+            let (successor, feedback) = env.step(state, &action, &mut rng, &mut ());
+            state = successor.into_continue().unwrap();
+            assert!((state.position - position).abs() < 1e-12);
+            assert!((state.velocity - velocity).abs() < 1e-12);
+            assert!((feedback.unwrap() - reward).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn goal_reward() {
+        let env = ContinuousMountainCar::default();
+        let mut rng = Prng::seed_from_u64(3);
+        let state = MountainCarState {
+            position: 0.44,
+            velocity: 0.05,
+        };
+        let (successor, feedback) = env.step(state, &1.0, &mut rng, &mut ());
+        assert!(matches!(successor, Successor::Terminate));
+        assert!((feedback.unwrap() - 99.9).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,279 @@
+use super::{CloneBuild, EnvStructure, Environment, Successor};
+use crate::feedback::Reward;
+use crate::logging::StatsLogger;
+use crate::spaces::IntervalSpace;
+use crate::Prng;
+use rand::distributions::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Inverted pendulum swing-up environment.
+///
+/// Consists of a pendulum attached at one end to a fixed pivot.
+/// The goal is to swing the pendulum upright and keep it balanced by applying a torque to the
+/// pivot. The torque is too weak to lift the pendulum directly so it must be swung up.
+/// Each step incurs a cost for the distance from upright, the angular velocity, and the applied
+/// torque. Episodes never terminate so the environment should be wrapped with a step limit.
+///
+/// The dynamics and default constants are based on the [OpenAI Gym Pendulum-v1][pendulum_source]
+/// environment.
+///
+/// [pendulum_source]: https://github.com/openai/gym/blob/master/gym/envs/classic_control/pendulum.py
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pendulum {
+    /// Physics configuration
+    pub physics: PendulumPhysics,
+    /// Discount factor
+    pub discount_factor: f64,
+}
+
+impl CloneBuild for Pendulum {}
+
+impl Pendulum {
+    #[must_use]
+    pub const fn new(physics: PendulumPhysics, discount_factor: f64) -> Self {
+        Self {
+            physics,
+            discount_factor,
+        }
+    }
+}
+
+/// Physical constants for the [`Pendulum`] environment.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendulumPhysics {
+    /// Downward acceleration of gravity (m/s^2)
+    pub gravity: f64,
+    /// Mass of the pendulum (kg)
+    pub mass: f64,
+    /// Length of the pendulum (m)
+    pub length: f64,
+    /// Maximum absolute angular velocity (radians / s)
+    pub max_speed: f64,
+    /// Maximum absolute torque (N m) applied by actions
+    pub max_torque: f64,
+    /// Simulation time step (s)
+    pub time_step: f64,
+}
+
+impl Default for PendulumPhysics {
+    fn default() -> Self {
+        // Defaults from the OpenAI Pendulum-v1 environment
+        Self {
+            gravity: 10.0,
+            mass: 1.0,
+            length: 1.0,
+            max_speed: 8.0,
+            max_torque: 2.0,
+            time_step: 0.05,
+        }
+    }
+}
+
+impl Default for Pendulum {
+    fn default() -> Self {
+        Self {
+            physics: PendulumPhysics::default(),
+            discount_factor: 0.99,
+        }
+    }
+}
+
+impl EnvStructure for Pendulum {
+    type ObservationSpace = PendulumObservationSpace;
+    type ActionSpace = IntervalSpace<f64>;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        let max_speed = self.physics.max_speed;
+        PendulumObservationSpace {
+            cos_angle: IntervalSpace::new(-1.0, 1.0),
+            sin_angle: IntervalSpace::new(-1.0, 1.0),
+            angular_velocity: IntervalSpace::new(-max_speed, max_speed),
+        }
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        let max_torque = self.physics.max_torque;
+        IntervalSpace::new(-max_torque, max_torque)
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        let max_cost = cost(PI, self.physics.max_speed, self.physics.max_torque);
+        IntervalSpace::new(Reward(-max_cost), Reward(0.0))
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.discount_factor
+    }
+}
+
+impl Environment for Pendulum {
+    type State = PendulumState;
+    type Observation = PendulumObservation;
+    type Action = f64;
+    type Feedback = Reward;
+
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        PendulumState {
+            angle: Uniform::new_inclusive(-PI, PI).sample(rng),
+            angular_velocity: Uniform::new_inclusive(-1.0, 1.0).sample(rng),
+        }
+    }
+
+    fn observe(&self, state: &Self::State, _: &mut Prng) -> Self::Observation {
+        let (sin_angle, cos_angle) = state.angle.sin_cos();
+        PendulumObservation {
+            cos_angle,
+            sin_angle,
+            angular_velocity: state.angular_velocity,
+        }
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        _: &mut Prng,
+        _: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        let torque = action.clamp(-self.physics.max_torque, self.physics.max_torque);
+        let cost = cost(state.angle, state.angular_velocity, torque);
+        let next_state = self.physics.next_state(&state, torque);
+        (Successor::Continue(next_state), Reward(-cost))
+    }
+}
+
+impl PendulumPhysics {
+    /// Simulate the state for one time step with an applied torque (in N m).
+    #[must_use]
+    pub fn next_state(&self, state: &PendulumState, torque: f64) -> PendulumState {
+        let angular_acceleration = 3.0 * self.gravity / (2.0 * self.length) * state.angle.sin()
+            + 3.0 / (self.mass * self.length * self.length) * torque;
+        let angular_velocity = (state.angular_velocity + angular_acceleration * self.time_step)
+            .clamp(-self.max_speed, self.max_speed);
+        let angle = state.angle + angular_velocity * self.time_step;
+        PendulumState {
+            angle,
+            angular_velocity,
+        }
+    }
+}
+
+/// Cost of a state and action. The negative of the step reward.
+fn cost(angle: f64, angular_velocity: f64, torque: f64) -> f64 {
+    let normalized_angle = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    normalized_angle * normalized_angle
+        + 0.1 * angular_velocity * angular_velocity
+        + 0.001 * torque * torque
+}
+
+/// State of the [`Pendulum`] environment.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendulumState {
+    /// Angle of the pendulum from upright (radians). Not normalized.
+    pub angle: f64,
+    /// Angular velocity of the pendulum (radians / s).
+    pub angular_velocity: f64,
+}
+
+/// Observation of the [`Pendulum`] environment.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendulumObservation {
+    /// Cosine of the pendulum angle from upright.
+    pub cos_angle: f64,
+    /// Sine of the pendulum angle from upright.
+    pub sin_angle: f64,
+    /// Angular velocity of the pendulum (radians / s).
+    pub angular_velocity: f64,
+}
+
+/// [`Pendulum`] observation space.
+#[derive(Debug, Copy, Clone, PartialEq, ProductSpace, Serialize, Deserialize)]
+#[element(PendulumObservation)]
+pub struct PendulumObservationSpace {
+    /// Cosine of the pendulum angle from upright.
+    pub cos_angle: IntervalSpace<f64>,
+    /// Sine of the pendulum angle from upright.
+    pub sin_angle: IntervalSpace<f64>,
+    /// Angular velocity of the pendulum (radians / s).
+    pub angular_velocity: IntervalSpace<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing;
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn run_default() {
+        testing::check_structured_env(&Pendulum::default(), 1000, 0);
+    }
+
+    /// Compare against a trajectory generated by the reference implementation.
+    #[test]
+    fn reference_trajectory() {
+        let env = Pendulum::default();
+        let mut rng = Prng::seed_from_u64(1);
+        let mut state = PendulumState {
+            angle: 1.0,
+            angular_velocity: -0.5,
+        };
+        let expected = [
+            (
+                0.5,
+                1.010_305_161_930_296,
+                0.206_103_238_605_922_4,
+                -1.025_25,
+            ),
+            (
+                2.0,
+                1.067_372_602_941_708_8,
+                1.141_348_820_228_256_2,
+                -1.028_964_374_719_385_7,
+            ),
+            (
+                -3.0,
+                1.142_287_643_990_203_9,
+                1.498_300_820_969_902_2,
+                -1.273_551_986_454_201_8,
+            ),
+            (
+                0.0,
+                1.251_312_175_985_886_7,
+                2.180_490_639_913_658_3,
+                -1.529_311_596_624_599_1,
+            ),
+            (
+                1.5,
+                1.407_189_116_507_260_8,
+                3.117_538_810_427_481_3,
+                -2.043_486_104_845_642_5,
+            ),
+        ];
+        for (action, angle, angular_velocity, reward) in expected {
+            let (successor, feedback) = env.step(state, &action, &mut rng, &mut ());
+            state = successor.into_continue().unwrap();
+            assert!((state.angle - angle).abs() < 1e-12);
+            assert!((state.angular_velocity - angular_velocity).abs() < 1e-12);
+            assert!((feedback.unwrap() - reward).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn speed_clamped() {
+        let env = Pendulum::default();
+        let mut rng = Prng::seed_from_u64(2);
+        let state = PendulumState {
+            angle: 0.5 * PI,
+            angular_velocity: 7.9,
+        };
+        let (successor, _) = env.step(state, &2.0, &mut rng, &mut ());
+        let state = successor.into_continue().unwrap();
+        #[allow(clippy::float_cmp)] // clamped to exact value
+        {
+            assert_eq!(state.angular_velocity, 8.0);
+        }
+    }
+}
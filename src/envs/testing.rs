@@ -5,6 +5,7 @@ use super::{
 };
 use crate::agents::{ActorMode, Agent, RandomAgent};
 use crate::feedback::Reward;
+use crate::logging::StatsLogger;
 use crate::simulation::SimSeed;
 use crate::spaces::{IndexSpace, IntervalSpace, SampleSpace, SingletonSpace, Space, SubsetOrd};
 use crate::Prng;
@@ -159,3 +160,72 @@ impl EnvDistribution for RoundRobinDeterministicBandits {
         DeterministicBandit::from_values(&values)
     }
 }
+
+/// Single-step environment with a continuous action in `[-1, 1]` and reward `-|action - target|`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ContinuousTargetBandit {
+    pub target: f64,
+}
+
+impl CloneBuild for ContinuousTargetBandit {}
+
+impl ContinuousTargetBandit {
+    #[must_use]
+    pub fn new(target: f64) -> Self {
+        assert!((-1.0..=1.0).contains(&target), "target must be in [-1, 1]");
+        Self { target }
+    }
+}
+
+impl EnvStructure for ContinuousTargetBandit {
+    type ObservationSpace = SingletonSpace;
+    type ActionSpace = IntervalSpace<f64>;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        SingletonSpace::new()
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        IntervalSpace::new(-1.0, 1.0)
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        IntervalSpace::new(Reward(-2.0), Reward(0.0))
+    }
+
+    fn discount_factor(&self) -> f64 {
+        1.0
+    }
+}
+
+impl Environment for ContinuousTargetBandit {
+    type State = ();
+    type Observation = ();
+    type Action = f64;
+    type Feedback = Reward;
+
+    fn initial_state(&self, _: &mut Prng) -> Self::State {}
+
+    fn observe(&self, _state: &Self::State, _: &mut Prng) -> Self::Observation {}
+
+    fn step(
+        &self,
+        _state: Self::State,
+        action: &Self::Action,
+        _: &mut Prng,
+        _: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        (Successor::Terminate, Reward(-(action - self.target).abs()))
+    }
+}
+
+#[cfg(test)]
+mod continuous_target_bandit {
+    use super::*;
+
+    #[test]
+    fn run_default() {
+        check_structured_env(&ContinuousTargetBandit::new(0.5), 100, 31);
+    }
+}
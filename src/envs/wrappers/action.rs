@@ -0,0 +1,364 @@
+use super::super::{EnvStructure, Environment, Successor};
+use super::{StructurePreservingWrapper, Wrapped};
+use crate::feedback::Reward;
+use crate::logging::StatsLogger;
+use crate::spaces::IntervalSpace;
+use crate::Prng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU64;
+
+/// Environment wrapper that repeats each action for a fixed number of inner steps.
+///
+/// The reward of a wrapped step is the (undiscounted) sum of the inner step rewards.
+/// The repetition stops early if the inner episode terminates or is interrupted.
+/// The discount factor is that of the inner environment raised to the number of repeats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RepeatAction {
+    /// Number of inner steps taken for each action.
+    pub repeats: NonZeroU64,
+}
+
+impl RepeatAction {
+    /// # Panics
+    /// If `repeats` is zero.
+    #[must_use]
+    #[inline]
+    pub const fn new(repeats: u64) -> Self {
+        match NonZeroU64::new(repeats) {
+            Some(repeats) => Self { repeats },
+            None => panic!("number of repeats must be positive"),
+        }
+    }
+
+    /// Sum `repeats` copies of an interval bound, keeping unbounded ends unbounded.
+    #[allow(clippy::cast_precision_loss)]
+    fn map_bound(self, bound: f64) -> f64 {
+        (bound * self.repeats.get() as f64).clamp(f64::MIN, f64::MAX)
+    }
+}
+
+impl Default for RepeatAction {
+    #[inline]
+    fn default() -> Self {
+        Self::new(4)
+    }
+}
+
+/// Wrap an environment to repeat each action several times.
+pub type WithRepeatedActions<E> = Wrapped<E, RepeatAction>;
+
+impl<E> EnvStructure for Wrapped<E, RepeatAction>
+where
+    E: EnvStructure<FeedbackSpace = IntervalSpace<Reward>>,
+{
+    type ObservationSpace = E::ObservationSpace;
+    type ActionSpace = E::ActionSpace;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    #[inline]
+    fn observation_space(&self) -> Self::ObservationSpace {
+        self.inner.observation_space()
+    }
+    #[inline]
+    fn action_space(&self) -> Self::ActionSpace {
+        self.inner.action_space()
+    }
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        // The episode may end before all repeats so a single inner reward is also possible.
+        let IntervalSpace { low, high } = self.inner.feedback_space();
+        IntervalSpace::new(
+            Reward(low.0.min(self.wrapper.map_bound(low.0))),
+            Reward(high.0.max(self.wrapper.map_bound(high.0))),
+        )
+    }
+    #[allow(clippy::cast_precision_loss)]
+    fn discount_factor(&self) -> f64 {
+        // powf since the number of repeats might not fit in the i32 exponent of powi
+        self.inner
+            .discount_factor()
+            .powf(self.wrapper.repeats.get() as f64)
+    }
+}
+
+impl<E: Environment<Feedback = Reward>> Environment for Wrapped<E, RepeatAction> {
+    type State = E::State;
+    type Observation = E::Observation;
+    type Action = E::Action;
+    type Feedback = Reward;
+
+    #[inline]
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        self.inner.initial_state(rng)
+    }
+
+    #[inline]
+    fn observe(&self, state: &Self::State, rng: &mut Prng) -> Self::Observation {
+        self.inner.observe(state, rng)
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        rng: &mut Prng,
+        logger: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        let (mut successor, Reward(mut total)) = self.inner.step(state, action, rng, logger);
+        for _ in 1..self.wrapper.repeats.get() {
+            let Successor::Continue(state) = successor else {
+                break;
+            };
+            let (next_successor, Reward(reward)) = self.inner.step(state, action, rng, logger);
+            successor = next_successor;
+            total += reward;
+        }
+        (successor, Reward(total))
+    }
+}
+
+/// Environment wrapper that randomly repeats the previous action instead of the given one.
+///
+/// On each step, with probability `stick_prob` the previous action of the episode is executed in
+/// place of the selected action. The first action of each episode is always executed as given.
+/// Randomness is drawn from the environment PRNG.
+///
+/// Sticky actions are described in
+/// "[Revisiting the Arcade Learning Environment][ale]" by Machado et al. (2018).
+///
+/// [ale]: https://arxiv.org/abs/1709.06009
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StickyActions {
+    /// Probability of executing the previous action instead of the selected one.
+    pub stick_prob: f64,
+}
+
+impl StickyActions {
+    #[must_use]
+    #[inline]
+    pub fn new(stick_prob: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&stick_prob),
+            "stick_prob must be in [0, 1]"
+        );
+        Self { stick_prob }
+    }
+}
+
+impl Default for StickyActions {
+    #[inline]
+    fn default() -> Self {
+        Self { stick_prob: 0.25 }
+    }
+}
+
+/// Wrap an environment with sticky actions.
+pub type WithStickyActions<E> = Wrapped<E, StickyActions>;
+
+impl StructurePreservingWrapper for StickyActions {}
+
+/// Wrapped environment state with the previously executed action.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StickyActionState<T, A> {
+    pub inner: T,
+    /// The action executed on the previous step of the episode, if any.
+    pub prev_action: Option<A>,
+}
+
+impl<E> Environment for Wrapped<E, StickyActions>
+where
+    E: Environment,
+    E::Action: Clone,
+{
+    type State = StickyActionState<E::State, E::Action>;
+    type Observation = E::Observation;
+    type Action = E::Action;
+    type Feedback = E::Feedback;
+
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        StickyActionState {
+            inner: self.inner.initial_state(rng),
+            prev_action: None,
+        }
+    }
+
+    #[inline]
+    fn observe(&self, state: &Self::State, rng: &mut Prng) -> Self::Observation {
+        self.inner.observe(&state.inner, rng)
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        rng: &mut Prng,
+        logger: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        let action = match state.prev_action {
+            Some(prev_action) if rng.gen::<f64>() < self.wrapper.stick_prob => prev_action,
+            _ => action.clone(),
+        };
+        let (inner_successor, feedback) = self.inner.step(state.inner, &action, rng, logger);
+        let successor = inner_successor.map(|inner| StickyActionState {
+            inner,
+            prev_action: Some(action),
+        });
+        (successor, feedback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{
+        chain::Move, testing, BuildEnv, Chain, DeterministicBandit, VisibleStepLimit,
+    };
+    use super::super::Wrap;
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn repeat_run_default() {
+        testing::check_structured_env(&Chain::default().wrap(RepeatAction::default()), 1000, 0);
+    }
+
+    #[test]
+    fn repeat_build() {
+        let config = Chain::default().wrap(RepeatAction::new(3));
+        let _env = config.build_env(&mut Prng::seed_from_u64(0)).unwrap();
+    }
+
+    #[test]
+    fn repeat_sums_inner_rewards() {
+        let env = Chain::default();
+        let wrapped = env.wrap(RepeatAction::new(3));
+
+        let mut rng = Prng::seed_from_u64(1);
+        let mut state = env.initial_state(&mut rng);
+        let mut total = 0.0;
+        for _ in 0..3 {
+            let (successor, reward) = env.step(state, &Move::Right, &mut rng, &mut ());
+            state = successor.into_continue().unwrap();
+            total += reward.unwrap();
+        }
+
+        let mut rng = Prng::seed_from_u64(1);
+        let wrapped_state = wrapped.initial_state(&mut rng);
+        let (successor, reward) = wrapped.step(wrapped_state, &Move::Right, &mut rng, &mut ());
+        assert_eq!(successor.into_continue(), Some(state));
+        #[allow(clippy::float_cmp)] // same operations in the same order
+        {
+            assert_eq!(reward.unwrap(), total);
+        }
+    }
+
+    #[test]
+    fn repeat_stops_on_terminate() {
+        let env = DeterministicBandit::from_values([0.0, 1.0]).wrap(RepeatAction::new(3));
+        let mut rng = Prng::seed_from_u64(2);
+        let (successor, reward) = env.step((), &1, &mut rng, &mut ());
+        assert!(matches!(successor, Successor::Terminate));
+        assert_eq!(reward, Reward(1.0));
+    }
+
+    #[test]
+    fn repeat_stops_on_interrupt() {
+        let env = Chain::default()
+            .wrap(VisibleStepLimit::new(2))
+            .wrap(RepeatAction::new(3));
+        let mut rng = Prng::seed_from_u64(3);
+        let state = env.initial_state(&mut rng);
+        let (successor, _) = env.step(state, &Move::Left, &mut rng, &mut ());
+        assert!(matches!(successor, Successor::Interrupt(_)));
+    }
+
+    #[test]
+    fn repeat_run_with_visible_step_limit() {
+        let env = Chain::default()
+            .wrap(RepeatAction::new(3))
+            .wrap(VisibleStepLimit::new(10));
+        testing::check_structured_env(&env, 1000, 4);
+    }
+
+    #[test]
+    fn repeat_feedback_space() {
+        let env = Chain::default().wrap(RepeatAction::new(3));
+        assert_eq!(
+            env.feedback_space(),
+            IntervalSpace::new(Reward(0.0), Reward(30.0))
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn repeat_discount_factor() {
+        let env = Chain::default();
+        let discount = env.wrap(RepeatAction::new(3)).discount_factor();
+        assert!((discount - env.discount_factor().powi(3)).abs() < 1e-12);
+        // Repeats that overflow an i32 exponent
+        assert_eq!(env.wrap(RepeatAction::new(1 << 32)).discount_factor(), 0.0);
+    }
+
+    #[test]
+    fn repeat_deserialize_zero() {
+        assert!(serde_json::from_str::<RepeatAction>(r#"{"repeats": 0}"#).is_err());
+        assert_eq!(
+            serde_json::from_str::<RepeatAction>(r#"{"repeats": 3}"#).unwrap(),
+            RepeatAction::new(3)
+        );
+    }
+
+    #[test]
+    fn sticky_run_default() {
+        testing::check_structured_env(&Chain::default().wrap(StickyActions::default()), 1000, 5);
+    }
+
+    #[test]
+    fn sticky_build() {
+        let config = Chain::default().wrap(StickyActions::new(0.5));
+        let _env = config.build_env(&mut Prng::seed_from_u64(0)).unwrap();
+    }
+
+    #[test]
+    fn sticky_run_with_visible_step_limit() {
+        let env = Chain::default()
+            .wrap(StickyActions::default())
+            .wrap(VisibleStepLimit::new(10));
+        testing::check_structured_env(&env, 1000, 6);
+    }
+
+    #[test]
+    fn sticky_always_repeats_first_action() {
+        let env = Chain::default().wrap(StickyActions::new(1.0));
+        let mut rng = Prng::seed_from_u64(7);
+        let state = env.initial_state(&mut rng);
+        let (successor, _) = env.step(state, &Move::Right, &mut rng, &mut ());
+        let mut state = successor.into_continue().unwrap();
+        for _ in 0..10 {
+            let (successor, _) = env.step(state, &Move::Left, &mut rng, &mut ());
+            state = successor.into_continue().unwrap();
+            assert_eq!(state.prev_action, Some(Move::Right));
+        }
+    }
+
+    #[test]
+    fn sticky_never_repeats() {
+        let env = Chain::default().wrap(StickyActions::new(0.0));
+        let mut rng = Prng::seed_from_u64(8);
+        let mut state = env.initial_state(&mut rng);
+        for action in [Move::Right, Move::Left, Move::Left, Move::Right] {
+            let (successor, _) = env.step(state, &action, &mut rng, &mut ());
+            state = successor.into_continue().unwrap();
+            assert_eq!(state.prev_action, Some(action));
+        }
+    }
+
+    #[test]
+    fn sticky_serialize_config() {
+        let config = StickyActions::new(0.3);
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            serde_json::from_str::<StickyActions>(&json).unwrap(),
+            config
+        );
+    }
+}
@@ -1,8 +1,28 @@
+mod action;
+mod noise;
+mod normalize;
+mod reward;
+mod stack;
 mod step_limit;
+mod time;
 
+pub use action::{RepeatAction, StickyActions, WithRepeatedActions, WithStickyActions};
+pub use noise::{
+    ActionNoise, Noise, NoisySpace, ObservationNoise, WithActionNoise, WithObservationNoise,
+};
+pub use normalize::{
+    FeatureStats, NormalizeObservations, NormalizedObsSpace, WithNormalizedObservations,
+};
+pub use reward::{
+    ClipReward, NormalizeReward, ScaleReward, SignReward, WithClippedReward, WithNormalizedReward,
+    WithScaledReward, WithSignReward,
+};
+pub use stack::{StackObservations, WithStackedObservations};
 pub use step_limit::{
-    LatentStepLimit, VisibleStepLimit, WithLatentStepLimit, WithVisibleStepLimit,
+    LatentStepLimit, StepLimitObs, StepLimitObsSpace, VisibleStepLimit, WithLatentStepLimit,
+    WithVisibleStepLimit,
 };
+pub use time::{StepLimited, TimeRemaining, WithTimeRemaining};
 
 use super::{
     BuildEnv, BuildEnvDist, BuildEnvError, EnvDistribution, EnvStructure, Environment,
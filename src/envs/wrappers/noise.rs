@@ -0,0 +1,281 @@
+use super::super::{Environment, StructuredEnvironment, Successor};
+use super::{StructurePreservingWrapper, Wrapped};
+use crate::logging::StatsLogger;
+use crate::spaces::{IntervalSpace, NdArraySpace, SampleSpace, Space};
+use crate::Prng;
+use ndarray::Dimension;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+
+/// Distribution of additive noise applied to scalar values.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Noise {
+    /// Zero-mean Gaussian noise with the given standard deviation.
+    Gaussian { std: f64 },
+    /// Uniform noise on the interval `[-half_width, half_width]`.
+    Uniform { half_width: f64 },
+}
+
+impl Default for Noise {
+    #[inline]
+    fn default() -> Self {
+        Self::Gaussian { std: 0.1 }
+    }
+}
+
+impl Noise {
+    /// Sample a noise value.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        match self {
+            Self::Gaussian { std } => std * rng.sample::<f64, _>(StandardNormal),
+            Self::Uniform { half_width } => half_width * rng.gen_range(-1.0..=1.0),
+        }
+    }
+}
+
+/// A space with real-valued elements that can be perturbed by additive noise.
+pub trait NoisySpace: Space {
+    /// Add noise to each scalar component of an element, keeping the result within the space.
+    fn perturb(&self, element: &mut Self::Element, noise: &Noise, rng: &mut Prng);
+}
+
+impl NoisySpace for IntervalSpace<f64> {
+    #[inline]
+    fn perturb(&self, element: &mut Self::Element, noise: &Noise, rng: &mut Prng) {
+        *element = (*element + noise.sample(rng)).clamp(self.low, self.high);
+    }
+}
+
+impl NoisySpace for IntervalSpace<f32> {
+    #[allow(clippy::cast_possible_truncation)]
+    #[inline]
+    fn perturb(&self, element: &mut Self::Element, noise: &Noise, rng: &mut Prng) {
+        *element = (*element + noise.sample(rng) as f32).clamp(self.low, self.high);
+    }
+}
+
+impl<S: NoisySpace, D: Dimension> NoisySpace for NdArraySpace<S, D> {
+    fn perturb(&self, element: &mut Self::Element, noise: &Noise, rng: &mut Prng) {
+        for inner_element in element.iter_mut() {
+            self.inner.perturb(inner_element, noise, rng);
+        }
+    }
+}
+
+/// Environment wrapper that adds random noise to observations.
+///
+/// Noise is sampled independently for each scalar component of the observation and
+/// the noisy observation is clamped to the observation space.
+/// Randomness is drawn from the environment PRNG.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ObservationNoise {
+    /// Distribution of the noise added to each observation component.
+    pub noise: Noise,
+}
+
+impl ObservationNoise {
+    #[must_use]
+    #[inline]
+    pub const fn new(noise: Noise) -> Self {
+        Self { noise }
+    }
+}
+
+/// Wrap an environment with noisy observations.
+pub type WithObservationNoise<E> = Wrapped<E, ObservationNoise>;
+
+impl StructurePreservingWrapper for ObservationNoise {}
+
+impl<E> Environment for Wrapped<E, ObservationNoise>
+where
+    E: StructuredEnvironment,
+    E::ObservationSpace: NoisySpace,
+{
+    type State = E::State;
+    type Observation = E::Observation;
+    type Action = E::Action;
+    type Feedback = E::Feedback;
+
+    #[inline]
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        self.inner.initial_state(rng)
+    }
+
+    fn observe(&self, state: &Self::State, rng: &mut Prng) -> Self::Observation {
+        let mut observation = self.inner.observe(state, rng);
+        self.inner
+            .observation_space()
+            .perturb(&mut observation, &self.wrapper.noise, rng);
+        observation
+    }
+
+    #[inline]
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        rng: &mut Prng,
+        logger: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        self.inner.step(state, action, rng, logger)
+    }
+}
+
+/// Environment wrapper that randomly replaces actions with samples from the action space.
+///
+/// On each step, with probability `epsilon` the selected action is replaced with an action sampled
+/// uniformly from the action space. Randomness is drawn from the environment PRNG.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ActionNoise {
+    /// Probability of replacing the selected action with a random action.
+    pub epsilon: f64,
+}
+
+impl ActionNoise {
+    #[must_use]
+    #[inline]
+    pub fn new(epsilon: f64) -> Self {
+        assert!((0.0..=1.0).contains(&epsilon), "epsilon must be in [0, 1]");
+        Self { epsilon }
+    }
+}
+
+impl Default for ActionNoise {
+    #[inline]
+    fn default() -> Self {
+        Self { epsilon: 0.1 }
+    }
+}
+
+/// Wrap an environment with randomly replaced actions.
+pub type WithActionNoise<E> = Wrapped<E, ActionNoise>;
+
+impl StructurePreservingWrapper for ActionNoise {}
+
+impl<E> Environment for Wrapped<E, ActionNoise>
+where
+    E: StructuredEnvironment,
+    E::ActionSpace: SampleSpace,
+{
+    type State = E::State;
+    type Observation = E::Observation;
+    type Action = E::Action;
+    type Feedback = E::Feedback;
+
+    #[inline]
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        self.inner.initial_state(rng)
+    }
+
+    #[inline]
+    fn observe(&self, state: &Self::State, rng: &mut Prng) -> Self::Observation {
+        self.inner.observe(state, rng)
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        rng: &mut Prng,
+        logger: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        if rng.gen::<f64>() < self.wrapper.epsilon {
+            let random_action = self.inner.action_space().sample(rng);
+            self.inner.step(state, &random_action, rng, logger)
+        } else {
+            self.inner.step(state, action, rng, logger)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{testing, BuildEnv, CartPole, CartPoleConfig, DeterministicBandit};
+    use super::super::Wrap;
+    use super::*;
+    use crate::spaces::Array1Space;
+    use ndarray::Array;
+    use rand::SeedableRng;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::gaussian(Noise::Gaussian { std: 0.1 })]
+    #[case::uniform(Noise::Uniform { half_width: 0.1 })]
+    fn observation_noise_run_cartpole(#[case] noise: Noise) {
+        testing::check_structured_env(
+            &CartPole::default().wrap(ObservationNoise::new(noise)),
+            1000,
+            0,
+        );
+    }
+
+    #[test]
+    fn observation_noise_build() {
+        let config = CartPoleConfig::default().wrap(ObservationNoise::default());
+        let _env = config.build_env(&mut Prng::seed_from_u64(0)).unwrap();
+    }
+
+    #[test]
+    fn observation_noise_perturbs() {
+        let env = CartPole::default();
+        let noisy_env = env.wrap(ObservationNoise::new(Noise::Uniform { half_width: 0.01 }));
+        let mut rng = Prng::seed_from_u64(1);
+        let state = env.initial_state(&mut rng);
+        let observation = env.observe(&state, &mut rng);
+        let noisy_observation = noisy_env.observe(&state, &mut rng);
+        assert_ne!(observation, noisy_observation);
+        assert!((observation.cart_position - noisy_observation.cart_position).abs() <= 0.01);
+        assert!((observation.pole_angle - noisy_observation.pole_angle).abs() <= 0.01);
+    }
+
+    #[test]
+    fn interval_noise_clamped() {
+        let space = IntervalSpace::new(0.0, 1.0);
+        let noise = Noise::Gaussian { std: 10.0 };
+        let mut rng = Prng::seed_from_u64(2);
+        for _ in 0..100 {
+            let mut x = 0.5;
+            space.perturb(&mut x, &noise, &mut rng);
+            assert!(space.contains(&x));
+        }
+    }
+
+    #[test]
+    fn ndarray_noise_each_element() {
+        let space = Array1Space::new(IntervalSpace::new(-1.0, 1.0), 5);
+        let noise = Noise::Uniform { half_width: 0.5 };
+        let mut rng = Prng::seed_from_u64(3);
+        let mut x = Array::zeros(5);
+        space.perturb(&mut x, &noise, &mut rng);
+        assert!(x.iter().all(|v: &f64| *v != 0.0 && v.abs() <= 0.5));
+    }
+
+    #[test]
+    fn action_noise_run_cartpole() {
+        testing::check_structured_env(&CartPole::default().wrap(ActionNoise::default()), 1000, 4);
+    }
+
+    #[test]
+    fn action_noise_build() {
+        let config = CartPoleConfig::default().wrap(ActionNoise::new(0.5));
+        let _env = config.build_env(&mut Prng::seed_from_u64(0)).unwrap();
+    }
+
+    #[rstest]
+    #[case::never(0.0, 1.0, 1.0)]
+    #[case::always(1.0, 0.4, 0.6)]
+    fn action_noise_bandit(#[case] epsilon: f64, #[case] min_mean: f64, #[case] max_mean: f64) {
+        let env = DeterministicBandit::from_values([0.0, 1.0]).wrap(ActionNoise::new(epsilon));
+        let mut rng = Prng::seed_from_u64(5);
+        let num_steps = 1000;
+        let total: f64 = (0..num_steps)
+            .map(|_| env.step((), &1, &mut rng, &mut ()).1.unwrap())
+            .sum();
+        let mean = total / f64::from(num_steps);
+        assert!(
+            (min_mean..=max_mean).contains(&mean),
+            "mean reward {mean} not in [{min_mean}, {max_mean}]"
+        );
+    }
+}
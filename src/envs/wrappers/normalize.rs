@@ -0,0 +1,420 @@
+use super::super::{EnvStructure, Environment, StructuredEnvironment, Successor};
+use super::Wrapped;
+use crate::logging::{LogError, StatsLogger};
+use crate::spaces::{FeatureSpace, LogElementSpace, NonEmptySpace, Space, SubsetOrd};
+use crate::utils::stats::OnlineMeanVariance;
+use crate::Prng;
+use num_traits::Float;
+use rand::distributions::Distribution;
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// Environment wrapper that normalizes observation features with running statistics.
+///
+/// Observations are passed through unchanged but the observation space is replaced by a
+/// [`NormalizedObsSpace`] whose feature vectors are standardized by the running mean and variance
+/// of the observation features seen so far and then clipped to `[-clip, clip]`.
+///
+/// The statistics are shared by all clones of the wrapper and by all observation spaces created
+/// from it, so an agent built for the environment sees the statistics update as the environment
+/// is simulated. Serializing an observation space (e.g. as part of an actor) saves a snapshot of
+/// the statistics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NormalizeObservations {
+    /// Normalized features are clipped to the interval `[-clip, clip]`.
+    pub clip: f64,
+    /// Added to the variance for numerical stability.
+    pub epsilon: f64,
+    /// Running statistics of the observation features.
+    #[serde(skip)]
+    stats: FeatureStats,
+}
+
+impl NormalizeObservations {
+    #[must_use]
+    #[inline]
+    pub fn new(clip: f64) -> Self {
+        assert!(clip > 0.0, "clip must be positive");
+        Self {
+            clip,
+            ..Self::default()
+        }
+    }
+
+    /// Running statistics of the observation features.
+    #[must_use]
+    #[inline]
+    pub const fn stats(&self) -> &FeatureStats {
+        &self.stats
+    }
+}
+
+impl Default for NormalizeObservations {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            clip: 10.0,
+            epsilon: 1e-8,
+            stats: FeatureStats::default(),
+        }
+    }
+}
+
+/// Wrap an environment with running observation normalization.
+pub type WithNormalizedObservations<E> = Wrapped<E, NormalizeObservations>;
+
+/// Running per-feature mean and variance statistics.
+///
+/// Clones share the same underlying statistics.
+/// Serializes as a snapshot of the current statistics.
+#[derive(Debug, Default, Clone)]
+pub struct FeatureStats(Arc<RwLock<FeatureStatsState>>);
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct FeatureStatsState {
+    features: Vec<OnlineMeanVariance<f64>>,
+    frozen: bool,
+}
+
+impl FeatureStats {
+    /// Stop updating the statistics. Use for evaluation.
+    pub fn freeze(&self) {
+        self.0.write().unwrap().frozen = true;
+    }
+
+    /// Resume updating the statistics.
+    pub fn unfreeze(&self) {
+        self.0.write().unwrap().frozen = false;
+    }
+
+    /// Whether the statistics are frozen.
+    #[must_use]
+    pub fn is_frozen(&self) -> bool {
+        self.0.read().unwrap().frozen
+    }
+
+    /// The current statistics of each feature.
+    #[must_use]
+    pub fn features(&self) -> Vec<OnlineMeanVariance<f64>> {
+        self.0.read().unwrap().features.clone()
+    }
+
+    /// The current statistics of a single feature, if any.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<OnlineMeanVariance<f64>> {
+        self.0.read().unwrap().features.get(index).copied()
+    }
+
+    /// Update the statistics with a feature vector. Does nothing if frozen.
+    ///
+    /// # Panics
+    /// If the number of features differs from that of previous updates.
+    pub fn update(&self, features: &[f64]) {
+        let mut state = self.0.write().unwrap();
+        if state.frozen {
+            return;
+        }
+        if state.features.is_empty() {
+            state.features = vec![OnlineMeanVariance::new(); features.len()];
+        }
+        assert_eq!(
+            state.features.len(),
+            features.len(),
+            "inconsistent number of features"
+        );
+        for (stats, &value) in state.features.iter_mut().zip(features) {
+            stats.push(value);
+        }
+    }
+
+    /// Standardize features in place then clip to `[-clip, clip]`.
+    ///
+    /// Features without any statistics are only clipped.
+    fn normalize<F: Float>(&self, features: &mut [F], clip: f64, epsilon: f64) {
+        let state = self.0.read().unwrap();
+        for (i, x) in features.iter_mut().enumerate() {
+            let mut value = x.to_f64().unwrap();
+            if let Some(stats) = state.features.get(i) {
+                if let (Some(mean), Some(variance)) = (stats.mean(), stats.variance()) {
+                    value = (value - mean) / (variance + epsilon).sqrt();
+                }
+            }
+            *x = F::from(value.clamp(-clip, clip)).unwrap();
+        }
+    }
+}
+
+/// Compares the current statistics.
+impl PartialEq for FeatureStats {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || *self.0.read().unwrap() == *other.0.read().unwrap()
+    }
+}
+
+impl Serialize for FeatureStats {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.read().unwrap().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FeatureStats {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = FeatureStatsState::deserialize(deserializer)?;
+        Ok(Self(Arc::new(RwLock::new(state))))
+    }
+}
+
+/// Observation space with features normalized by running statistics.
+///
+/// Elements are those of the inner space.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NormalizedObsSpace<S> {
+    pub inner: S,
+    pub stats: FeatureStats,
+    /// Normalized features are clipped to the interval `[-clip, clip]`.
+    pub clip: f64,
+    /// Added to the variance for numerical stability.
+    pub epsilon: f64,
+}
+
+impl<S: fmt::Display> fmt::Display for NormalizedObsSpace<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NormalizedObsSpace<{}>", self.inner)
+    }
+}
+
+impl<S: Space> Space for NormalizedObsSpace<S> {
+    type Element = S::Element;
+
+    #[inline]
+    fn contains(&self, value: &Self::Element) -> bool {
+        self.inner.contains(value)
+    }
+}
+
+/// Only spaces with the same normalization are comparable.
+impl<S: SubsetOrd> SubsetOrd for NormalizedObsSpace<S> {
+    #[allow(clippy::float_cmp)] // exact comparison of configuration
+    fn subset_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.stats == other.stats && self.clip == other.clip && self.epsilon == other.epsilon {
+            self.inner.subset_cmp(&other.inner)
+        } else {
+            None
+        }
+    }
+}
+
+impl<S: NonEmptySpace> NonEmptySpace for NormalizedObsSpace<S> {
+    #[inline]
+    fn some_element(&self) -> Self::Element {
+        self.inner.some_element()
+    }
+}
+
+impl<S: Space + Distribution<S::Element>> Distribution<<Self as Space>::Element>
+    for NormalizedObsSpace<S>
+{
+    #[inline]
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> <Self as Space>::Element {
+        self.inner.sample(rng)
+    }
+}
+
+impl<S: FeatureSpace> FeatureSpace for NormalizedObsSpace<S> {
+    #[inline]
+    fn num_features(&self) -> usize {
+        self.inner.num_features()
+    }
+
+    fn features_out<'a, F: Float>(
+        &self,
+        element: &Self::Element,
+        out: &'a mut [F],
+        zeroed: bool,
+    ) -> &'a mut [F] {
+        let num_features = self.inner.num_features();
+        self.inner.features_out(element, out, zeroed);
+        let (features, rest) = out.split_at_mut(num_features);
+        self.stats.normalize(features, self.clip, self.epsilon);
+        rest
+    }
+}
+
+impl<S: LogElementSpace> LogElementSpace for NormalizedObsSpace<S> {
+    #[inline]
+    fn log_element<L: StatsLogger + ?Sized>(
+        &self,
+        name: &'static str,
+        element: &Self::Element,
+        logger: &mut L,
+    ) -> Result<(), LogError> {
+        self.inner.log_element(name, element, logger)
+    }
+}
+
+impl<E: EnvStructure> EnvStructure for Wrapped<E, NormalizeObservations> {
+    type ObservationSpace = NormalizedObsSpace<E::ObservationSpace>;
+    type ActionSpace = E::ActionSpace;
+    type FeedbackSpace = E::FeedbackSpace;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        NormalizedObsSpace {
+            inner: self.inner.observation_space(),
+            stats: self.wrapper.stats.clone(),
+            clip: self.wrapper.clip,
+            epsilon: self.wrapper.epsilon,
+        }
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        self.inner.action_space()
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        self.inner.feedback_space()
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.inner.discount_factor()
+    }
+}
+
+impl<E> Environment for Wrapped<E, NormalizeObservations>
+where
+    E: StructuredEnvironment,
+    E::ObservationSpace: FeatureSpace,
+{
+    type State = E::State;
+    type Observation = E::Observation;
+    type Action = E::Action;
+    type Feedback = E::Feedback;
+
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        self.inner.initial_state(rng)
+    }
+
+    /// Observe the inner environment and update the running feature statistics.
+    fn observe(&self, state: &Self::State, rng: &mut Prng) -> Self::Observation {
+        let observation = self.inner.observe(state, rng);
+        if !self.wrapper.stats.is_frozen() {
+            let observation_space = self.inner.observation_space();
+            let mut features = vec![0.0; observation_space.num_features()];
+            observation_space.features_out(&observation, &mut features, true);
+            self.wrapper.stats.update(&features);
+        }
+        observation
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        rng: &mut Prng,
+        logger: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        self.inner.step(state, action, rng, logger)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)] // expecting exact values
+mod tests {
+    use super::super::super::{testing, BuildEnv, CartPole, Chain};
+    use super::super::Wrap;
+    use super::*;
+    use crate::agents::RandomAgent;
+    use crate::simulation::SimSeed;
+    use crate::spaces::{IntervalSpace, NonEmptyFeatures, TupleSpace2};
+    use rand::SeedableRng;
+
+    #[test]
+    fn run_default() {
+        testing::check_structured_env(
+            &Chain::default().wrap(NormalizeObservations::default()),
+            1000,
+            119,
+        );
+    }
+
+    #[test]
+    fn build() {
+        let config = Chain::default().wrap(NormalizeObservations::default());
+        let _env = config.build_env(&mut Prng::seed_from_u64(0)).unwrap();
+    }
+
+    #[test]
+    fn normalizes_features() {
+        let stats = FeatureStats::default();
+        stats.update(&[1.0, 10.0]);
+        stats.update(&[3.0, 10.0]);
+        let space = NormalizedObsSpace {
+            inner: TupleSpace2(IntervalSpace::default(), IntervalSpace::default()),
+            stats,
+            clip: 5.0,
+            epsilon: 0.0,
+        };
+        let features: Vec<f64> = space.features::<ndarray::Array1<_>>(&(4.0, 9.0)).to_vec();
+        // First feature: mean 2, stddev 1. Second: mean 10, stddev 0 so clipped.
+        assert_eq!(features, [2.0, -5.0]);
+    }
+
+    #[test]
+    fn no_stats_only_clips() {
+        let space = NormalizedObsSpace {
+            inner: IntervalSpace::default(),
+            stats: FeatureStats::default(),
+            clip: 5.0,
+            epsilon: 1e-8,
+        };
+        let features: Vec<f64> = space.features::<ndarray::Array1<_>>(&-7.0).to_vec();
+        assert_eq!(features, [-5.0]);
+    }
+
+    #[test]
+    fn frozen_stats_do_not_update() {
+        let stats = FeatureStats::default();
+        stats.update(&[1.0]);
+        stats.freeze();
+        stats.update(&[3.0]);
+        assert_eq!(stats.features()[0].count(), 1);
+        stats.unfreeze();
+        stats.update(&[3.0]);
+        assert_eq!(stats.features()[0].count(), 2);
+    }
+
+    #[test]
+    fn observe_updates_shared_stats() {
+        let env = CartPole::default().wrap(NormalizeObservations::default());
+        let observation_space = env.observation_space();
+        let agent = RandomAgent::new(env.action_space());
+        let _: Vec<_> = env.run(agent, SimSeed::Root(0), ()).take(100).collect();
+        let stats = observation_space.stats.features();
+        assert_eq!(stats.len(), observation_space.num_features());
+        assert!(stats[0].count() >= 100);
+    }
+
+    /// A deserialized actor observation space normalizes identically to the original.
+    #[test]
+    fn serialized_space_normalizes_identically() {
+        let env = CartPole::default().wrap(NormalizeObservations::default());
+        let agent = RandomAgent::new(env.action_space());
+        let _: Vec<_> = (&env).run(agent, SimSeed::Root(1), ()).take(100).collect();
+        env.wrapper.stats().freeze();
+
+        let space = NonEmptyFeatures::new(env.observation_space());
+        let serialized = serde_cbor::to_vec(&space).unwrap();
+        let deserialized: NonEmptyFeatures<NormalizedObsSpace<_>> =
+            serde_cbor::from_slice(&serialized).unwrap();
+        assert_eq!(deserialized, space);
+
+        let mut rng = Prng::seed_from_u64(2);
+        let state = env.initial_state(&mut rng);
+        let observation = env.observe(&state, &mut rng);
+        let expected: ndarray::Array1<f32> = space.features(&observation);
+        let actual: ndarray::Array1<f32> = deserialized.features(&observation);
+        assert_eq!(actual, expected);
+    }
+}
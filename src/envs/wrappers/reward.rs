@@ -0,0 +1,489 @@
+use super::super::{EnvStructure, Environment, Successor};
+use super::{FeatureStats, Wrapped};
+use crate::feedback::Reward;
+use crate::logging::StatsLogger;
+use crate::spaces::IntervalSpace;
+use crate::Prng;
+use serde::{Deserialize, Serialize};
+
+/// A stateless transformation of reward values.
+trait MapReward {
+    /// Transform a reward value.
+    fn map_reward(&self, reward: f64) -> f64;
+
+    /// The space of transformed rewards given the space of original rewards.
+    fn map_reward_space(&self, space: IntervalSpace<Reward>) -> IntervalSpace<Reward>;
+}
+
+/// Implement [`EnvStructure`] and [`Environment`] for `Wrapped<E, $wrapper>` where
+/// `$wrapper: MapReward`.
+macro_rules! impl_map_reward_wrapper {
+    ($wrapper:ty) => {
+        impl<E> EnvStructure for Wrapped<E, $wrapper>
+        where
+            E: EnvStructure<FeedbackSpace = IntervalSpace<Reward>>,
+        {
+            type ObservationSpace = E::ObservationSpace;
+            type ActionSpace = E::ActionSpace;
+            type FeedbackSpace = IntervalSpace<Reward>;
+
+            #[inline]
+            fn observation_space(&self) -> Self::ObservationSpace {
+                self.inner.observation_space()
+            }
+            #[inline]
+            fn action_space(&self) -> Self::ActionSpace {
+                self.inner.action_space()
+            }
+            #[inline]
+            fn feedback_space(&self) -> Self::FeedbackSpace {
+                self.wrapper.map_reward_space(self.inner.feedback_space())
+            }
+            #[inline]
+            fn discount_factor(&self) -> f64 {
+                self.inner.discount_factor()
+            }
+        }
+
+        impl<E: Environment<Feedback = Reward>> Environment for Wrapped<E, $wrapper> {
+            type State = E::State;
+            type Observation = E::Observation;
+            type Action = E::Action;
+            type Feedback = Reward;
+
+            #[inline]
+            fn initial_state(&self, rng: &mut Prng) -> Self::State {
+                self.inner.initial_state(rng)
+            }
+
+            #[inline]
+            fn observe(&self, state: &Self::State, rng: &mut Prng) -> Self::Observation {
+                self.inner.observe(state, rng)
+            }
+
+            #[inline]
+            fn step(
+                &self,
+                state: Self::State,
+                action: &Self::Action,
+                rng: &mut Prng,
+                logger: &mut dyn StatsLogger,
+            ) -> (Successor<Self::State>, Self::Feedback) {
+                let (successor, Reward(reward)) = self.inner.step(state, action, rng, logger);
+                (successor, Reward(self.wrapper.map_reward(reward)))
+            }
+        }
+    };
+}
+
+/// Whether a reward bound represents an unbounded end of an interval.
+#[allow(clippy::float_cmp)] // exact extreme values
+fn is_unbounded(bound: f64) -> bool {
+    bound.is_infinite() || bound == f64::MIN || bound == f64::MAX
+}
+
+/// Environment wrapper that applies a fixed affine transformation to rewards.
+///
+/// Each reward `r` is replaced by `scale * r + shift`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScaleReward {
+    /// Multiplicative scale applied to the reward.
+    pub scale: f64,
+    /// Offset added to the scaled reward.
+    pub shift: f64,
+}
+
+impl ScaleReward {
+    #[must_use]
+    #[inline]
+    pub const fn new(scale: f64, shift: f64) -> Self {
+        Self { scale, shift }
+    }
+
+    /// Transform an interval bound, keeping unbounded ends unbounded.
+    fn map_bound(&self, bound: f64) -> f64 {
+        if self.scale == 0.0 {
+            self.shift
+        } else if is_unbounded(bound) {
+            if (bound > 0.0) == (self.scale > 0.0) {
+                f64::MAX
+            } else {
+                f64::MIN
+            }
+        } else {
+            (self.scale * bound + self.shift).clamp(f64::MIN, f64::MAX)
+        }
+    }
+}
+
+impl Default for ScaleReward {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            shift: 0.0,
+        }
+    }
+}
+
+impl MapReward for ScaleReward {
+    #[inline]
+    fn map_reward(&self, reward: f64) -> f64 {
+        self.scale * reward + self.shift
+    }
+
+    fn map_reward_space(&self, space: IntervalSpace<Reward>) -> IntervalSpace<Reward> {
+        let low = self.map_bound(space.low.0);
+        let high = self.map_bound(space.high.0);
+        if self.scale >= 0.0 {
+            IntervalSpace::new(Reward(low), Reward(high))
+        } else {
+            IntervalSpace::new(Reward(high), Reward(low))
+        }
+    }
+}
+
+impl_map_reward_wrapper!(ScaleReward);
+
+/// Wrap an environment with a fixed affine reward transformation.
+pub type WithScaledReward<E> = Wrapped<E, ScaleReward>;
+
+/// Environment wrapper that clips rewards to an interval.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClipReward {
+    /// Minimum reward value.
+    pub min: f64,
+    /// Maximum reward value.
+    pub max: f64,
+}
+
+impl ClipReward {
+    #[must_use]
+    #[inline]
+    pub fn new(min: f64, max: f64) -> Self {
+        assert!(min <= max, "require min <= max");
+        Self { min, max }
+    }
+}
+
+impl Default for ClipReward {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            min: -1.0,
+            max: 1.0,
+        }
+    }
+}
+
+impl MapReward for ClipReward {
+    #[inline]
+    fn map_reward(&self, reward: f64) -> f64 {
+        reward.clamp(self.min, self.max)
+    }
+
+    fn map_reward_space(&self, space: IntervalSpace<Reward>) -> IntervalSpace<Reward> {
+        IntervalSpace::new(
+            Reward(self.map_reward(space.low.0)),
+            Reward(self.map_reward(space.high.0)),
+        )
+    }
+}
+
+impl_map_reward_wrapper!(ClipReward);
+
+/// Wrap an environment with reward clipping.
+pub type WithClippedReward<E> = Wrapped<E, ClipReward>;
+
+/// Environment wrapper that replaces each reward with its sign: `-1`, `0`, or `1`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SignReward;
+
+impl MapReward for SignReward {
+    #[inline]
+    fn map_reward(&self, reward: f64) -> f64 {
+        if reward > 0.0 {
+            1.0
+        } else if reward < 0.0 {
+            -1.0
+        } else {
+            0.0
+        }
+    }
+
+    fn map_reward_space(&self, space: IntervalSpace<Reward>) -> IntervalSpace<Reward> {
+        IntervalSpace::new(
+            Reward(self.map_reward(space.low.0)),
+            Reward(self.map_reward(space.high.0)),
+        )
+    }
+}
+
+impl_map_reward_wrapper!(SignReward);
+
+/// Wrap an environment to replace rewards with their sign.
+pub type WithSignReward<E> = Wrapped<E, SignReward>;
+
+/// Environment wrapper that normalizes rewards by a running estimate of the return stddev.
+///
+/// Rewards are divided by the running standard deviation of the discounted return accumulated
+/// from the start of each episode and then clipped to `[-clip, clip]`. Rewards are not
+/// shifted so their sign is preserved.
+///
+/// The statistics are shared by all clones of the wrapper.
+/// The reward bounds of the inner environment are not preserved apart from their sign.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NormalizeReward {
+    /// Normalized rewards are clipped to the interval `[-clip, clip]`.
+    pub clip: f64,
+    /// Added to the variance for numerical stability.
+    pub epsilon: f64,
+    /// Running statistics of the discounted return.
+    #[serde(skip)]
+    stats: FeatureStats,
+}
+
+impl NormalizeReward {
+    #[must_use]
+    #[inline]
+    pub fn new(clip: f64) -> Self {
+        assert!(clip > 0.0, "clip must be positive");
+        Self {
+            clip,
+            ..Self::default()
+        }
+    }
+
+    /// Running statistics of the discounted return. Has one feature.
+    #[must_use]
+    #[inline]
+    pub const fn stats(&self) -> &FeatureStats {
+        &self.stats
+    }
+}
+
+impl Default for NormalizeReward {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            clip: 10.0,
+            epsilon: 1e-8,
+            stats: FeatureStats::default(),
+        }
+    }
+}
+
+/// Wrap an environment with running reward normalization.
+pub type WithNormalizedReward<E> = Wrapped<E, NormalizeReward>;
+
+/// Wrapped environment state with the discounted return so far in the episode.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReturnState<T> {
+    pub inner: T,
+    pub discounted_return: f64,
+}
+
+impl<E> EnvStructure for Wrapped<E, NormalizeReward>
+where
+    E: EnvStructure<FeedbackSpace = IntervalSpace<Reward>>,
+{
+    type ObservationSpace = E::ObservationSpace;
+    type ActionSpace = E::ActionSpace;
+    type FeedbackSpace = IntervalSpace<Reward>;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        self.inner.observation_space()
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        self.inner.action_space()
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        let IntervalSpace { low, high } = self.inner.feedback_space();
+        let clip = self.wrapper.clip;
+        IntervalSpace::new(
+            Reward(if low.0 >= 0.0 { 0.0 } else { -clip }),
+            Reward(if high.0 <= 0.0 { 0.0 } else { clip }),
+        )
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.inner.discount_factor()
+    }
+}
+
+impl<E> Environment for Wrapped<E, NormalizeReward>
+where
+    E: Environment<Feedback = Reward> + EnvStructure,
+{
+    type State = ReturnState<E::State>;
+    type Observation = E::Observation;
+    type Action = E::Action;
+    type Feedback = Reward;
+
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        ReturnState {
+            inner: self.inner.initial_state(rng),
+            discounted_return: 0.0,
+        }
+    }
+
+    fn observe(&self, state: &Self::State, rng: &mut Prng) -> Self::Observation {
+        self.inner.observe(&state.inner, rng)
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        rng: &mut Prng,
+        logger: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        let (inner_successor, Reward(reward)) = self.inner.step(state.inner, action, rng, logger);
+
+        let discounted_return = state.discounted_return * self.inner.discount_factor() + reward;
+        self.wrapper.stats.update(&[discounted_return]);
+        // The variance of a single return is zero so use 1 until there are at least two.
+        let variance = self
+            .wrapper
+            .stats
+            .get(0)
+            .filter(|stats| stats.count() >= 2)
+            .and_then(|stats| stats.variance())
+            .unwrap_or(1.0);
+        let clip = self.wrapper.clip;
+        let normalized = (reward / (variance + self.wrapper.epsilon).sqrt()).clamp(-clip, clip);
+
+        let successor = inner_successor.map(|inner| ReturnState {
+            inner,
+            discounted_return,
+        });
+        (successor, Reward(normalized))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)] // expecting exact values
+mod tests {
+    use super::super::super::{testing, BuildEnv, Chain, DeterministicBandit};
+    use super::super::Wrap;
+    use super::*;
+    use rand::SeedableRng;
+
+    fn step_reward<E: Environment<Feedback = Reward>>(env: &E, action: &E::Action) -> f64 {
+        let mut rng = Prng::seed_from_u64(0);
+        let state = env.initial_state(&mut rng);
+        env.step(state, action, &mut rng, &mut ()).1 .0
+    }
+
+    #[test]
+    fn scale_run_default() {
+        testing::check_structured_env(&Chain::default().wrap(ScaleReward::new(-2.0, 1.0)), 1000, 0);
+    }
+
+    #[test]
+    fn scale_build() {
+        let config = Chain::default().wrap(ScaleReward::default());
+        let _env = config.build_env(&mut Prng::seed_from_u64(0)).unwrap();
+    }
+
+    #[test]
+    fn scale_reward() {
+        let env = DeterministicBandit::from_values([0.5, 1.0]).wrap(ScaleReward::new(2.0, -1.0));
+        assert_eq!(step_reward(&env, &0), 0.0);
+        assert_eq!(step_reward(&env, &1), 1.0);
+        assert_eq!(
+            env.feedback_space(),
+            IntervalSpace::new(Reward(0.0), Reward(1.0))
+        );
+    }
+
+    #[test]
+    fn scale_feedback_space() {
+        let env = Chain::default().wrap(ScaleReward::new(-0.5, 1.0));
+        assert_eq!(
+            env.feedback_space(),
+            IntervalSpace::new(Reward(-4.0), Reward(1.0))
+        );
+    }
+
+    #[test]
+    fn scale_unbounded_feedback_space() {
+        let scale = ScaleReward::new(-2.0, 1.0);
+        assert_eq!(
+            scale.map_reward_space(IntervalSpace::new(Reward(0.0), Reward(f64::MAX))),
+            IntervalSpace::new(Reward(f64::MIN), Reward(1.0))
+        );
+    }
+
+    #[test]
+    fn clip_run_default() {
+        testing::check_structured_env(&Chain::default().wrap(ClipReward::default()), 1000, 0);
+    }
+
+    #[test]
+    fn clip_reward() {
+        let env = DeterministicBandit::from_values([-3.0, 0.5]).wrap(ClipReward::new(-1.0, 1.0));
+        assert_eq!(step_reward(&env, &0), -1.0);
+        assert_eq!(step_reward(&env, &1), 0.5);
+    }
+
+    #[test]
+    fn clip_feedback_space() {
+        let env = Chain::default().wrap(ClipReward::new(-1.0, 1.0));
+        assert_eq!(
+            env.feedback_space(),
+            IntervalSpace::new(Reward(0.0), Reward(1.0))
+        );
+    }
+
+    #[test]
+    fn sign_run_default() {
+        testing::check_structured_env(&Chain::default().wrap(SignReward), 1000, 0);
+    }
+
+    #[test]
+    fn sign_reward() {
+        let env = DeterministicBandit::from_values([-3.0, 0.0, 0.5]).wrap(SignReward);
+        assert_eq!(step_reward(&env, &0), -1.0);
+        assert_eq!(step_reward(&env, &1), 0.0);
+        assert_eq!(step_reward(&env, &2), 1.0);
+        assert_eq!(
+            env.feedback_space(),
+            IntervalSpace::new(Reward(-1.0), Reward(1.0))
+        );
+    }
+
+    #[test]
+    fn normalize_run_default() {
+        testing::check_structured_env(&Chain::default().wrap(NormalizeReward::default()), 1000, 0);
+    }
+
+    #[test]
+    fn normalize_feedback_space() {
+        let env = Chain::default().wrap(NormalizeReward::new(5.0));
+        assert_eq!(
+            env.feedback_space(),
+            IntervalSpace::new(Reward(0.0), Reward(5.0))
+        );
+    }
+
+    #[test]
+    fn normalize_reward() {
+        let env = DeterministicBandit::from_values([2.0, 4.0]).wrap(NormalizeReward::default());
+        // Returns: 2, 4 (single-step episodes); stddev = 1
+        step_reward(&env, &0);
+        let reward = step_reward(&env, &1);
+        assert!((reward - 4.0).abs() < 1e-6);
+        assert_eq!(env.wrapper.stats().get(0).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn normalize_first_reward() {
+        let wrapper = NormalizeReward::default();
+        let epsilon = wrapper.epsilon;
+        let env = DeterministicBandit::from_values([0.5]).wrap(wrapper);
+        assert_eq!(step_reward(&env, &0), 0.5 / (1.0 + epsilon).sqrt());
+    }
+}
@@ -0,0 +1,178 @@
+use super::super::{EnvStructure, Environment, Successor};
+use super::Wrapped;
+use crate::logging::StatsLogger;
+use crate::spaces::{OptionSpace, PowerSpace};
+use crate::Prng;
+use serde::{Deserialize, Serialize};
+
+/// Environment wrapper that stacks the most recent `K` observations into each observation.
+///
+/// Each observation is an array of the last `K` inner observations ordered from oldest to newest.
+/// At the start of an episode, the missing history is padded with `None`,
+/// which has all-zero inner features in the [`OptionSpace`] feature encoding.
+///
+/// This allows a feed-forward policy to act on a bounded window of history.
+///
+/// `K` must be positive. Using the wrapper with `K = 0` is a compile-time error.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StackObservations<const K: usize>;
+
+impl<const K: usize> StackObservations<K> {
+    /// Fails to evaluate (at compile time) if `K` is zero.
+    const ASSERT_NONEMPTY: () = assert!(K > 0, "must stack at least one observation");
+
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::ASSERT_NONEMPTY;
+        Self
+    }
+}
+
+/// Wrap an environment to stack the last `K` observations.
+pub type WithStackedObservations<E, const K: usize> = Wrapped<E, StackObservations<K>>;
+
+/// Wrapped environment state with the most recent observations.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+// See <https://github.com/est31/serde-big-array/issues/12#issue-1115462786>
+#[serde(bound(
+    serialize = "T: Serialize, for<'a> O: Serialize + Deserialize<'a>",
+    deserialize = "T: Deserialize<'de>, O: Serialize + Deserialize<'de>"
+))]
+pub struct StackedState<T, O, const K: usize> {
+    pub inner: T,
+    /// Most recent observations from oldest to newest. The last is the current observation.
+    #[serde(with = "serde_big_array::BigArray")]
+    pub observations: [Option<O>; K],
+}
+
+impl<E: EnvStructure, const K: usize> EnvStructure for Wrapped<E, StackObservations<K>> {
+    type ObservationSpace = PowerSpace<OptionSpace<E::ObservationSpace>, K>;
+    type ActionSpace = E::ActionSpace;
+    type FeedbackSpace = E::FeedbackSpace;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        PowerSpace::new(OptionSpace::new(self.inner.observation_space()))
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        self.inner.action_space()
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        self.inner.feedback_space()
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.inner.discount_factor()
+    }
+}
+
+impl<E, const K: usize> Environment for Wrapped<E, StackObservations<K>>
+where
+    E: Environment,
+    E::Observation: Clone,
+{
+    type State = StackedState<E::State, E::Observation, K>;
+    type Observation = [Option<E::Observation>; K];
+    type Action = E::Action;
+    type Feedback = E::Feedback;
+
+    /// The current observation is sampled with the state so that the same observation is stacked
+    /// into the observations of later steps.
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        // Also checked here since the wrapper can be created without `new` (default, deserialize)
+        #[allow(clippy::let_unit_value)]
+        let () = StackObservations::<K>::ASSERT_NONEMPTY;
+        let inner = self.inner.initial_state(rng);
+        let mut observations = array_init::array_init(|_| None);
+        observations[K - 1] = Some(self.inner.observe(&inner, rng));
+        StackedState {
+            inner,
+            observations,
+        }
+    }
+
+    fn observe(&self, state: &Self::State, _: &mut Prng) -> Self::Observation {
+        state.observations.clone()
+    }
+
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        rng: &mut Prng,
+        logger: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        let StackedState {
+            inner,
+            mut observations,
+        } = state;
+        let (inner_successor, feedback) = self.inner.step(inner, action, rng, logger);
+        let successor = inner_successor.map(|inner| {
+            observations.rotate_left(1);
+            observations[K - 1] = Some(self.inner.observe(&inner, rng));
+            StackedState {
+                inner,
+                observations,
+            }
+        });
+        (successor, feedback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{chain::Move, testing, BuildEnv, Chain, MemoryGame};
+    use super::super::Wrap;
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn run_default() {
+        testing::check_structured_env(
+            &Chain::default().wrap(StackObservations::<3>::new()),
+            1000,
+            119,
+        );
+    }
+
+    #[test]
+    fn build() {
+        let config = Chain::default().wrap(StackObservations::<3>::new());
+        let _env = config.build_env(&mut Prng::seed_from_u64(0)).unwrap();
+    }
+
+    #[test]
+    fn stacks_observations() {
+        let mut rng = Prng::seed_from_u64(0);
+        let env = Chain::default().wrap(StackObservations::<3>::new());
+        let state = env.initial_state(&mut rng);
+        assert_eq!(env.observe(&state, &mut rng), [None, None, Some(0)]);
+
+        let (successor, _) = env.step(state, &Move::Right, &mut rng, &mut ());
+        let state = successor.into_continue().unwrap();
+        assert_eq!(env.observe(&state, &mut rng), [None, Some(0), Some(1)]);
+
+        let (successor, _) = env.step(state, &Move::Right, &mut rng, &mut ());
+        let state = successor.into_continue().unwrap();
+        let (successor, _) = env.step(state, &Move::Right, &mut rng, &mut ());
+        let state = successor.into_continue().unwrap();
+        assert_eq!(env.observe(&state, &mut rng), [Some(1), Some(2), Some(3)]);
+    }
+
+    /// Stacking enough observations makes `MemoryGame` fully observable at the decision step.
+    #[test]
+    fn memory_game_decision_observes_initial_state() {
+        let mut rng = Prng::seed_from_u64(1);
+        let env = MemoryGame::new(2, 1).wrap(StackObservations::<2>::new());
+        for _ in 0..10 {
+            let state = env.initial_state(&mut rng);
+            let (initial, _) = state.inner;
+            let (successor, _) = env.step(state, &0, &mut rng, &mut ());
+            let state = successor.into_continue().unwrap();
+            assert_eq!(env.observe(&state, &mut rng), [Some(initial), Some(2)]);
+        }
+    }
+}
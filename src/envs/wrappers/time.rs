@@ -0,0 +1,191 @@
+use super::super::{EnvStructure, Environment, Successor};
+use super::{LatentStepLimit, StepLimitObs, StepLimitObsSpace, VisibleStepLimit, Wrapped};
+use crate::logging::StatsLogger;
+use crate::Prng;
+use serde::{Deserialize, Serialize};
+
+/// An environment with a fixed maximum number of steps per episode.
+pub trait StepLimited: Environment {
+    /// Maximum number of steps per episode.
+    fn max_steps_per_episode(&self) -> u64;
+
+    /// Number of steps remaining in the episode from the given state.
+    fn steps_remaining(&self, state: &Self::State) -> u64;
+}
+
+impl<E: Environment> StepLimited for Wrapped<E, LatentStepLimit> {
+    #[inline]
+    fn max_steps_per_episode(&self) -> u64 {
+        self.wrapper.max_steps_per_episode
+    }
+    #[inline]
+    fn steps_remaining(&self, state: &Self::State) -> u64 {
+        state.steps_remaining
+    }
+}
+
+impl<E: Environment> StepLimited for Wrapped<E, VisibleStepLimit> {
+    #[inline]
+    fn max_steps_per_episode(&self) -> u64 {
+        self.wrapper.max_steps_per_episode
+    }
+    #[inline]
+    fn steps_remaining(&self, state: &Self::State) -> u64 {
+        state.steps_remaining
+    }
+}
+
+/// Environment wrapper that adds the fraction of the episode remaining to each observation.
+///
+/// The inner environment must have a step limit (implement [`StepLimited`]),
+/// for example an environment wrapped with [`LatentStepLimit`].
+/// The fraction is a number between 0 and 1 so that a value function can predict when an episode
+/// will be interrupted by the step limit, as opposed to terminating.
+///
+/// `env.wrap(LatentStepLimit::new(n)).wrap(TimeRemaining)` is equivalent to
+/// `env.wrap(VisibleStepLimit::new(n))`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TimeRemaining;
+
+impl TimeRemaining {
+    #[must_use]
+    #[inline]
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+/// Wrap a step-limited environment to observe the fraction of the episode remaining.
+pub type WithTimeRemaining<E> = Wrapped<E, TimeRemaining>;
+
+impl<E: EnvStructure> EnvStructure for Wrapped<E, TimeRemaining> {
+    type ObservationSpace = StepLimitObsSpace<E::ObservationSpace>;
+    type ActionSpace = E::ActionSpace;
+    type FeedbackSpace = E::FeedbackSpace;
+
+    fn observation_space(&self) -> Self::ObservationSpace {
+        self.inner.observation_space().into()
+    }
+
+    fn action_space(&self) -> Self::ActionSpace {
+        self.inner.action_space()
+    }
+
+    fn feedback_space(&self) -> Self::FeedbackSpace {
+        self.inner.feedback_space()
+    }
+
+    fn discount_factor(&self) -> f64 {
+        self.inner.discount_factor()
+    }
+}
+
+impl<E: StepLimited> Environment for Wrapped<E, TimeRemaining> {
+    type State = E::State;
+    type Observation = StepLimitObs<E::Observation>;
+    type Action = E::Action;
+    type Feedback = E::Feedback;
+
+    #[inline]
+    fn initial_state(&self, rng: &mut Prng) -> Self::State {
+        self.inner.initial_state(rng)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn observe(&self, state: &Self::State, rng: &mut Prng) -> Self::Observation {
+        let remaining =
+            self.inner.steps_remaining(state) as f64 / self.inner.max_steps_per_episode() as f64;
+        StepLimitObs {
+            inner: self.inner.observe(state, rng),
+            remaining,
+        }
+    }
+
+    #[inline]
+    fn step(
+        &self,
+        state: Self::State,
+        action: &Self::Action,
+        rng: &mut Prng,
+        logger: &mut dyn StatsLogger,
+    ) -> (Successor<Self::State>, Self::Feedback) {
+        self.inner.step(state, action, rng, logger)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{chain::Move, testing, BuildEnv, Chain};
+    use super::super::Wrap;
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn run_default() {
+        testing::check_structured_env(
+            &Chain::default()
+                .wrap(LatentStepLimit::default())
+                .wrap(TimeRemaining),
+            1000,
+            119,
+        );
+    }
+
+    #[test]
+    fn build() {
+        let config = Chain::default()
+            .wrap(LatentStepLimit::default())
+            .wrap(TimeRemaining);
+        let _env = config.build_env(&mut Prng::seed_from_u64(0)).unwrap();
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)] // expecting exact values
+    fn time_remaining() {
+        let mut rng = Prng::seed_from_u64(110);
+        let env = Chain::default()
+            .wrap(LatentStepLimit::new(4))
+            .wrap(TimeRemaining);
+        let state = env.initial_state(&mut rng);
+        assert_eq!(env.observe(&state, &mut rng).remaining, 1.0);
+
+        let (successor, _) = env.step(state, &Move::Left, &mut rng, &mut ());
+        let state = successor.into_continue().unwrap();
+        assert_eq!(env.observe(&state, &mut rng).remaining, 0.75);
+
+        let (successor, _) = env.step(state, &Move::Left, &mut rng, &mut ());
+        let state = successor.into_continue().unwrap();
+        let (successor, _) = env.step(state, &Move::Left, &mut rng, &mut ());
+        let state = successor.into_continue().unwrap();
+        assert_eq!(env.observe(&state, &mut rng).remaining, 0.25);
+
+        let (successor, _) = env.step(state, &Move::Left, &mut rng, &mut ());
+        let state = successor.into_interrupt().unwrap();
+        assert_eq!(env.observe(&state, &mut rng).remaining, 0.0);
+    }
+
+    #[test]
+    fn matches_visible_step_limit() {
+        let latent = Chain::default()
+            .wrap(LatentStepLimit::new(5))
+            .wrap(TimeRemaining);
+        let visible = Chain::default().wrap(VisibleStepLimit::new(5));
+        assert_eq!(latent.observation_space(), visible.observation_space());
+
+        let mut latent_rng = Prng::seed_from_u64(1);
+        let mut visible_rng = Prng::seed_from_u64(1);
+        let mut latent_state = latent.initial_state(&mut latent_rng);
+        let mut visible_state = visible.initial_state(&mut visible_rng);
+        for _ in 0..4 {
+            assert_eq!(
+                latent.observe(&latent_state, &mut latent_rng),
+                visible.observe(&visible_state, &mut visible_rng)
+            );
+            let (successor, _) = latent.step(latent_state, &Move::Right, &mut latent_rng, &mut ());
+            latent_state = successor.into_continue().unwrap();
+            let (successor, _) =
+                visible.step(visible_state, &Move::Right, &mut visible_rng, &mut ());
+            visible_state = successor.into_continue().unwrap();
+        }
+    }
+}
@@ -17,8 +17,9 @@
 //!
 //! Environment-actor simulation is performed by [`Steps`](crate::simulation::Steps) and the
 //! resulting [`Step`](crate::simulation::Step) are accessible via an `Iterator` interface.
-//! Training is performed by [`train_serial`](crate::simulation::train_serial) and
-//! [`train_parallel`](crate::simulation::train_parallel).
+//! Training is performed by [`train_serial`](crate::simulation::train_serial),
+//! [`train_parallel`](crate::simulation::train_parallel), and
+//! [`train_async`](crate::simulation::train_async).
 //!
 //! This library uses [PyTorch](https://pytorch.org/) via [tch].
 #![warn(clippy::pedantic)]
@@ -55,9 +56,11 @@ pub mod spaces;
 pub mod torch;
 pub mod utils;
 
-pub use agents::{Actor, Agent, BatchUpdate, BuildAgent};
+pub use agents::{Actor, Agent, BatchActor, BatchUpdate, BuildAgent};
 pub use envs::{BuildEnv, EnvStructure, Environment};
-pub use simulation::{train_parallel, train_serial, Simulation, Step, Steps, StepsIter};
+pub use simulation::{
+    train_async, train_parallel, train_serial, Simulation, Step, Steps, StepsIter,
+};
 
 /// Pseudo-random number generator type used by agents and environments in this crate.
 ///
@@ -1,5 +1,6 @@
+use super::statistics::ScalarStats;
 use super::{Id, LogError, LogValue, StatsLogger};
-use crate::utils::stats::OnlineMeanVariance;
+use crate::utils::stats::StreamingHistogram;
 use std::collections::{btree_map::Entry, BTreeMap};
 use std::ops::Drop;
 use std::time::{Duration, Instant};
@@ -158,13 +159,33 @@ struct CounterSummary {
     initial_value: u64,
 }
 
+/// Maximum number of histogram bins in a vector summary.
+const MAX_VECTOR_BINS: usize = 64;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ChunkSummary {
     Nothing,
-    Counter { increment: u64, initial_value: u64 },
-    Duration { stats: OnlineMeanVariance<f64> },
-    Scalar { stats: OnlineMeanVariance<f64> },
-    Index { counts: Vec<usize> },
+    Counter {
+        increment: u64,
+        initial_value: u64,
+    },
+    Duration {
+        stats: ScalarStats,
+    },
+    Scalar {
+        stats: ScalarStats,
+    },
+    Index {
+        counts: Vec<usize>,
+    },
+    /// Summarized with constant memory regardless of the number of values.
+    Vector {
+        stats: ScalarStats,
+        histogram: StreamingHistogram,
+    },
+    Text {
+        text: String,
+    },
 }
 
 impl From<LogValue> for ChunkSummary {
@@ -176,12 +197,12 @@ impl From<LogValue> for ChunkSummary {
                 initial_value: 0,
             },
             LogValue::Duration(d) => {
-                let mut stats = OnlineMeanVariance::new();
+                let mut stats = ScalarStats::new();
                 stats.push(d.as_secs_f64());
                 Self::Duration { stats }
             }
             LogValue::Scalar(v) => {
-                let mut stats = OnlineMeanVariance::new();
+                let mut stats = ScalarStats::new();
                 stats.push(v);
                 Self::Scalar { stats }
             }
@@ -190,6 +211,16 @@ impl From<LogValue> for ChunkSummary {
                 counts[v] += 1;
                 Self::Index { counts }
             }
+            LogValue::Vector(values) => {
+                let mut stats = ScalarStats::new();
+                let mut histogram = StreamingHistogram::new(MAX_VECTOR_BINS);
+                for value in values {
+                    stats.push(value);
+                    histogram.push(value);
+                }
+                Self::Vector { stats, histogram }
+            }
+            LogValue::Text(text) => Self::Text { text },
         }
     }
 }
@@ -225,6 +256,13 @@ impl ChunkSummary {
                 }
                 counts[v] += 1;
             }
+            (Self::Vector { stats, histogram }, LogValue::Vector(values)) => {
+                for value in values {
+                    stats.push(value);
+                    histogram.push(value);
+                }
+            }
+            (Self::Text { text }, LogValue::Text(t)) => *text = t,
             (summary, value) => {
                 return Err(LogError::IncompatibleValue {
                     prev: summary.loggable_variant_name(),
@@ -246,8 +284,13 @@ impl ChunkSummary {
                 *initial_value += *increment;
                 *increment = 0
             }
-            Self::Duration { stats } | Self::Scalar { stats } => *stats = OnlineMeanVariance::new(),
+            Self::Duration { stats } | Self::Scalar { stats } => *stats = ScalarStats::new(),
             Self::Index { counts } => counts.iter_mut().for_each(|c| *c = 0),
+            Self::Vector { stats, histogram } => {
+                *stats = ScalarStats::new();
+                histogram.clear();
+            }
+            Self::Text { text } => text.clear(),
         }
     }
 
@@ -262,6 +305,11 @@ impl ChunkSummary {
             Self::Duration { stats: _ } => "Duration",
             Self::Scalar { stats: _ } => "Scalar",
             Self::Index { counts: _ } => "Index",
+            Self::Vector {
+                stats: _,
+                histogram: _,
+            } => "Vector",
+            Self::Text { text: _ } => "Text",
         }
     }
 }
@@ -0,0 +1,219 @@
+//! CSV logger
+use super::chunk::{ChunkLogger, ChunkSummary, Chunker, SummaryWriter};
+use super::{ByTime, Id, LogError, LogValue, StatsLogger, SummaryRecord};
+use log::warn;
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Column names of the CSV file.
+const HEADER: &str =
+    "id,chunk,elapsed,kind,count,mean,stddev,min,max,p05,median,p95,counter,increment,histogram,text";
+
+/// Logger that writes grouped summaries to a CSV file.
+#[derive(Debug)]
+pub struct CsvLogger<C: Chunker = ByTime, W: Write + Send = BufWriter<File>>(
+    ChunkLogger<C, CsvBackend<W>>,
+);
+
+impl<C: Chunker> CsvLogger<C> {
+    /// Create a logger that writes to a new file at `path`, truncating any existing file.
+    ///
+    /// # Errors
+    /// If the file cannot be created.
+    #[inline]
+    pub fn create<P: AsRef<Path>>(chunker: C, path: P) -> io::Result<Self> {
+        Ok(Self(ChunkLogger::new(chunker, CsvBackend::create(path)?)))
+    }
+}
+
+impl<C: Chunker, W: Write + Send> CsvLogger<C, W> {
+    #[inline]
+    pub fn new(chunker: C, writer: W) -> Self {
+        Self(ChunkLogger::new(chunker, CsvBackend::new(writer)))
+    }
+}
+
+impl<C: Chunker, W: Write + Send> StatsLogger for CsvLogger<C, W> {
+    #[inline]
+    fn group_start(&mut self) {
+        self.0.group_start()
+    }
+    #[inline]
+    fn group_log(&mut self, id: Id, value: LogValue) -> Result<(), LogError> {
+        self.0.group_log(id, value)
+    }
+    #[inline]
+    fn group_end(&mut self) {
+        self.0.group_end()
+    }
+    #[inline]
+    fn flush(&mut self) {
+        self.0.flush()
+    }
+}
+
+/// Logging backend that writes summaries to a CSV file.
+///
+/// The first row is a header and each following row is a [`SummaryRecord`].
+/// Fields that do not apply to a summary are empty
+/// and the histogram counts are separated by spaces.
+/// Text containing commas, quotes, or newlines is quoted.
+#[derive(Debug)]
+pub struct CsvBackend<W = BufWriter<File>> {
+    writer: W,
+    chunk_index: u64,
+}
+
+impl CsvBackend {
+    /// Create a backend that writes to a new file at `path`, truncating any existing file.
+    ///
+    /// # Errors
+    /// If the file cannot be created.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W> CsvBackend<W> {
+    pub const fn new(writer: W) -> Self {
+        Self {
+            writer,
+            chunk_index: 0,
+        }
+    }
+}
+
+impl<W: Write + Send> SummaryWriter for CsvBackend<W> {
+    fn write_summaries<'a, I>(&mut self, summaries: I, elapsed: Duration)
+    where
+        I: Iterator<Item = (&'a Id, &'a ChunkSummary)>,
+    {
+        if self.chunk_index == 0 {
+            writeln!(self.writer, "{HEADER}")
+                .unwrap_or_else(|err| warn!("error writing log summary: {err}"));
+        }
+        for (id, summary) in summaries {
+            let record = SummaryRecord::new(id, summary, self.chunk_index, elapsed);
+            write_record(&mut self.writer, &record)
+                .unwrap_or_else(|err| warn!("error writing log summary: {err}"));
+        }
+        self.chunk_index += 1;
+        self.writer
+            .flush()
+            .unwrap_or_else(|err| warn!("error flushing log file: {err}"));
+    }
+}
+
+/// Write a record as a CSV row.
+fn write_record<W: Write>(writer: &mut W, record: &SummaryRecord) -> io::Result<()> {
+    write!(
+        writer,
+        "{},{},{},{},{},{},{},{},{},{},{},{},{},{},",
+        escape(&record.id),
+        record.chunk,
+        record.elapsed,
+        record.kind.as_str(),
+        OptionField(record.count),
+        OptionField(record.mean),
+        OptionField(record.stddev),
+        OptionField(record.min),
+        OptionField(record.max),
+        OptionField(record.p05),
+        OptionField(record.median),
+        OptionField(record.p95),
+        OptionField(record.counter),
+        OptionField(record.increment),
+    )?;
+    if let Some(histogram) = &record.histogram {
+        for (i, count) in histogram.iter().enumerate() {
+            if i > 0 {
+                write!(writer, " ")?;
+            }
+            write!(writer, "{count}")?;
+        }
+    }
+    write!(writer, ",")?;
+    if let Some(text) = &record.text {
+        write!(writer, "{}", escape(text))?;
+    }
+    writeln!(writer)
+}
+
+/// Quote a CSV field if necessary.
+fn escape(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Display an optional value as an empty string if `None`.
+struct OptionField<T>(Option<T>);
+
+impl<T: Display> Display for OptionField<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.0 {
+            Some(value) => value.fmt(f),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::statistics::ScalarStats;
+    use super::*;
+
+    #[test]
+    fn write_rows() {
+        let mut backend = CsvBackend::new(Vec::new());
+        let scalar_id: Id = ["agent", "loss"].into_iter().collect();
+        let stats: ScalarStats = [1.0, 3.0].into_iter().collect();
+        let scalar = ChunkSummary::Scalar { stats };
+        let counter_id = Id::from("steps");
+        let counter = ChunkSummary::Counter {
+            increment: 5,
+            initial_value: 10,
+        };
+        backend.write_summaries(
+            [(&scalar_id, &scalar), (&counter_id, &counter)].into_iter(),
+            Duration::from_secs(2),
+        );
+        let index_id = Id::from("a,b");
+        let index = ChunkSummary::Index {
+            counts: vec![1, 0, 2],
+        };
+        let vector_id = Id::from("obs");
+        let vector = ChunkSummary::from(LogValue::Vector(vec![-1.0, 3.0]));
+        let text_id = Id::from("render");
+        let text = ChunkSummary::Text {
+            text: "a \"b\"\nc".into(),
+        };
+        backend.write_summaries(
+            [
+                (&index_id, &index),
+                (&vector_id, &vector),
+                (&text_id, &text),
+            ]
+            .into_iter(),
+            Duration::from_millis(500),
+        );
+
+        let text = String::from_utf8(backend.writer).unwrap();
+        let expected = "\
+id,chunk,elapsed,kind,count,mean,stddev,min,max,p05,median,p95,counter,increment,histogram,text
+agent/loss,0,2,scalar,2,2,1,1,3,1.1,2,2.9,,,,
+steps,0,2,counter,,,,,,,,,15,5,,
+\"a,b\",1,0.5,index,3,,,,,,,,,,1 0 2,
+obs,1,0.5,vector,2,1,2,-1,3,-0.8,1,2.8,,,,
+render,1,0.5,text,,,,,,,,,,,,\"a \"\"b\"\"
+c\"
+";
+        assert_eq!(text, expected);
+    }
+}
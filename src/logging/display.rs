@@ -1,6 +1,7 @@
 //! Command-line logger
 use super::chunk::{ChunkLogger, ChunkSummary, Chunker, SummaryWriter};
-use super::{ByTime, Id, LogError, LogValue, StatsLogger};
+use super::statistics::ScalarStats;
+use super::{ByTime, Id, LogError, LogValue, Statistic, StatisticsConfig, StatsLogger};
 use crate::utils::fmt::{DisplayFn, Frequency, PrettyPrint};
 use std::fmt;
 use std::time::Duration;
@@ -13,7 +14,13 @@ pub struct DisplayLogger<C: Chunker = ByTime>(ChunkLogger<C, DisplayBackend>);
 impl<C: Chunker> DisplayLogger<C> {
     #[inline]
     pub fn new(chunker: C) -> Self {
-        Self(ChunkLogger::new(chunker, DisplayBackend))
+        Self(ChunkLogger::new(chunker, DisplayBackend::default()))
+    }
+
+    /// Create a logger that displays the given statistics of scalar and duration summaries.
+    #[inline]
+    pub fn with_statistics(chunker: C, statistics: StatisticsConfig) -> Self {
+        Self(ChunkLogger::new(chunker, DisplayBackend::new(statistics)))
     }
 }
 
@@ -37,8 +44,24 @@ impl<C: Chunker> StatsLogger for DisplayLogger<C> {
 }
 
 /// Logging backend that displays summaries to standard output.
-#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
-pub struct DisplayBackend;
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DisplayBackend {
+    statistics: StatisticsConfig,
+}
+
+impl Default for DisplayBackend {
+    fn default() -> Self {
+        Self::new(StatisticsConfig::new([Statistic::Mean, Statistic::Stddev]))
+    }
+}
+
+impl DisplayBackend {
+    /// Create a backend that displays the given statistics of scalar and duration summaries.
+    #[must_use]
+    pub const fn new(statistics: StatisticsConfig) -> Self {
+        Self { statistics }
+    }
+}
 
 impl SummaryWriter for DisplayBackend {
     fn write_summaries<'a, I>(&mut self, summaries: I, elapsed: Duration)
@@ -48,10 +71,15 @@ impl SummaryWriter for DisplayBackend {
         let elapsed = &elapsed;
         println!();
         for (id, summary) in summaries {
+            let statistics = self.statistics.statistics(id);
             println!(
                 "{:<24} {}",
                 Paint::fixed(35, id),
-                DisplaySummary { summary, elapsed }
+                DisplaySummary {
+                    summary,
+                    elapsed,
+                    statistics
+                }
             );
         }
     }
@@ -61,6 +89,7 @@ impl SummaryWriter for DisplayBackend {
 struct DisplaySummary<'a> {
     summary: &'a ChunkSummary,
     elapsed: &'a Duration,
+    statistics: &'a [Statistic],
 }
 
 impl<'a> fmt::Display for DisplaySummary<'a> {
@@ -91,23 +120,10 @@ impl<'a> fmt::Display for DisplaySummary<'a> {
                 Ok(())
             }
             ChunkSummary::Duration { stats } => {
-                if stats.count() > 0 {
-                    let mean = stats.mean().unwrap();
-                    write!(f, "{:.4}", PrettyPrint(Duration::from_secs_f64(mean)))?;
-                    if stats.count() > 1 {
-                        write!(
-                            f,
-                            " {}",
-                            Paint::fixed(
-                                8,
-                                DisplayFn(|f| write!(
-                                    f,
-                                    "(σ {:.4})",
-                                    PrettyPrint(Duration::from_secs_f64(stats.stddev().unwrap()))
-                                ))
-                            )
-                        )?;
-                    }
+                fmt_stats(f, stats, self.statistics, |f, x| {
+                    write!(f, "{:.4}", PrettyPrint(Duration::from_secs_f64(x)))
+                })?;
+                if let Some(mean) = stats.mean() {
                     write!(
                         f,
                         " {}",
@@ -123,26 +139,9 @@ impl<'a> fmt::Display for DisplaySummary<'a> {
                 }
                 Ok(())
             }
-            ChunkSummary::Scalar { stats } => {
-                if stats.count() > 0 {
-                    write!(f, "{:.3}", PrettyPrint(stats.mean().unwrap()))?;
-                    if stats.count() > 1 {
-                        write!(
-                            f,
-                            " {}",
-                            Paint::fixed(
-                                8,
-                                DisplayFn(|f| write!(
-                                    f,
-                                    "(σ {:.3})",
-                                    PrettyPrint(stats.stddev().unwrap())
-                                ))
-                            )
-                        )?;
-                    }
-                }
-                Ok(())
-            }
+            ChunkSummary::Scalar { stats } => fmt_stats(f, stats, self.statistics, |f, x| {
+                write!(f, "{:.3}", PrettyPrint(x))
+            }),
             ChunkSummary::Index { counts } => {
                 let n: usize = counts.iter().sum();
                 write!(f, "(n {})  [", n)?;
@@ -157,8 +156,97 @@ impl<'a> fmt::Display for DisplaySummary<'a> {
                 }
                 write!(f, "]%")
             }
+            ChunkSummary::Vector {
+                stats,
+                histogram: _,
+            } => fmt_vector(f, stats),
+            ChunkSummary::Text { text } => {
+                let mut lines = text.lines();
+                write!(f, "{}", lines.next().unwrap_or_default())?;
+                if lines.next().is_some() {
+                    write!(f, " {}", Paint::fixed(8, "…"))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Display the selected statistics of a scalar or duration summary.
+///
+/// The mean is displayed unlabelled and the other statistics are labelled and dimmed.
+/// The standard deviation is omitted if there is only a single value.
+fn fmt_stats<F>(
+    f: &mut fmt::Formatter,
+    stats: &ScalarStats,
+    statistics: &[Statistic],
+    fmt_value: F,
+) -> fmt::Result
+where
+    F: Fn(&mut fmt::Formatter, f64) -> fmt::Result,
+{
+    if stats.count() == 0 {
+        return Ok(());
+    }
+    let mut first = true;
+    for &statistic in statistics {
+        if statistic == Statistic::Stddev && stats.count() <= 1 {
+            continue;
+        }
+        let value = stats.get(statistic).unwrap();
+        if first {
+            first = false;
+        } else {
+            write!(f, " ")?;
+        }
+        if statistic == Statistic::Mean {
+            fmt_value(f, value)?;
+        } else {
+            let label = match statistic {
+                Statistic::Stddev => "σ",
+                _ => statistic.as_str(),
+            };
+            write!(
+                f,
+                "{}",
+                Paint::fixed(
+                    8,
+                    DisplayFn(|f| {
+                        write!(f, "({label} ")?;
+                        fmt_value(f, value)?;
+                        write!(f, ")")
+                    })
+                )
+            )?;
         }
     }
+    Ok(())
+}
+
+/// Display the size, mean, standard deviation, and range of a vector summary.
+fn fmt_vector(f: &mut fmt::Formatter, stats: &ScalarStats) -> fmt::Result {
+    write!(f, "(n {})", stats.count())?;
+    if let Some(mean) = stats.mean() {
+        write!(f, "  {:.3}", PrettyPrint(mean))?;
+        if stats.count() > 1 {
+            let stddev = stats.stddev().unwrap();
+            write!(
+                f,
+                " {}",
+                Paint::fixed(
+                    8,
+                    DisplayFn(|f| write!(f, "(σ {:.3})", PrettyPrint(stddev)))
+                )
+            )?;
+        }
+        write!(
+            f,
+            "  [{:.3}, {:.3}]",
+            PrettyPrint(stats.min().unwrap()),
+            PrettyPrint(stats.max().unwrap())
+        )?;
+    }
+    Ok(())
 }
 
 /// Divide a `Duration` by `u64`
@@ -171,3 +259,22 @@ fn duration_div_u64(d: Duration, x: u64) -> Duration {
         d.div_f64(x as f64)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_selected_statistics() {
+        Paint::disable();
+        let summary = ChunkSummary::Scalar {
+            stats: [1.0, 2.0, 6.0].into_iter().collect(),
+        };
+        let display = DisplaySummary {
+            summary: &summary,
+            elapsed: &Duration::from_secs(1),
+            statistics: &[Statistic::Mean, Statistic::Min, Statistic::Max],
+        };
+        assert_eq!(display.to_string(), "3.000 (min 1.000) (max 6.000)");
+    }
+}
@@ -0,0 +1,399 @@
+//! Filtering logger
+use super::{Id, LogError, LogValue, StatsLogger};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Pattern matching hierarchical [`Id`s](Id).
+///
+/// Written like an `Id` path with `/`-separated components.
+/// Within a component, `*` matches any sequence of characters.
+/// A component that is exactly `**` matches any number (including zero) of components.
+///
+/// # Examples
+/// * `agent_update/step/*` matches `agent_update/step/loss` but not `agent_update/step/a/b`.
+/// * `agent_update/step/**` matches `agent_update/step` and all IDs below it.
+/// * `**/loss*` matches `loss`, `policy/loss_clip`, and `a/b/loss`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct IdPattern {
+    components: Vec<String>,
+}
+
+impl IdPattern {
+    /// Create a pattern from a `/`-separated string.
+    #[must_use]
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            components: pattern.split('/').map(String::from).collect(),
+        }
+    }
+
+    /// Check whether the pattern matches an ID.
+    #[must_use]
+    pub fn matches(&self, id: &Id) -> bool {
+        let components: Vec<&str> = id.components().collect();
+        match_components(&self.components, &components)
+    }
+}
+
+impl From<&str> for IdPattern {
+    fn from(pattern: &str) -> Self {
+        Self::new(pattern)
+    }
+}
+
+impl From<String> for IdPattern {
+    fn from(pattern: String) -> Self {
+        Self::new(&pattern)
+    }
+}
+
+impl From<IdPattern> for String {
+    fn from(pattern: IdPattern) -> Self {
+        pattern.components.join("/")
+    }
+}
+
+impl fmt::Display for IdPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.components.join("/"))
+    }
+}
+
+/// Match a sequence of pattern components against ID components.
+fn match_components(pattern: &[String], components: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => components.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=components.len()).any(|i| match_components(rest, &components[i..]))
+        }
+        Some((first, rest)) => match components.split_first() {
+            Some((component, components_rest)) => {
+                match_glob(first.as_bytes(), component.as_bytes())
+                    && match_components(rest, components_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Match a single component against a pattern in which `*` matches any sequence of characters.
+fn match_glob(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|i| match_glob(rest, &text[i..])),
+        Some((c, rest)) => match text.split_first() {
+            Some((t, text_rest)) => c == t && match_glob(rest, text_rest),
+            None => false,
+        },
+    }
+}
+
+/// A rule of a [`LogFilter`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterRule {
+    /// Log values with matching IDs.
+    Include(IdPattern),
+    /// Drop values with matching IDs.
+    Exclude(IdPattern),
+    /// Log values with matching IDs only in every `every`-th log group.
+    ///
+    /// Log groups are counted separately for each sampling rule
+    /// and only groups that log a matching ID are counted.
+    Sample { pattern: IdPattern, every: u64 },
+}
+
+/// Declarative configuration of which values a [`FilteredLogger`] passes on.
+///
+/// Rules are applied in order and later rules take precedence:
+/// a value is logged if the last matching include / exclude rule is an include rule
+/// (or if no such rule matches) and it is in a group selected by the last matching sample rule
+/// (if any).
+///
+/// Counter increments are never dropped by sampling so that counter values remain accurate,
+/// but they can be excluded.
+///
+/// # Example
+/// ```
+/// use relearn::logging::{ByTime, DisplayLogger, LogFilter, StatsLogger};
+///
+/// let filter = LogFilter::new()
+///     .exclude("agent_update/step/**")
+///     .include("agent_update/step/loss")
+///     .sample("sim/step/**", 10);
+/// let logger = DisplayLogger::new(ByTime::default()).filtered(filter);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LogFilter {
+    pub rules: Vec<FilterRule>,
+}
+
+impl LogFilter {
+    /// Create a filter that logs everything.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Add a rule that logs values with IDs matching `pattern`.
+    #[must_use]
+    pub fn include<P: Into<IdPattern>>(mut self, pattern: P) -> Self {
+        self.rules.push(FilterRule::Include(pattern.into()));
+        self
+    }
+
+    /// Add a rule that drops values with IDs matching `pattern`.
+    #[must_use]
+    pub fn exclude<P: Into<IdPattern>>(mut self, pattern: P) -> Self {
+        self.rules.push(FilterRule::Exclude(pattern.into()));
+        self
+    }
+
+    /// Add a rule that logs values with IDs matching `pattern` only in every `every`-th group.
+    ///
+    /// # Panics
+    /// If `every` is 0.
+    #[must_use]
+    pub fn sample<P: Into<IdPattern>>(mut self, pattern: P, every: u64) -> Self {
+        assert!(every > 0, "sampling interval must be positive");
+        self.rules.push(FilterRule::Sample {
+            pattern: pattern.into(),
+            every,
+        });
+        self
+    }
+
+    /// Decide how to handle values with the given ID.
+    fn decide(&self, id: &Id) -> Decision {
+        let mut decision = Decision {
+            include: true,
+            sample_rule: None,
+        };
+        for (i, rule) in self.rules.iter().enumerate() {
+            match rule {
+                FilterRule::Include(pattern) if pattern.matches(id) => decision.include = true,
+                FilterRule::Exclude(pattern) if pattern.matches(id) => decision.include = false,
+                FilterRule::Sample { pattern, .. } if pattern.matches(id) => {
+                    decision.sample_rule = Some(i)
+                }
+                _ => {}
+            }
+        }
+        decision
+    }
+}
+
+/// How to handle values with a particular ID.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct Decision {
+    include: bool,
+    /// Index of the sample rule that applies, if any.
+    sample_rule: Option<usize>,
+}
+
+/// Group sampling state of a sample rule.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+struct Sampler {
+    /// Number of groups in which the rule has matched.
+    num_groups: u64,
+    /// Whether matching values are logged in the current group, once determined.
+    in_group: Option<bool>,
+}
+
+/// Logger that passes on only the values selected by a [`LogFilter`].
+///
+/// Useful for giving different backends different views of the same logs,
+/// for example `(display_logger.filtered(filter), tensorboard_logger)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilteredLogger<L> {
+    logger: L,
+    filter: LogFilter,
+    /// Sampling state for each rule (only used for sample rules).
+    samplers: Vec<Sampler>,
+    /// Cached decisions by ID
+    decisions: HashMap<Id, Decision>,
+}
+
+impl<L> FilteredLogger<L> {
+    /// Create a logger that passes the values selected by `filter` on to `logger`.
+    ///
+    /// # Panics
+    /// If `filter` has a sample rule with `every` equal to 0.
+    #[must_use]
+    pub fn new(logger: L, filter: LogFilter) -> Self {
+        assert!(
+            filter
+                .rules
+                .iter()
+                .all(|rule| !matches!(rule, FilterRule::Sample { every: 0, .. })),
+            "sampling interval must be positive"
+        );
+        let samplers = vec![Sampler::default(); filter.rules.len()];
+        Self {
+            logger,
+            filter,
+            samplers,
+            decisions: HashMap::new(),
+        }
+    }
+
+    /// Whether values for the given sample rule are logged in the current group.
+    fn sample(&mut self, rule: usize) -> bool {
+        let FilterRule::Sample { every, .. } = self.filter.rules[rule] else {
+            unreachable!("not a sample rule")
+        };
+        let sampler = &mut self.samplers[rule];
+        if let Some(in_group) = sampler.in_group {
+            return in_group;
+        }
+        let in_group = sampler.num_groups.is_multiple_of(every);
+        sampler.num_groups += 1;
+        sampler.in_group = Some(in_group);
+        in_group
+    }
+}
+
+impl<L: StatsLogger> StatsLogger for FilteredLogger<L> {
+    #[inline]
+    fn group_start(&mut self) {
+        self.logger.group_start()
+    }
+
+    fn group_log(&mut self, id: Id, value: LogValue) -> Result<(), LogError> {
+        let decision = match self.decisions.get(&id) {
+            Some(decision) => *decision,
+            None => {
+                let decision = self.filter.decide(&id);
+                self.decisions.insert(id.clone(), decision);
+                decision
+            }
+        };
+        if !decision.include {
+            return Ok(());
+        }
+        if let Some(rule) = decision.sample_rule {
+            if !matches!(value, LogValue::CounterIncrement(_)) && !self.sample(rule) {
+                return Ok(());
+            }
+        }
+        self.logger.group_log(id, value)
+    }
+
+    fn group_end(&mut self) {
+        for sampler in &mut self.samplers {
+            sampler.in_group = None;
+        }
+        self.logger.group_end()
+    }
+
+    #[inline]
+    fn flush(&mut self) {
+        self.logger.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Logger that records the logged IDs.
+    #[derive(Debug, Default)]
+    struct IdLogger {
+        ids: Vec<String>,
+    }
+
+    impl StatsLogger for IdLogger {
+        fn group_start(&mut self) {}
+        fn group_log(&mut self, id: Id, _: LogValue) -> Result<(), LogError> {
+            self.ids.push(id.to_string());
+            Ok(())
+        }
+        fn group_end(&mut self) {}
+        fn flush(&mut self) {}
+    }
+
+    fn id(path: &'static str) -> Id {
+        path.split('/').collect()
+    }
+
+    #[test]
+    fn pattern_exact() {
+        let pattern = IdPattern::new("a/b");
+        assert!(pattern.matches(&id("a/b")));
+        assert!(!pattern.matches(&id("a")));
+        assert!(!pattern.matches(&id("a/b/c")));
+    }
+
+    #[test]
+    fn pattern_component_wildcard() {
+        let pattern = IdPattern::new("a/*/loss*");
+        assert!(pattern.matches(&id("a/b/loss")));
+        assert!(pattern.matches(&id("a/c/loss_clip")));
+        assert!(!pattern.matches(&id("a/loss")));
+        assert!(!pattern.matches(&id("a/b/c/loss")));
+        assert!(!pattern.matches(&id("a/b/value_loss")));
+    }
+
+    #[test]
+    fn pattern_recursive_wildcard() {
+        let pattern = IdPattern::new("a/**");
+        assert!(pattern.matches(&id("a")));
+        assert!(pattern.matches(&id("a/b/c")));
+        assert!(!pattern.matches(&id("b/a")));
+
+        let pattern = IdPattern::new("**/loss");
+        assert!(pattern.matches(&id("loss")));
+        assert!(pattern.matches(&id("a/b/loss")));
+        assert!(!pattern.matches(&id("a/loss/b")));
+    }
+
+    #[test]
+    fn later_rules_take_precedence() {
+        let filter = LogFilter::new()
+            .exclude("agent_update/step/**")
+            .include("agent_update/step/loss");
+        let mut logger = FilteredLogger::new(IdLogger::default(), filter);
+        for path in [
+            "agent_update/step/loss",
+            "agent_update/step/entropy",
+            "agent_update/count",
+        ] {
+            logger.log(id(path), LogValue::Scalar(0.0)).unwrap();
+        }
+        assert_eq!(
+            logger.logger.ids,
+            ["agent_update/step/loss", "agent_update/count"]
+        );
+    }
+
+    #[test]
+    fn sample_every_nth_group() {
+        let filter = LogFilter::new().sample("step/**", 3);
+        let mut logger = FilteredLogger::new(IdLogger::default(), filter);
+        for _ in 0..5 {
+            let mut group = (&mut logger).group();
+            group.log(id("step/reward"), LogValue::Scalar(0.0)).unwrap();
+            group.log(id("step/action"), LogValue::Scalar(0.0)).unwrap();
+            group
+                .log(id("step/count"), LogValue::CounterIncrement(1))
+                .unwrap();
+            group.log(id("episode"), LogValue::Scalar(0.0)).unwrap();
+        }
+        let count = |path| logger.logger.ids.iter().filter(|id| *id == path).count();
+        assert_eq!(count("step/reward"), 2);
+        assert_eq!(count("step/action"), 2);
+        assert_eq!(count("step/count"), 5);
+        assert_eq!(count("episode"), 5);
+    }
+
+    #[test]
+    fn deserialize_filter() {
+        let filter: LogFilter = serde_json::from_str(
+            r#"{"rules": [{"exclude": "a/**"}, {"sample": {"pattern": "b/*", "every": 2}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(filter, LogFilter::new().exclude("a/**").sample("b/*", 2));
+    }
+}
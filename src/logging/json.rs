@@ -0,0 +1,182 @@
+//! JSON lines logger
+use super::chunk::{ChunkLogger, ChunkSummary, Chunker, SummaryWriter};
+use super::{ByTime, Id, LogError, LogValue, StatsLogger, SummaryRecord};
+use log::warn;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Logger that writes grouped summaries to a JSON lines file.
+#[derive(Debug)]
+pub struct JsonLogger<C: Chunker = ByTime, W: Write + Send = BufWriter<File>>(
+    ChunkLogger<C, JsonBackend<W>>,
+);
+
+impl<C: Chunker> JsonLogger<C> {
+    /// Create a logger that writes to a new file at `path`, truncating any existing file.
+    ///
+    /// # Errors
+    /// If the file cannot be created.
+    #[inline]
+    pub fn create<P: AsRef<Path>>(chunker: C, path: P) -> io::Result<Self> {
+        Ok(Self(ChunkLogger::new(chunker, JsonBackend::create(path)?)))
+    }
+}
+
+impl<C: Chunker, W: Write + Send> JsonLogger<C, W> {
+    #[inline]
+    pub fn new(chunker: C, writer: W) -> Self {
+        Self(ChunkLogger::new(chunker, JsonBackend::new(writer)))
+    }
+}
+
+impl<C: Chunker, W: Write + Send> StatsLogger for JsonLogger<C, W> {
+    #[inline]
+    fn group_start(&mut self) {
+        self.0.group_start()
+    }
+    #[inline]
+    fn group_log(&mut self, id: Id, value: LogValue) -> Result<(), LogError> {
+        self.0.group_log(id, value)
+    }
+    #[inline]
+    fn group_end(&mut self) {
+        self.0.group_end()
+    }
+    #[inline]
+    fn flush(&mut self) {
+        self.0.flush()
+    }
+}
+
+/// Logging backend that writes summaries as JSON lines.
+///
+/// Each summary is written as a [`SummaryRecord`] JSON object on its own line.
+#[derive(Debug)]
+pub struct JsonBackend<W = BufWriter<File>> {
+    writer: W,
+    chunk_index: u64,
+}
+
+impl JsonBackend {
+    /// Create a backend that writes to a new file at `path`, truncating any existing file.
+    ///
+    /// # Errors
+    /// If the file cannot be created.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W> JsonBackend<W> {
+    pub const fn new(writer: W) -> Self {
+        Self {
+            writer,
+            chunk_index: 0,
+        }
+    }
+}
+
+impl<W: Write + Send> SummaryWriter for JsonBackend<W> {
+    fn write_summaries<'a, I>(&mut self, summaries: I, elapsed: Duration)
+    where
+        I: Iterator<Item = (&'a Id, &'a ChunkSummary)>,
+    {
+        for (id, summary) in summaries {
+            let record = SummaryRecord::new(id, summary, self.chunk_index, elapsed);
+            serde_json::to_writer(&mut self.writer, &record)
+                .map_err(io::Error::from)
+                .and_then(|()| writeln!(self.writer))
+                .unwrap_or_else(|err| warn!("error writing log summary: {err}"));
+        }
+        self.chunk_index += 1;
+        self.writer
+            .flush()
+            .unwrap_or_else(|err| warn!("error flushing log file: {err}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::statistics::ScalarStats;
+    use super::super::SummaryKind;
+    use super::*;
+
+    #[test]
+    fn write_records() {
+        let mut backend = JsonBackend::new(Vec::new());
+        let scalar_id: Id = ["agent", "loss"].into_iter().collect();
+        let stats: ScalarStats = [1.0, 3.0].into_iter().collect();
+        let scalar = ChunkSummary::Scalar { stats };
+        let counter_id = Id::from("steps");
+        let counter = ChunkSummary::Counter {
+            increment: 5,
+            initial_value: 10,
+        };
+        backend.write_summaries(
+            [(&scalar_id, &scalar), (&counter_id, &counter)].into_iter(),
+            Duration::from_secs(2),
+        );
+        let index_id = Id::from("action");
+        let index = ChunkSummary::Index {
+            counts: vec![1, 0, 2],
+        };
+        let vector_id = Id::from("obs");
+        let vector = ChunkSummary::from(LogValue::Vector(vec![2.0, -1.0, 5.0]));
+        let text_id = Id::from("render");
+        let text = ChunkSummary::Text {
+            text: "# Title\nbody".into(),
+        };
+        backend.write_summaries(
+            [
+                (&index_id, &index),
+                (&vector_id, &vector),
+                (&text_id, &text),
+            ]
+            .into_iter(),
+            Duration::from_secs(1),
+        );
+
+        let text = String::from_utf8(backend.writer).unwrap();
+        let records: Vec<SummaryRecord> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 5);
+
+        assert_eq!(records[0].id, "agent/loss");
+        assert_eq!(records[0].chunk, 0);
+        assert_eq!(records[0].kind, SummaryKind::Scalar);
+        assert_eq!(records[0].count, Some(2));
+        assert_eq!(records[0].mean, Some(2.0));
+        assert_eq!(records[0].stddev, Some(1.0));
+        assert_eq!(records[0].min, Some(1.0));
+        assert_eq!(records[0].max, Some(3.0));
+        assert_eq!(records[0].median, Some(2.0));
+
+        assert_eq!(records[1].kind, SummaryKind::Counter);
+        assert_eq!(records[1].counter, Some(15));
+        assert_eq!(records[1].increment, Some(5));
+        assert_eq!(records[1].mean, None);
+
+        assert_eq!(records[2].id, "action");
+        assert_eq!(records[2].chunk, 1);
+        assert_eq!(records[2].count, Some(3));
+        assert_eq!(records[2].histogram, Some(vec![1, 0, 2]));
+        #[allow(clippy::float_cmp)]
+        {
+            assert_eq!(records[2].elapsed, 1.0);
+        }
+
+        assert_eq!(records[3].kind, SummaryKind::Vector);
+        assert_eq!(records[3].count, Some(3));
+        assert_eq!(records[3].mean, Some(2.0));
+        assert_eq!(records[3].min, Some(-1.0));
+        assert_eq!(records[3].max, Some(5.0));
+
+        assert_eq!(records[4].kind, SummaryKind::Text);
+        assert_eq!(records[4].text.as_deref(), Some("# Title\nbody"));
+        assert_eq!(records[4].count, None);
+    }
+}
@@ -2,14 +2,26 @@
 mod chunk;
 mod chunk_by_counter;
 mod chunk_by_time;
+mod csv;
 mod display;
+mod filter;
+mod json;
+mod record;
+mod statistics;
 mod tensorboard;
+mod tensorboard_reader;
 
 pub use chunk::ChunkLogger;
 pub use chunk_by_counter::ByCounter;
 pub use chunk_by_time::ByTime;
+pub use csv::{CsvBackend, CsvLogger};
 pub use display::{DisplayBackend, DisplayLogger};
+pub use filter::{FilterRule, FilteredLogger, IdPattern, LogFilter};
+pub use json::{JsonBackend, JsonLogger};
+pub use record::{SummaryKind, SummaryRecord};
+pub use statistics::{Statistic, StatisticsConfig};
 pub use tensorboard::{TensorBoardBackend, TensorBoardLogger};
+pub use tensorboard_reader::{ReadEventsError, ScalarPoint, TensorBoardScalars};
 
 use smallvec::SmallVec;
 use std::borrow::Cow;
@@ -79,6 +91,15 @@ pub trait StatsLogger: Send {
         ScopedLogger::new(scope, self)
     }
 
+    /// Wrap this logger such that only the values selected by `filter` are logged.
+    #[inline]
+    fn filtered(self, filter: LogFilter) -> FilteredLogger<Self>
+    where
+        Self: Sized,
+    {
+        FilteredLogger::new(self, filter)
+    }
+
     // Convenience functions
 
     /// Log an increment to a named counter (convenience function).
@@ -131,6 +152,22 @@ pub trait StatsLogger: Send {
         self.log(name.into(), LogValue::Index { value, size })
             .unwrap()
     }
+
+    /// Log a named batch of scalar values to be summarized as a histogram (convenience function).
+    ///
+    /// Panics if this name was previously used to log a value of a different type.
+    #[inline]
+    fn log_vector(&mut self, name: &'static str, values: Vec<f64>) {
+        self.log(name.into(), LogValue::Vector(values)).unwrap()
+    }
+
+    /// Log a named text or markdown string (convenience function).
+    ///
+    /// Panics if this name was previously used to log a value of a different type.
+    #[inline]
+    fn log_text(&mut self, name: &'static str, text: String) {
+        self.log(name.into(), LogValue::Text(text)).unwrap()
+    }
 }
 
 /// Implement `StatsLogger` for a deref-able wrapper type generic over `T: StatsLogger + ?Sized`.
@@ -182,7 +219,14 @@ pub enum LogValue {
     CounterIncrement(u64),
     Duration(Duration),
     Scalar(f64),
-    Index { value: usize, size: usize },
+    Index {
+        value: usize,
+        size: usize,
+    },
+    /// A batch of scalar samples. Summarized as a histogram.
+    Vector(Vec<f64>),
+    /// Text, may be formatted as markdown. Only the most recent text in a chunk is kept.
+    Text(String),
 }
 
 impl From<f64> for LogValue {
@@ -197,6 +241,18 @@ impl From<Duration> for LogValue {
     }
 }
 
+impl From<Vec<f64>> for LogValue {
+    fn from(values: Vec<f64>) -> Self {
+        Self::Vector(values)
+    }
+}
+
+impl From<String> for LogValue {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
 impl LogValue {
     const fn variant_name(&self) -> &'static str {
         use LogValue::*;
@@ -206,6 +262,8 @@ impl LogValue {
             Duration(_) => "Duration",
             Scalar(_) => "Scalar",
             Index { value: _, size: _ } => "Index",
+            Vector(_) => "Vector",
+            Text(_) => "Text",
         }
     }
 }
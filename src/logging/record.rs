@@ -0,0 +1,150 @@
+//! Machine-readable summary records
+use super::chunk::ChunkSummary;
+use super::statistics::{ScalarStats, Statistic};
+use super::Id;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Machine-readable record of the summary of one statistic over one chunk.
+///
+/// Written by [`CsvBackend`](super::CsvBackend) and [`JsonBackend`](super::JsonBackend).
+/// Fields that do not apply to the summary kind are `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SummaryRecord {
+    /// Statistic identifier
+    pub id: String,
+    /// Chunk index, starting from 0.
+    pub chunk: u64,
+    /// Duration of the chunk in seconds.
+    pub elapsed: f64,
+    /// Kind of summary.
+    pub kind: SummaryKind,
+    /// Number of values logged in the chunk. For duration, scalar, index, and vector summaries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+    /// Mean of the values logged in the chunk. Durations are in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mean: Option<f64>,
+    /// Standard deviation of the values logged in the chunk. Durations are in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stddev: Option<f64>,
+    /// Minimum of the values logged in the chunk. Durations are in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Maximum of the values logged in the chunk. Durations are in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// Estimated 5th percentile of the values logged in the chunk. Durations are in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p05: Option<f64>,
+    /// Estimated median of the values logged in the chunk. Durations are in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub median: Option<f64>,
+    /// Estimated 95th percentile of the values logged in the chunk. Durations are in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p95: Option<f64>,
+    /// Counter value at the end of the chunk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counter: Option<u64>,
+    /// Counter increment over the chunk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub increment: Option<u64>,
+    /// Number of times each index value was logged in the chunk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub histogram: Option<Vec<usize>>,
+    /// Most recent text logged in the chunk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// The kind of a [`SummaryRecord`], corresponding to the logged [`LogValue`](super::LogValue).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryKind {
+    Nothing,
+    Counter,
+    Duration,
+    Scalar,
+    Index,
+    Vector,
+    Text,
+}
+
+impl SummaryKind {
+    /// Name of the kind as it appears in serialized records.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Nothing => "nothing",
+            Self::Counter => "counter",
+            Self::Duration => "duration",
+            Self::Scalar => "scalar",
+            Self::Index => "index",
+            Self::Vector => "vector",
+            Self::Text => "text",
+        }
+    }
+}
+
+impl SummaryRecord {
+    /// Create a record from a chunk summary.
+    pub(super) fn new(id: &Id, summary: &ChunkSummary, chunk: u64, elapsed: Duration) -> Self {
+        let mut record = Self {
+            id: id.to_string(),
+            chunk,
+            elapsed: elapsed.as_secs_f64(),
+            kind: SummaryKind::Nothing,
+            count: None,
+            mean: None,
+            stddev: None,
+            min: None,
+            max: None,
+            p05: None,
+            median: None,
+            p95: None,
+            counter: None,
+            increment: None,
+            histogram: None,
+            text: None,
+        };
+        match summary {
+            ChunkSummary::Nothing => {}
+            ChunkSummary::Counter {
+                increment,
+                initial_value,
+            } => {
+                record.kind = SummaryKind::Counter;
+                record.counter = Some(initial_value + increment);
+                record.increment = Some(*increment);
+            }
+            ChunkSummary::Duration { stats } => record.set_stats(SummaryKind::Duration, stats),
+            ChunkSummary::Scalar { stats } => record.set_stats(SummaryKind::Scalar, stats),
+            ChunkSummary::Index { counts } => {
+                record.kind = SummaryKind::Index;
+                record.count = Some(counts.iter().sum::<usize>() as u64);
+                record.histogram = Some(counts.clone());
+            }
+            ChunkSummary::Vector {
+                stats,
+                histogram: _,
+            } => record.set_stats(SummaryKind::Vector, stats),
+            ChunkSummary::Text { text } => {
+                record.kind = SummaryKind::Text;
+                record.text = Some(text.clone());
+            }
+        }
+        record
+    }
+
+    fn set_stats(&mut self, kind: SummaryKind, stats: &ScalarStats) {
+        self.kind = kind;
+        self.count = Some(stats.count());
+        self.mean = stats.mean();
+        self.stddev = stats.stddev();
+        self.min = stats.min();
+        self.max = stats.max();
+        self.p05 = stats.get(Statistic::P05);
+        self.median = stats.get(Statistic::Median);
+        self.p95 = stats.get(Statistic::P95);
+    }
+}
@@ -0,0 +1,269 @@
+//! Summary statistics of scalar values
+use super::Id;
+use crate::utils::stats::{OnlineMeanVariance, P2Quantile};
+use serde::{Deserialize, Serialize};
+use std::iter::FromIterator;
+
+/// A summary statistic of a series of scalar values.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Statistic {
+    Mean,
+    Stddev,
+    Min,
+    Max,
+    /// 5th percentile (streaming estimate)
+    P05,
+    /// 50th percentile (streaming estimate)
+    Median,
+    /// 95th percentile (streaming estimate)
+    P95,
+}
+
+impl Statistic {
+    /// All statistics.
+    pub const ALL: [Self; 7] = [
+        Self::Mean,
+        Self::Stddev,
+        Self::Min,
+        Self::Max,
+        Self::P05,
+        Self::Median,
+        Self::P95,
+    ];
+
+    /// Short name of the statistic. Used as the tag suffix in tensorboard.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Mean => "mean",
+            Self::Stddev => "stddev",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::P05 => "p05",
+            Self::Median => "median",
+            Self::P95 => "p95",
+        }
+    }
+}
+
+/// Summary statistics of a series of scalar values with support for online updates.
+///
+/// Tracks the mean, variance, minimum, maximum, and streaming estimates of the
+/// 5th, 50th, and 95th percentiles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalarStats {
+    mean_variance: OnlineMeanVariance<f64>,
+    min: f64,
+    max: f64,
+    /// Estimates of the 5th, 50th, and 95th percentiles
+    quantiles: [P2Quantile; 3],
+}
+
+impl Default for ScalarStats {
+    fn default() -> Self {
+        Self {
+            mean_variance: OnlineMeanVariance::new(),
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            quantiles: [
+                P2Quantile::new(0.05),
+                P2Quantile::new(0.5),
+                P2Quantile::new(0.95),
+            ],
+        }
+    }
+}
+
+impl ScalarStats {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a new value to the statistics.
+    pub fn push(&mut self, value: f64) {
+        self.mean_variance.push(value);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        for quantile in &mut self.quantiles {
+            quantile.push(value);
+        }
+    }
+
+    /// The number of accumulated values.
+    #[inline]
+    #[must_use]
+    pub const fn count(&self) -> u64 {
+        self.mean_variance.count()
+    }
+
+    /// The mean of all accumulated values if at least one value has been accumulated.
+    #[inline]
+    #[must_use]
+    pub const fn mean(&self) -> Option<f64> {
+        self.mean_variance.mean()
+    }
+
+    /// The (population) standard deviation if at least one value has been accumulated.
+    #[inline]
+    #[must_use]
+    pub fn stddev(&self) -> Option<f64> {
+        self.mean_variance.stddev()
+    }
+
+    /// The minimum value if at least one value has been accumulated.
+    #[inline]
+    #[must_use]
+    pub const fn min(&self) -> Option<f64> {
+        self.nonempty(self.min)
+    }
+
+    /// The maximum value if at least one value has been accumulated.
+    #[inline]
+    #[must_use]
+    pub const fn max(&self) -> Option<f64> {
+        self.nonempty(self.max)
+    }
+
+    /// Evaluate a statistic if at least one value has been accumulated.
+    #[must_use]
+    pub fn get(&self, statistic: Statistic) -> Option<f64> {
+        match statistic {
+            Statistic::Mean => self.mean(),
+            Statistic::Stddev => self.stddev(),
+            Statistic::Min => self.min(),
+            Statistic::Max => self.max(),
+            Statistic::P05 => self.quantiles[0].quantile(),
+            Statistic::Median => self.quantiles[1].quantile(),
+            Statistic::P95 => self.quantiles[2].quantile(),
+        }
+    }
+
+    const fn nonempty(&self, value: f64) -> Option<f64> {
+        if self.count() > 0 {
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
+impl Extend<f64> for ScalarStats {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        for value in iter {
+            self.push(value)
+        }
+    }
+}
+
+impl FromIterator<f64> for ScalarStats {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        let mut s = Self::default();
+        s.extend(iter);
+        s
+    }
+}
+
+/// Selects which scalar [`Statistic`s](Statistic) a logging backend writes for each [`Id`].
+///
+/// Statistics are configured by `Id` prefix; the longest matching prefix takes precedence.
+/// IDs that do not match any prefix use the default statistics.
+///
+/// # Example
+/// ```
+/// use relearn::logging::{Id, Statistic, StatisticsConfig};
+///
+/// let config = StatisticsConfig::new([Statistic::Mean])
+///     .with_prefix(["sim", "ep"], [Statistic::Mean, Statistic::Min, Statistic::Max]);
+/// let id: Id = ["sim", "ep", "length"].into_iter().collect();
+/// assert_eq!(config.statistics(&id).len(), 3);
+/// assert_eq!(config.statistics(&"loss".into()), [Statistic::Mean]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StatisticsConfig {
+    default: Vec<Statistic>,
+    /// Pairs of `Id` prefix components and the statistics for IDs with that prefix.
+    prefixes: Vec<(Vec<&'static str>, Vec<Statistic>)>,
+}
+
+impl StatisticsConfig {
+    /// Create a configuration that writes `statistics` for all IDs.
+    pub fn new<T: IntoIterator<Item = Statistic>>(statistics: T) -> Self {
+        Self {
+            default: statistics.into_iter().collect(),
+            prefixes: Vec::new(),
+        }
+    }
+
+    /// Write `statistics` for IDs starting with the components of `prefix`.
+    #[must_use]
+    pub fn with_prefix<P, T>(mut self, prefix: P, statistics: T) -> Self
+    where
+        P: IntoIterator<Item = &'static str>,
+        T: IntoIterator<Item = Statistic>,
+    {
+        self.prefixes.push((
+            prefix.into_iter().collect(),
+            statistics.into_iter().collect(),
+        ));
+        self
+    }
+
+    /// The statistics to write for the given ID, in order.
+    #[must_use]
+    pub fn statistics(&self, id: &Id) -> &[Statistic] {
+        self.prefixes
+            .iter()
+            .filter(|(prefix, _)| {
+                let mut components = id.components();
+                prefix.iter().all(|p| components.next() == Some(*p))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(&self.default, |(_, statistics)| statistics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn scalar_stats() {
+        let stats: ScalarStats = [2.0, -1.0, 4.0, 3.0].into_iter().collect();
+        assert_eq!(stats.count(), 4);
+        assert_eq!(stats.mean(), Some(2.0));
+        assert_eq!(stats.min(), Some(-1.0));
+        assert_eq!(stats.max(), Some(4.0));
+        assert_eq!(stats.get(Statistic::Median), Some(2.5));
+    }
+
+    #[test]
+    fn scalar_stats_empty() {
+        let stats = ScalarStats::new();
+        for statistic in Statistic::ALL {
+            assert_eq!(stats.get(statistic), None);
+        }
+    }
+
+    #[test]
+    fn config_longest_prefix() {
+        let config = StatisticsConfig::new([Statistic::Mean])
+            .with_prefix(["a"], [Statistic::Max])
+            .with_prefix(["a", "b"], [Statistic::Min])
+            .with_prefix(["a", "b", "c", "d"], [Statistic::Stddev]);
+        let id = |components: &[&'static str]| components.iter().copied().collect::<Id>();
+        assert_eq!(config.statistics(&id(&["x"])), [Statistic::Mean]);
+        assert_eq!(config.statistics(&id(&["a"])), [Statistic::Max]);
+        assert_eq!(config.statistics(&id(&["a", "x"])), [Statistic::Max]);
+        assert_eq!(config.statistics(&id(&["a", "b", "c"])), [Statistic::Min]);
+        assert_eq!(config.statistics(&id(&["x", "a", "b"])), [Statistic::Mean]);
+    }
+}
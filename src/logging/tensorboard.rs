@@ -1,10 +1,19 @@
 //! Tensorboard logger
 use super::chunk::{ChunkLogger, ChunkSummary, Chunker, SummaryWriter};
-use super::{ByTime, Id, LogError, LogValue, StatsLogger};
+use super::statistics::ScalarStats;
+use super::{ByTime, Id, LogError, LogValue, Statistic, StatisticsConfig, StatsLogger};
+use crate::utils::stats::StreamingHistogram;
 use std::fmt::{self, Write};
 use std::path::Path;
 use std::time::Duration;
-use tensorboard_rs::summary_writer::SummaryWriter as TbSummaryWriter;
+use tensorboard_proto::summary::{Summary, Summary_Value};
+use tensorboard_proto::tensor::TensorProto;
+use tensorboard_proto::types::DataType;
+use tensorboard_rs::summary::{histogram_raw, scalar};
+use tensorboard_rs::summary_writer::FileWriter;
+
+/// Number of histogram buckets used to summarize vector values.
+const NUM_VECTOR_BUCKETS: usize = 30;
 
 /// Logger that saves grouped summaries to a tensorboard file.
 #[derive(Debug)]
@@ -15,6 +24,19 @@ impl<C: Chunker> TensorBoardLogger<C> {
     pub fn new<P: AsRef<Path>>(chunker: C, log_dir: P) -> Self {
         Self(ChunkLogger::new(chunker, TensorBoardBackend::new(log_dir)))
     }
+
+    /// Create a logger that writes the given statistics of scalar and duration summaries.
+    #[inline]
+    pub fn with_statistics<P: AsRef<Path>>(
+        chunker: C,
+        log_dir: P,
+        statistics: StatisticsConfig,
+    ) -> Self {
+        Self(ChunkLogger::new(
+            chunker,
+            TensorBoardBackend::with_statistics(log_dir, statistics),
+        ))
+    }
 }
 
 impl<C: Chunker> StatsLogger for TensorBoardLogger<C> {
@@ -37,31 +59,44 @@ impl<C: Chunker> StatsLogger for TensorBoardLogger<C> {
 }
 
 /// Logging backend that saves summaries to a tensorboard file.
+///
+/// The mean of a scalar or duration summary is written with the summary ID as the tag.
+/// Other statistics are written with the statistic name appended to the tag,
+/// like `agent/loss/max`.
 pub struct TensorBoardBackend {
-    writer: TbSummaryWriter,
+    writer: FileWriter,
     summary_index: usize,
+    statistics: StatisticsConfig,
 }
 
 impl fmt::Debug for TensorBoardBackend {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("TensorBoardBackend")
-            // TODO: Output the log dir if TbSummaryWriter adds support for reading it.
-            // .field("log_dir", &self.writer.get_logdir())
+            .field("log_dir", &self.writer.get_logdir())
+            .field("summary_index", &self.summary_index)
+            .field("statistics", &self.statistics)
             .finish()
     }
 }
 
 impl TensorBoardBackend {
+    /// Create a backend that writes the mean of scalar and duration summaries.
     pub fn new<P: AsRef<Path>>(log_dir: P) -> Self {
+        Self::with_statistics(log_dir, StatisticsConfig::new([Statistic::Mean]))
+    }
+
+    /// Create a backend that writes the given statistics of scalar and duration summaries.
+    pub fn with_statistics<P: AsRef<Path>>(log_dir: P, statistics: StatisticsConfig) -> Self {
         Self {
-            writer: TbSummaryWriter::new(log_dir),
+            writer: FileWriter::new(log_dir),
             summary_index: 0,
+            statistics,
         }
     }
 }
 
 impl SummaryWriter for TensorBoardBackend {
-    fn write_summaries<'a, I>(&mut self, summaries: I, elapsed: Duration)
+    fn write_summaries<'a, I>(&mut self, summaries: I, _: Duration)
     where
         I: Iterator<Item = (&'a Id, &'a ChunkSummary)>,
     {
@@ -74,52 +109,202 @@ impl SummaryWriter for TensorBoardBackend {
         for (id, summary) in summaries {
             tag_buffer.clear();
             write!(tag_buffer, "{}", id).unwrap();
-            self.write_summary(&tag_buffer, summary, elapsed);
+            let statistics = self.statistics.statistics(id);
+            if let Some(summary) = tb_summary(&tag_buffer, summary, statistics) {
+                self.writer.add_summary(summary, self.summary_index);
+            }
         }
         self.summary_index += 1;
         self.writer.flush();
     }
 }
 
-impl TensorBoardBackend {
-    fn write_summary(&mut self, tag: &str, summary: &ChunkSummary, _: Duration) {
-        use ChunkSummary::*;
+/// Convert a chunk summary into a tensorboard summary, if there is anything to write.
+fn tb_summary(tag: &str, summary: &ChunkSummary, statistics: &[Statistic]) -> Option<Summary> {
+    use ChunkSummary::*;
 
-        #[allow(clippy::cast_possible_truncation)]
-        match summary {
-            Counter {
-                increment,
-                initial_value,
-            } => {
-                self.writer
-                    .add_scalar(tag, (initial_value + increment) as f32, self.summary_index)
-            }
-            Duration { stats } | Scalar { stats } => {
-                if let Some(mean) = stats.mean() {
-                    self.writer.add_scalar(tag, mean as f32, self.summary_index)
-                }
-            }
-            Index { counts } => {
-                // Treat as a histogram with bucket boundaries half way between each integer.
-                self.writer.add_histogram_raw(
-                    tag,
-                    -0.5,                                                         // min
-                    counts.len() as f64 - 0.5,                                    // max
-                    counts.iter().map(|n| *n as f64).sum(),                       // num
-                    counts.iter().enumerate().map(|(i, n)| (i * n) as f64).sum(), // sum
-                    counts
-                        .iter()
-                        .enumerate()
-                        .map(|(i, n)| (i * i * n) as f64)
-                        .sum(), // sum_squares
-                    &(0..counts.len())
-                        .map(|i| i as f64 + 0.5)
-                        .collect::<Vec<_>>(), // bucket_limits
-                    &counts.iter().map(|n| *n as f64).collect::<Vec<_>>(),        // bucket counts
-                    self.summary_index,
-                )
-            }
-            Nothing => {}
+    #[allow(clippy::cast_possible_truncation)]
+    match summary {
+        Counter {
+            increment,
+            initial_value,
+        } => Some(scalar(tag, (initial_value + increment) as f32)),
+        Duration { stats } | Scalar { stats } => stats_summary(tag, stats, statistics),
+        Index { counts } => {
+            // Treat as a histogram with bucket boundaries half way between each integer.
+            Some(histogram_raw(
+                tag,
+                -0.5,                                                         // min
+                counts.len() as f64 - 0.5,                                    // max
+                counts.iter().map(|n| *n as f64).sum(),                       // num
+                counts.iter().enumerate().map(|(i, n)| (i * n) as f64).sum(), // sum
+                counts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, n)| (i * i * n) as f64)
+                    .sum(), // sum_squares
+                &(0..counts.len())
+                    .map(|i| i as f64 + 0.5)
+                    .collect::<Vec<_>>(), // bucket_limits
+                &counts.iter().map(|n| *n as f64).collect::<Vec<_>>(),        // bucket counts
+            ))
+        }
+        Vector { stats, histogram } => vector_histogram(tag, stats, histogram),
+        Text { text } => Some(text_summary(tag, text)),
+        Nothing => None,
+    }
+}
+
+/// Scalar summaries of the selected statistics.
+///
+/// The mean is tagged with `tag` and the other statistics with `tag/<statistic>`.
+/// Returns `None` if there are no values.
+fn stats_summary(tag: &str, stats: &ScalarStats, statistics: &[Statistic]) -> Option<Summary> {
+    if stats.count() == 0 {
+        return None;
+    }
+    let mut summary = Summary::new();
+    for &statistic in statistics {
+        let mut value = Summary_Value::new();
+        if statistic == Statistic::Mean {
+            value.set_tag(tag.to_string());
+        } else {
+            value.set_tag(format!("{tag}/{}", statistic.as_str()));
         }
+        #[allow(clippy::cast_possible_truncation)]
+        value.set_simple_value(stats.get(statistic).unwrap() as f32);
+        summary.mut_value().push(value);
+    }
+    Some(summary)
+}
+
+/// Histogram summary of a vector of values with equal-width buckets spanning the values.
+///
+/// The bucket counts are approximated from the bins of the streaming histogram.
+/// Returns `None` if there are no values.
+fn vector_histogram(
+    tag: &str,
+    stats: &ScalarStats,
+    histogram: &StreamingHistogram,
+) -> Option<Summary> {
+    let (min, max) = (stats.min()?, stats.max()?);
+    let num = stats.count() as f64;
+    let mean = stats.mean().unwrap();
+    let variance = stats.stddev().unwrap().powi(2);
+
+    let (bucket_limits, bucket_counts) =
+        histogram_buckets(histogram.bins(), min, max, NUM_VECTOR_BUCKETS);
+    Some(histogram_raw(
+        tag,
+        min,
+        max,
+        num,
+        num * mean,                     // sum
+        num * (variance + mean * mean), // sum_squares
+        &bucket_limits,
+        &bucket_counts,
+    ))
+}
+
+/// Divide `[min, max]` into `num_buckets` equal-width buckets and count the values in each.
+///
+/// The values are given as `(value, count)` bins.
+/// Returns the upper limit of each bucket and the bucket counts.
+/// If `min == max` then there is a single bucket containing all of the values.
+fn histogram_buckets(
+    bins: &[(f64, u64)],
+    min: f64,
+    max: f64,
+    num_buckets: usize,
+) -> (Vec<f64>, Vec<f64>) {
+    let width = max - min;
+    #[allow(clippy::float_cmp)]
+    if width == 0.0 || !width.is_finite() {
+        let count = bins.iter().map(|(_, count)| count).sum::<u64>();
+        return (vec![max], vec![count as f64]);
+    }
+    let bucket_limits = (1..=num_buckets)
+        .map(|i| min + width * i as f64 / num_buckets as f64)
+        .collect();
+    let mut bucket_counts = vec![0.0; num_buckets];
+    for &(x, count) in bins {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let i = ((x - min) / width * num_buckets as f64) as usize;
+        // The maximum value belongs in the last bucket
+        bucket_counts[i.min(num_buckets - 1)] += count as f64;
+    }
+    (bucket_limits, bucket_counts)
+}
+
+/// Text summary displayed (as markdown) by the tensorboard text plugin.
+fn text_summary(tag: &str, text: &str) -> Summary {
+    let mut tensor = TensorProto::new();
+    tensor.set_dtype(DataType::DT_STRING);
+    // A scalar (rank 0) string tensor
+    tensor.mut_tensor_shape();
+    tensor.mut_string_val().push(text.as_bytes().to_vec());
+
+    let mut value = Summary_Value::new();
+    value.set_tag(tag.to_string());
+    value
+        .mut_metadata()
+        .mut_plugin_data()
+        .set_plugin_name("text".to_string());
+    value.set_tensor(tensor);
+
+    let mut summary = Summary::new();
+    summary.mut_value().push(value);
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn histogram_buckets_equal_width() {
+        let bins = [(0.0, 1), (0.1, 1), (0.5, 1), (0.9, 1), (1.0, 1), (2.0, 1)];
+        let (limits, counts) = histogram_buckets(&bins, 0.0, 2.0, 4);
+        assert_eq!(limits, [0.5, 1.0, 1.5, 2.0]);
+        assert_eq!(counts, [2.0, 2.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn histogram_buckets_single_value() {
+        let (limits, counts) = histogram_buckets(&[(3.0, 2)], 3.0, 3.0, 4);
+        assert_eq!(limits, [3.0]);
+        assert_eq!(counts, [2.0]);
+    }
+
+    #[test]
+    fn text_summary_plugin() {
+        let summary = text_summary("render", "**bold**");
+        let value = &summary.get_value()[0];
+        assert_eq!(value.get_tag(), "render");
+        assert_eq!(
+            value.get_metadata().get_plugin_data().get_plugin_name(),
+            "text"
+        );
+        assert_eq!(value.get_tensor().get_string_val(), [b"**bold**".to_vec()]);
+    }
+
+    #[test]
+    fn stats_summary_tags() {
+        let stats: ScalarStats = [1.0, 2.0, 6.0].into_iter().collect();
+        let summary = stats_summary("loss", &stats, &[Statistic::Mean, Statistic::Max]).unwrap();
+        let tags: Vec<_> = summary
+            .get_value()
+            .iter()
+            .map(Summary_Value::get_tag)
+            .collect();
+        assert_eq!(tags, ["loss", "loss/max"]);
+        let values: Vec<_> = summary
+            .get_value()
+            .iter()
+            .map(Summary_Value::get_simple_value)
+            .collect();
+        assert_eq!(values, [3.0, 6.0]);
     }
 }
@@ -0,0 +1,293 @@
+//! Read scalar summaries back from tensorboard event files
+use protobuf::{Message, ProtobufError};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tensorboard_proto::event::Event;
+use tensorboard_rs::masked_crc32c::masked_crc32c;
+use thiserror::Error;
+
+/// A single value of a scalar time series.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ScalarPoint {
+    /// Summary step. For [`TensorBoardLogger`](super::TensorBoardLogger) this is the chunk index.
+    pub step: i64,
+    /// Wall clock time in seconds since the Unix epoch.
+    pub wall_time: f64,
+    pub value: f32,
+}
+
+/// Scalar time series read from tensorboard event files, indexed by tag.
+///
+/// Reads files written by [`TensorBoardLogger`](super::TensorBoardLogger).
+/// Non-scalar summaries (histograms and text) are ignored.
+///
+/// # Example
+/// ```no_run
+/// use relearn::logging::TensorBoardScalars;
+///
+/// let scalars = TensorBoardScalars::read_dir("data/cartpole").unwrap();
+/// scalars.assert_final_exceeds("sim/ep/fbk/reward", 100.0);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TensorBoardScalars {
+    series: BTreeMap<String, Vec<ScalarPoint>>,
+}
+
+/// Error reading tensorboard event files.
+#[derive(Error, Debug)]
+pub enum ReadEventsError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("invalid event: {0}")]
+    InvalidEvent(#[from] ProtobufError),
+    #[error("checksum mismatch at byte {offset} of {path}")]
+    ChecksumMismatch { path: PathBuf, offset: usize },
+    #[error("no event files in {0}")]
+    NoEventFiles(PathBuf),
+}
+
+impl TensorBoardScalars {
+    /// Read all event files in a log directory.
+    ///
+    /// Files are read in order of their names, which start with the creation timestamp,
+    /// and the series from each file are concatenated.
+    /// A log directory that is reused by multiple runs contains one file per run.
+    ///
+    /// # Errors
+    /// If the directory contains no event files or any of the files cannot be read.
+    pub fn read_dir<P: AsRef<Path>>(log_dir: P) -> Result<Self, ReadEventsError> {
+        let log_dir = log_dir.as_ref();
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(log_dir)? {
+            let path = entry?.path();
+            let is_event_file = matches!(
+                path.file_name().and_then(|name| name.to_str()),
+                Some(name) if name.contains("tfevents")
+            );
+            if is_event_file && path.is_file() {
+                paths.push(path);
+            }
+        }
+        if paths.is_empty() {
+            return Err(ReadEventsError::NoEventFiles(log_dir.to_path_buf()));
+        }
+        paths.sort();
+
+        let mut scalars = Self::default();
+        for path in paths {
+            scalars.extend_from_file(&path)?;
+        }
+        Ok(scalars)
+    }
+
+    /// Read a single event file.
+    ///
+    /// # Errors
+    /// If the file cannot be read or contains a corrupt record.
+    pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Self, ReadEventsError> {
+        let mut scalars = Self::default();
+        scalars.extend_from_file(path.as_ref())?;
+        Ok(scalars)
+    }
+
+    /// Iterator over all tags with scalar values, in sorted order.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.series.keys().map(String::as_str)
+    }
+
+    /// The time series of a tag, if the tag has any scalar values.
+    #[must_use]
+    pub fn get(&self, tag: &str) -> Option<&[ScalarPoint]> {
+        self.series.get(tag).map(Vec::as_slice)
+    }
+
+    /// The final value of a tag, if the tag has any scalar values.
+    #[must_use]
+    pub fn final_value(&self, tag: &str) -> Option<f32> {
+        self.get(tag)?.last().map(|point| point.value)
+    }
+
+    /// Assert that the final value of a tag is strictly greater than `threshold`.
+    ///
+    /// For scalar and duration summaries written with the default statistics the tag is the
+    /// logged `Id` and the value is the mean over the final chunk.
+    ///
+    /// # Panics
+    /// If the tag has no scalar values or the final value does not exceed `threshold`.
+    #[track_caller]
+    pub fn assert_final_exceeds(&self, tag: &str, threshold: f32) {
+        let value = self.final_value(tag).unwrap_or_else(|| {
+            panic!(
+                "no scalar values for tag {tag:?}; available tags: {:?}",
+                self.tags().collect::<Vec<_>>()
+            )
+        });
+        assert!(
+            value > threshold,
+            "final value of {tag:?} is {value}, expected > {threshold}"
+        );
+    }
+
+    /// Read the scalar summaries from an event file and append them to the series.
+    fn extend_from_file(&mut self, path: &Path) -> Result<(), ReadEventsError> {
+        let data = fs::read(path)?;
+        let checksum_error = |offset| ReadEventsError::ChecksumMismatch {
+            path: path.to_path_buf(),
+            offset,
+        };
+        for record in Records::new(&data) {
+            let record = record.map_err(checksum_error)?;
+            let event = Event::parse_from_bytes(record)?;
+            if !event.has_summary() {
+                continue;
+            }
+            for value in event.get_summary().get_value() {
+                if value.has_simple_value() {
+                    self.series
+                        .entry(value.get_tag().to_string())
+                        .or_default()
+                        .push(ScalarPoint {
+                            step: event.get_step(),
+                            wall_time: event.get_wall_time(),
+                            value: value.get_simple_value(),
+                        });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over the records of a `TFRecord` file.
+///
+/// Each record is a little-endian `u64` data length, the masked CRC-32C of the length,
+/// the data, and the masked CRC-32C of the data.
+/// Yields the data of each record, or the byte offset of a record with a checksum mismatch.
+/// A truncated final record (as from a file that is still being written) is ignored.
+struct Records<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Records<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<&'a [u8], usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset;
+        let rest = &self.data[offset..];
+        let header = rest.get(..12)?;
+        let (len_bytes, len_crc) = header.split_at(8);
+        if masked_crc32c(len_bytes).to_le_bytes() != len_crc {
+            self.offset = self.data.len();
+            return Some(Err(offset));
+        }
+        let len = usize::try_from(u64::from_le_bytes(len_bytes.try_into().unwrap())).ok()?;
+        let record = rest.get(12..12_usize.checked_add(len)?)?;
+        let data_crc = rest.get(12 + len..16 + len)?;
+        if masked_crc32c(record).to_le_bytes() != data_crc {
+            self.offset = self.data.len();
+            return Some(Err(offset));
+        }
+        self.offset += 16 + len;
+        Some(Ok(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{ByCounter, StatsLogger, TensorBoardLogger};
+    use super::*;
+    use std::env;
+    use std::process;
+
+    /// A fresh temporary directory, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("relearn-{name}-{}", process::id()));
+            let _ = fs::remove_dir_all(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write_logs(log_dir: &Path) {
+        let mut logger = TensorBoardLogger::new(ByCounter::of_path(["steps"], 1), log_dir);
+        for i in 0..3 {
+            logger.log_scalar("reward", f64::from(i));
+            logger.log_scalar("reward", f64::from(i) + 1.0);
+            logger.log_counter_increment("steps", 1);
+        }
+        logger.log_text("render", "text is ignored".into());
+    }
+
+    #[test]
+    fn read_logger_output() {
+        let dir = TempDir::new("read-logger-output");
+        write_logs(&dir.0);
+        let scalars = TensorBoardScalars::read_dir(&dir.0).unwrap();
+
+        assert_eq!(scalars.tags().collect::<Vec<_>>(), ["reward", "steps"]);
+        let reward = scalars.get("reward").unwrap();
+        assert_eq!(
+            reward.iter().map(|p| (p.step, p.value)).collect::<Vec<_>>(),
+            [(0, 0.5), (1, 1.5), (2, 2.5)]
+        );
+        assert_eq!(scalars.final_value("steps"), Some(3.0));
+        assert_eq!(scalars.get("render"), None);
+        scalars.assert_final_exceeds("reward", 2.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected > 3")]
+    fn assert_final_exceeds_fails() {
+        let dir = TempDir::new("assert-final-exceeds-fails");
+        write_logs(&dir.0);
+        let scalars = TensorBoardScalars::read_dir(&dir.0).unwrap();
+        scalars.assert_final_exceeds("reward", 3.0);
+    }
+
+    #[test]
+    fn read_corrupt_file() {
+        let dir = TempDir::new("read-corrupt-file");
+        write_logs(&dir.0);
+        let path = fs::read_dir(&dir.0)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let mut data = fs::read(&path).unwrap();
+        let last = data.len() - 5;
+        data[last] ^= 0xFF;
+        fs::write(&path, data).unwrap();
+        assert!(matches!(
+            TensorBoardScalars::read_file(&path),
+            Err(ReadEventsError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn read_dir_without_events() {
+        let dir = TempDir::new("read-dir-without-events");
+        fs::create_dir_all(&dir.0).unwrap();
+        assert!(matches!(
+            TensorBoardScalars::read_dir(&dir.0),
+            Err(ReadEventsError::NoEventFiles(_))
+        ));
+    }
+}
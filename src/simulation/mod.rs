@@ -5,13 +5,15 @@ mod summary;
 mod take_episodes;
 mod take_steps;
 mod train;
+mod vec_steps;
 
 pub use log_steps::LogSteps;
 pub use steps::Steps;
 pub use summary::{OnlineStepsSummary, StepsSummary};
 pub use take_episodes::TakeEpisodes;
 pub use take_steps::TakeAlignedSteps;
-pub use train::{train_parallel, train_serial, TrainParallelConfig};
+pub use train::{train_async, train_parallel, train_serial, TrainAsyncConfig, TrainParallelConfig};
+pub use vec_steps::VecSteps;
 
 use crate::agents::Actor;
 use crate::envs::{EnvStructure, Environment, StructuredEnvironment, Successor};
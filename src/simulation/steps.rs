@@ -27,10 +27,10 @@ where
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-struct EpisodeState<ES, O, TS> {
-    env: ES,
-    observation: O,
-    actor: TS,
+pub(super) struct EpisodeState<ES, O, TS> {
+    pub env: ES,
+    pub observation: O,
+    pub actor: TS,
 }
 
 impl<E, T, R, L> Steps<E, T, R, L>
@@ -1,15 +1,22 @@
-use super::{OnlineStepsSummary, Simulation, Steps, StepsSummary};
-use crate::agents::{buffers::HistoryDataBound, ActorMode, Agent, BatchUpdate, WriteExperience};
+use super::{OnlineStepsSummary, PartialStep, Simulation, Steps, StepsSummary};
+use crate::agents::{
+    buffers::HistoryDataBound, Actor, ActorMode, Agent, BatchUpdate, WriteExperience,
+};
 use crate::envs::{EnvStructure, Environment, StructuredEnvironment};
 use crate::feedback::{Feedback, Summary};
 use crate::logging::{Loggable, StatsLogger};
 use crate::spaces::{LogElementSpace, Space};
+use crate::utils::stats::OnlineMeanVariance;
 use crate::Prng;
+use crossbeam::channel;
 use log::warn;
 use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::iter;
-use std::time::Instant;
+use std::num::NonZeroUsize;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
 /// Train a batch learning agent in this thread.
 pub fn train_serial<T, E>(
@@ -157,25 +164,8 @@ pub fn train_parallel<T, E>(
         })
         .unwrap();
 
-        let mut sim_logger = logger.with_scope("sim").group();
-        let mut episode_logger = (&mut sim_logger).with_scope("ep");
-        let num_episodes = summary.episode_length.count();
-        if num_episodes > 0 {
-            summary
-                .episode_feedback
-                .log("fbk", &mut episode_logger)
-                .unwrap();
-            episode_logger.log_scalar("length_mean", summary.episode_length.mean().unwrap());
-            episode_logger.log_scalar("length_stddev", summary.episode_length.stddev().unwrap());
-        }
-        episode_logger.log_counter_increment("count", num_episodes);
-
-        let mut step_logger = (&mut sim_logger).with_scope("step");
-        summary.step_feedback.log("fbk", &mut step_logger).unwrap();
-        step_logger.log_counter_increment("count", summary.step_feedback.size());
         let update_start = Instant::now();
-        sim_logger.log_duration("time", update_start - collect_start);
-        drop(sim_logger);
+        log_sim_summary(&summary, update_start - collect_start, logger);
 
         agent.batch_update(&mut buffers, &mut *logger);
 
@@ -185,11 +175,339 @@ pub fn train_parallel<T, E>(
     }
 }
 
+/// Configuration for [`train_async`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TrainAsyncConfig {
+    /// Number of agent updates.
+    pub num_periods: usize,
+    /// Number of simulation threads.
+    pub num_workers: usize,
+    /// Minimum number of steps that a worker collects before sending them to the learner.
+    ///
+    /// Each chunk of steps ends at an episode boundary or is interrupted after some slack.
+    pub worker_chunk_steps: NonZeroUsize,
+    /// Maximum policy lag of steps used for agent updates.
+    ///
+    /// The policy lag of a chunk of steps is the number of agent updates that have been performed
+    /// between the creation of the actor that generated the chunk and the use of the chunk.
+    /// Chunks that exceed this lag are discarded.
+    ///
+    /// The lag counts published actors, not policy changes. Actors that share their parameters
+    /// with the agent (like torch agents on the CPU, which use shallow clones of their modules)
+    /// act with the updated policy regardless so their data has no actual lag.
+    pub max_policy_lag: usize,
+    /// Record the log probability with which the actors select each action.
+    ///
+    /// The log probabilities are written to the agent history buffers along with the steps
+    /// (see [`WriteExperience::write_experience_with_log_probs`]) for off-policy corrections.
+    /// Only chunks in which the actor reported every log probability
+    /// (see [`Actor::act_with_log_prob`]) are written with log probabilities.
+    pub record_behaviour_log_probs: bool,
+}
+
+/// A chunk of steps sent from a worker to the learner in [`train_async`].
+struct StepsChunk<O, A, F: Feedback> {
+    worker: usize,
+    /// Number of agent updates performed before creating the actor that generated these steps.
+    policy_version: usize,
+    steps: Vec<PartialStep<O, A, F>>,
+    /// Behaviour policy log probability of each step, if recorded.
+    behaviour_log_probs: Option<Vec<f64>>,
+    summary: StepsSummary<F>,
+}
+
+impl<O, A, F: Feedback> StepsChunk<O, A, F> {
+    /// Simulate a chunk of steps, recording the log probabilities if `actor` records them.
+    fn simulate<E, T>(
+        environment: &E,
+        actor: LockedActor<T>,
+        chunk_size: HistoryDataBound,
+        rngs: &mut (Prng, Prng),
+        worker: usize,
+        policy_version: usize,
+    ) -> Self
+    where
+        E: Environment<Observation = O, Action = A, Feedback = F> + ?Sized,
+        T: Actor<O, A>,
+    {
+        let recorded_log_probs = actor.log_probs;
+        let mut summary = OnlineStepsSummary::default();
+        let steps: Vec<_> = chunk_size
+            .take(Steps::new(environment, actor, &mut rngs.0, &mut rngs.1, ()))
+            .inspect(|step| summary.push(step))
+            .collect();
+        let behaviour_log_probs = recorded_log_probs.and_then(|log_probs| {
+            let mut log_probs = log_probs.take();
+            // The actor may have acted for a step past the end of the chunk.
+            log_probs.truncate(steps.len());
+            log_probs.into_iter().collect()
+        });
+        Self {
+            worker,
+            policy_version,
+            steps,
+            behaviour_log_probs,
+            summary: summary.into(),
+        }
+    }
+}
+
+/// Train a batch learning agent with asynchronous data collection.
+///
+/// Worker threads continuously simulate the environment and send chunks of steps to the learner
+/// (the current thread) without waiting for agent updates.
+/// The learner writes the steps into history buffers, performs an update once enough steps have
+/// been received, and then publishes new actors to the workers.
+/// Since the workers do not wait for the new actors, the collected data may be generated by an
+/// outdated policy. This is only appropriate for agents that can learn from off-policy data.
+/// The amount of policy lag is bounded by [`TrainAsyncConfig::max_policy_lag`].
+///
+/// The agent must support being updated while actors exist.
+/// Actors that share model parameters with the agent are paused while the agent is updated.
+///
+/// The logger is only used by the learner thread.
+#[allow(clippy::trait_duplication_in_bounds)]
+pub fn train_async<T, E>(
+    agent: &mut T,
+    environment: &E,
+    config: &TrainAsyncConfig,
+    rng_env: &mut Prng,
+    rng_agent: &mut Prng,
+    logger: &mut dyn StatsLogger,
+) where
+    T: Agent<E::Observation, E::Action>
+        + BatchUpdate<E::Observation, E::Action, Feedback = E::Feedback>
+        + ?Sized,
+    T::Actor: Send,
+    E: Environment + Sync + ?Sized,
+    E::Observation: Send,
+    E::Action: Send,
+    E::Feedback: Feedback + Send,
+    <E::Feedback as Feedback>::StepSummary: Send,
+    <E::Feedback as Feedback>::EpisodeSummary: Send,
+{
+    assert!(config.num_workers > 0, "must have at least one worker");
+    let mut buffers: Vec<_> = (0..config.num_workers).map(|_| agent.buffer()).collect();
+    let mut thread_rngs: Vec<_> = (0..config.num_workers)
+        .map(|_| {
+            (
+                Prng::from_rng(&mut *rng_env).expect("Prng should be infallible"),
+                Prng::from_rng(&mut *rng_agent).expect("Prng should be infallible"),
+            )
+        })
+        .collect();
+    let chunk_size = HistoryDataBound::with_default_slack(config.worker_chunk_steps.get());
+
+    // Prevents agent updates from running concurrently with actors that might share its model.
+    let update_lock = RwLock::new(());
+
+    crossbeam::scope(|scope| {
+        // Bounded so that workers cannot get too far ahead of the learner.
+        let (steps_sender, steps_receiver) = channel::bounded(config.num_workers);
+        let mut actor_senders = Vec::with_capacity(config.num_workers);
+
+        for (worker, rngs) in thread_rngs.iter_mut().enumerate() {
+            let (actor_sender, actor_receiver) = channel::unbounded();
+            actor_senders.push(actor_sender);
+            let steps_sender = steps_sender.clone();
+            let mut policy_version = 0;
+            let mut actor = agent.actor(ActorMode::Training);
+            let update_lock = &update_lock;
+            let recorded_log_probs = config
+                .record_behaviour_log_probs
+                .then(|| RefCell::new(Vec::new()));
+            scope.spawn(move |_scope| loop {
+                // Switch to the most recently published actor, if any
+                while let Ok((new_version, new_actor)) = actor_receiver.try_recv() {
+                    policy_version = new_version;
+                    actor = new_actor;
+                }
+
+                let chunk = StepsChunk::simulate(
+                    environment,
+                    LockedActor::new(&actor, update_lock, recorded_log_probs.as_ref()),
+                    chunk_size,
+                    rngs,
+                    worker,
+                    policy_version,
+                );
+                // An error means that the learner is done.
+                if steps_sender.send(chunk).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(steps_sender);
+
+        for policy_version in 0..config.num_periods {
+            let collect_start = Instant::now();
+            let update_size = agent.min_update_size();
+
+            let mut summary = StepsSummary::default();
+            let mut num_update_steps = 0;
+            let mut policy_lag = OnlineMeanVariance::new();
+            let mut max_policy_lag = 0;
+            let mut num_dropped_chunks = 0;
+            let mut num_dropped_steps = 0;
+            while num_update_steps == 0 || num_update_steps < update_size.min_steps {
+                let chunk: StepsChunk<_, _, _> = steps_receiver
+                    .recv()
+                    .expect("worker threads should not stop before the learner");
+                let lag = policy_version - chunk.policy_version;
+                if lag > config.max_policy_lag {
+                    num_dropped_chunks += 1;
+                    num_dropped_steps += chunk.steps.len();
+                    continue;
+                }
+                policy_lag.push(lag as f64);
+                max_policy_lag = max_policy_lag.max(lag);
+                num_update_steps += chunk.steps.len();
+                summary += chunk.summary;
+                let buffer = &mut buffers[chunk.worker];
+                match chunk.behaviour_log_probs {
+                    Some(log_probs) => {
+                        buffer.write_experience_with_log_probs(chunk.steps, log_probs)
+                    }
+                    None => buffer.write_experience(chunk.steps),
+                }
+                .unwrap_or_else(|err| warn!("error filling buffer: {}", err));
+            }
+
+            let update_start = Instant::now();
+            log_sim_summary(&summary, update_start - collect_start, logger);
+            let mut lag_logger = logger.with_scope("policy_lag").group();
+            lag_logger.log_scalar("mean", policy_lag.mean().unwrap());
+            lag_logger.log_scalar("max", max_policy_lag as f64);
+            lag_logger.log_counter_increment("dropped_chunks", num_dropped_chunks);
+            lag_logger.log_counter_increment("dropped_steps", num_dropped_steps as u64);
+            drop(lag_logger);
+
+            {
+                let _guard = update_lock.write().unwrap();
+                agent.batch_update(&mut buffers, &mut *logger);
+            }
+
+            let mut agent_logger = logger.with_scope("agent_update").group();
+            agent_logger.log_duration("time", update_start.elapsed());
+            agent_logger.log_counter_increment("count", 1);
+            drop(agent_logger);
+
+            // Publish new actors to the workers.
+            for actor_sender in &actor_senders {
+                // Ignore errors; the worker has stopped and the learner will find out on recv.
+                let _ = actor_sender.send((policy_version + 1, agent.actor(ActorMode::Training)));
+            }
+        }
+
+        // Dropping the receiver signals the workers to stop.
+        drop(steps_receiver);
+    })
+    .unwrap();
+}
+
+/// Actor that holds a shared lock while acting.
+///
+/// Actors may share model parameters with the agent that created them
+/// so the agent must not be updated while they are in use.
+///
+/// Optionally records the log probability of each selected action.
+struct LockedActor<'a, T> {
+    actor: T,
+    lock: &'a RwLock<()>,
+    log_probs: Option<&'a RefCell<Vec<Option<f64>>>>,
+}
+
+impl<'a, T> LockedActor<'a, T> {
+    const fn new(
+        actor: T,
+        lock: &'a RwLock<()>,
+        log_probs: Option<&'a RefCell<Vec<Option<f64>>>>,
+    ) -> Self {
+        Self {
+            actor,
+            lock,
+            log_probs,
+        }
+    }
+}
+
+impl<T, O, A> Actor<O, A> for LockedActor<'_, T>
+where
+    T: Actor<O, A>,
+{
+    type EpisodeState = T::EpisodeState;
+
+    fn initial_state(&self, rng: &mut Prng) -> Self::EpisodeState {
+        let _guard = self.lock.read().unwrap();
+        self.actor.initial_state(rng)
+    }
+
+    fn act(&self, episode_state: &mut Self::EpisodeState, observation: &O, rng: &mut Prng) -> A {
+        let _guard = self.lock.read().unwrap();
+        match self.log_probs {
+            Some(log_probs) => {
+                let (action, log_prob) =
+                    self.actor
+                        .act_with_log_prob(episode_state, observation, rng);
+                log_probs.borrow_mut().push(log_prob);
+                action
+            }
+            None => self.actor.act(episode_state, observation, rng),
+        }
+    }
+}
+
+/// Log a summary of the simulation steps collected for an agent update.
+fn log_sim_summary<F: Feedback>(
+    summary: &StepsSummary<F>,
+    collect_time: Duration,
+    logger: &mut dyn StatsLogger,
+) {
+    let mut sim_logger = logger.with_scope("sim").group();
+    let mut episode_logger = (&mut sim_logger).with_scope("ep");
+    let num_episodes = summary.episode_length.count();
+    if num_episodes > 0 {
+        summary
+            .episode_feedback
+            .log("fbk", &mut episode_logger)
+            .unwrap();
+        episode_logger.log_scalar("length_mean", summary.episode_length.mean().unwrap());
+        episode_logger.log_scalar("length_stddev", summary.episode_length.stddev().unwrap());
+    }
+    episode_logger.log_counter_increment("count", num_episodes);
+
+    let mut step_logger = (&mut sim_logger).with_scope("step");
+    summary.step_feedback.log("fbk", &mut step_logger).unwrap();
+    step_logger.log_counter_increment("count", summary.step_feedback.size());
+    sim_logger.log_duration("time", collect_time);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::agents::{testing, BuildAgent, TabularQLearningAgentConfig};
     use crate::envs::DeterministicBandit;
+    use crate::logging::{Id, LogError, LogValue};
+
+    /// Logger that records the maximum policy lag of each update.
+    #[derive(Debug, Default)]
+    struct PolicyLagLogger {
+        max_lags: Vec<f64>,
+    }
+
+    impl StatsLogger for PolicyLagLogger {
+        fn group_start(&mut self) {}
+        fn group_log(&mut self, id: Id, value: LogValue) -> Result<(), LogError> {
+            if let LogValue::Scalar(lag) = value {
+                if id.to_string() == "policy_lag/max" {
+                    self.max_lags.push(lag);
+                }
+            }
+            Ok(())
+        }
+        fn group_end(&mut self) {}
+        fn flush(&mut self) {}
+    }
 
     #[test]
     fn train_parallel_tabular_q_bandit() {
@@ -217,4 +535,62 @@ mod tests {
 
         testing::eval_deterministic_bandit(agent.actor(ActorMode::Evaluation), &env, 0.9);
     }
+
+    #[test]
+    fn train_async_tabular_q_bandit() {
+        let config = TrainAsyncConfig {
+            num_periods: 10,
+            num_workers: 4,
+            worker_chunk_steps: NonZeroUsize::new(25).unwrap(),
+            max_policy_lag: 2,
+            record_behaviour_log_probs: true,
+        };
+        let mut rng_env = Prng::seed_from_u64(0);
+        let mut rng_actor = Prng::seed_from_u64(1);
+
+        let env = DeterministicBandit::from_values(vec![0.0, 1.0]);
+        let mut agent = TabularQLearningAgentConfig::default()
+            .build_agent(&env, &mut rng_actor)
+            .unwrap();
+
+        train_async(
+            &mut agent,
+            &env,
+            &config,
+            &mut rng_env,
+            &mut rng_actor,
+            &mut (),
+        );
+
+        testing::eval_deterministic_bandit(agent.actor(ActorMode::Evaluation), &env, 0.9);
+    }
+
+    #[test]
+    fn train_async_no_policy_lag() {
+        let config = TrainAsyncConfig {
+            num_periods: 5,
+            num_workers: 2,
+            worker_chunk_steps: NonZeroUsize::new(10).unwrap(),
+            max_policy_lag: 0,
+            record_behaviour_log_probs: false,
+        };
+        let mut rng_env = Prng::seed_from_u64(2);
+        let mut rng_actor = Prng::seed_from_u64(3);
+
+        let env = DeterministicBandit::from_values(vec![0.0, 1.0]);
+        let mut agent = TabularQLearningAgentConfig::default()
+            .build_agent(&env, &mut rng_actor)
+            .unwrap();
+
+        let mut logger = PolicyLagLogger::default();
+        train_async(
+            &mut agent,
+            &env,
+            &config,
+            &mut rng_env,
+            &mut rng_actor,
+            &mut logger,
+        );
+        assert_eq!(logger.max_lags, [0.0; 5]);
+    }
 }
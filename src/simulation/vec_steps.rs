@@ -0,0 +1,273 @@
+use super::steps::EpisodeState;
+use super::{PartialStep, SimSeed};
+use crate::agents::BatchActor;
+use crate::envs::{Environment, Successor};
+use crate::logging::StatsLogger;
+use crate::Prng;
+use rand::{Rng, SeedableRng};
+use std::borrow::BorrowMut;
+use std::iter::FusedIterator;
+
+/// Iterator of steps from several copies of an environment simulated in lockstep.
+///
+/// Each iteration advances every environment copy by one step and yields the steps as a vector
+/// in which element `i` belongs to environment copy `i`.
+/// The actions for all copies are selected together with a single call to
+/// [`BatchActor::batch_act`].
+/// An environment copy that reaches the end of an episode starts a new episode on the next step.
+///
+/// Like [`Steps`](super::Steps), uses separate PRNGs for the environment and actor so that a
+/// change in one will not affect the other. Each PRNG is shared by all environment copies.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VecSteps<E, T, R, L>
+where
+    E: Environment,
+    T: BatchActor<E::Observation, E::Action>,
+{
+    env: E,
+    actor: T,
+    rng_env: R,
+    rng_actor: R,
+    logger: L,
+
+    #[allow(clippy::type_complexity)]
+    states: Vec<Option<EpisodeState<E::State, E::Observation, T::EpisodeState>>>,
+}
+
+impl<E, T, R, L> VecSteps<E, T, R, L>
+where
+    E: Environment,
+    T: BatchActor<E::Observation, E::Action>,
+    R: Rng + SeedableRng,
+{
+    pub fn new_seeded(env: E, actor: T, num_envs: usize, seed: SimSeed, logger: L) -> Self {
+        let (rng_env, rng_actor) = seed.derive_rngs();
+        Self::new(env, actor, num_envs, rng_env, rng_actor, logger)
+    }
+}
+
+impl<E, T, R, L> VecSteps<E, T, R, L>
+where
+    E: Environment,
+    T: BatchActor<E::Observation, E::Action>,
+{
+    /// Create a new vectorized simulation.
+    ///
+    /// # Panics
+    /// If `num_envs` is zero.
+    pub fn new(env: E, actor: T, num_envs: usize, rng_env: R, rng_actor: R, logger: L) -> Self {
+        assert!(num_envs > 0, "must simulate at least one environment");
+        Self {
+            env,
+            actor,
+            rng_env,
+            rng_actor,
+            logger,
+            states: (0..num_envs).map(|_| None).collect(),
+        }
+    }
+
+    /// Number of environment copies simulated in parallel.
+    pub const fn num_envs(&self) -> usize {
+        self.states.len()
+    }
+
+    #[inline]
+    pub const fn env(&self) -> &E {
+        &self.env
+    }
+    #[inline]
+    pub const fn actor(&self) -> &T {
+        &self.actor
+    }
+    #[inline]
+    pub const fn actor_mut(&mut self) -> &mut T {
+        &mut self.actor
+    }
+    #[inline]
+    pub const fn logger(&self) -> &L {
+        &self.logger
+    }
+    #[inline]
+    pub const fn logger_mut(&mut self) -> &mut L {
+        &mut self.logger
+    }
+}
+
+impl<E, T, R, L> VecSteps<E, T, R, L>
+where
+    E: Environment,
+    T: BatchActor<E::Observation, E::Action>,
+    R: BorrowMut<Prng>,
+    L: StatsLogger,
+{
+    /// Advance every environment copy by one step.
+    ///
+    /// Returns one step per environment copy, ordered by copy index.
+    pub fn step(&mut self) -> Vec<PartialStep<E::Observation, E::Action, E::Feedback>> {
+        // Extract the current environment and actor states.
+        // Start a new episode for any copy without a stored state.
+        let mut episodes: Vec<_> = self
+            .states
+            .iter_mut()
+            .map(|state| {
+                state.take().unwrap_or_else(|| {
+                    let env_state = self.env.initial_state(self.rng_env.borrow_mut());
+                    EpisodeState {
+                        observation: self.env.observe(&env_state, self.rng_env.borrow_mut()),
+                        env: env_state,
+                        actor: self.actor.initial_state(self.rng_actor.borrow_mut()),
+                    }
+                })
+            })
+            .collect();
+
+        // Select actions for all copies at once.
+        let actions = {
+            let mut actor_states = Vec::with_capacity(episodes.len());
+            let mut observations = Vec::with_capacity(episodes.len());
+            for episode in &mut episodes {
+                actor_states.push(&mut episode.actor);
+                observations.push(&episode.observation);
+            }
+            self.actor.batch_act(
+                &mut actor_states,
+                &observations,
+                self.rng_actor.borrow_mut(),
+            )
+        };
+        assert_eq!(
+            actions.len(),
+            episodes.len(),
+            "actor returned the wrong number of actions"
+        );
+
+        // Take an environment step in each copy.
+        episodes
+            .into_iter()
+            .zip(actions)
+            .zip(&mut self.states)
+            .map(|((episode, action), state)| {
+                let (successor, feedback) = self.env.step(
+                    episode.env,
+                    &action,
+                    self.rng_env.borrow_mut(),
+                    &mut self.logger,
+                );
+                debug_assert!(state.is_none());
+                let next = match successor {
+                    Successor::Continue(next_state) => {
+                        *state = Some(EpisodeState {
+                            observation: self.env.observe(&next_state, self.rng_env.borrow_mut()),
+                            env: next_state,
+                            actor: episode.actor,
+                        });
+                        Successor::Continue(())
+                    }
+                    Successor::Terminate => Successor::Terminate,
+                    Successor::Interrupt(next_state) => Successor::Interrupt(
+                        self.env.observe(&next_state, self.rng_env.borrow_mut()),
+                    ),
+                };
+                PartialStep {
+                    observation: episode.observation,
+                    action,
+                    feedback,
+                    next,
+                }
+            })
+            .collect()
+    }
+}
+
+impl<E, T, R, L> Iterator for VecSteps<E, T, R, L>
+where
+    E: Environment,
+    T: BatchActor<E::Observation, E::Action>,
+    R: BorrowMut<Prng>,
+    L: StatsLogger,
+{
+    type Item = Vec<PartialStep<E::Observation, E::Action, E::Feedback>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.step())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // infinite
+        (usize::MAX, None)
+    }
+}
+
+impl<E, T, R, L> FusedIterator for VecSteps<E, T, R, L>
+where
+    E: Environment,
+    T: BatchActor<E::Observation, E::Action>,
+    R: BorrowMut<Prng>,
+    L: StatsLogger,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::{Actor, RandomAgent};
+    use crate::envs::{Chain, EnvStructure, Environment, VisibleStepLimit, Wrap};
+    use crate::simulation::StepsIter;
+
+    #[test]
+    fn steps_per_env() {
+        let env = Chain::default().wrap(VisibleStepLimit::new(10));
+        let agent = RandomAgent::new(env.action_space());
+        let steps: Vec<_> = env
+            .run_vec(agent, 3, SimSeed::Root(1), ())
+            .take(25)
+            .collect();
+        assert_eq!(steps.len(), 25);
+        assert!(steps.iter().all(|s| s.len() == 3));
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    #[test]
+    fn episodes_are_valid() {
+        let steps_per_episode = 10;
+        let num_envs = 4;
+        let env = Chain::default().wrap(VisibleStepLimit::new(steps_per_episode));
+        let agent = RandomAgent::new(env.action_space());
+        let sim = env.run_vec(agent, num_envs, SimSeed::Root(2), ());
+        let mut per_env: Vec<Vec<_>> = (0..num_envs).map(|_| Vec::new()).collect();
+        for steps in sim.take(3 * steps_per_episode as usize) {
+            for (env_steps, step) in per_env.iter_mut().zip(steps) {
+                env_steps.push(step);
+            }
+        }
+        for env_steps in per_env {
+            let summary = env_steps.into_iter().summarize();
+            assert_eq!(summary.num_episodes(), 3);
+            assert_eq!(summary.num_steps(), 3 * steps_per_episode);
+        }
+    }
+
+    /// The default `batch_act` is equivalent to calling `act` on each episode in turn.
+    #[test]
+    fn default_batch_act_matches_act() {
+        let env = Chain::default();
+        let actor = RandomAgent::new(env.action_space());
+        let observations = [0, 1, 2];
+
+        let mut rng = Prng::seed_from_u64(3);
+        let mut states: Vec<()> = vec![(); 3];
+        let mut state_refs: Vec<_> = states.iter_mut().collect();
+        let observation_refs: Vec<_> = observations.iter().collect();
+        let batch_actions = actor.batch_act(&mut state_refs, &observation_refs, &mut rng);
+
+        let mut rng = Prng::seed_from_u64(3);
+        let actions: Vec<_> = observations
+            .iter()
+            .map(|o| Actor::<usize, _>::act(&actor, &mut (), o, &mut rng))
+            .collect();
+        assert_eq!(batch_actions, actions);
+    }
+}
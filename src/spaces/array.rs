@@ -1,14 +1,17 @@
 //! Array space
 use super::{
-    iter_product_subset_ord, FeatureSpace, FiniteSpace, LogElementSpace, LogError, NonEmptySpace,
-    Space, StatsLogger, SubsetOrd,
+    iter_product_subset_ord, FeatureSpace, FiniteSpace, IntervalSpace, LogElementSpace, LogError,
+    NonEmptySpace, ParameterizedDistributionSpace, ReprSpace, Space, StatsLogger, SubsetOrd,
 };
-use num_traits::Float;
+use crate::torch::distributions::{Normal, TanhNormal};
+use crate::utils::distributions::ArrayDistribution;
+use num_traits::{Bounded, Float};
 use rand::distributions::Distribution;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use serde_big_array::BigArray;
 use std::cmp::Ordering;
+use tch::Tensor;
 
 /// A Cartesian product of `N` spaces of the same type (but not necessarily the same space).
 ///
@@ -147,6 +150,74 @@ impl<S: FeatureSpace, const N: usize> FeatureSpace for ArraySpace<S, N> {
     }
 }
 
+/// Represent elements as a one-dimensional tensor of length `N`.
+impl<T, const N: usize> ReprSpace<Tensor> for ArraySpace<IntervalSpace<T>, N>
+where
+    T: Bounded + PartialOrd + tch::kind::Element + Clone + Send + 'static,
+{
+    #[inline]
+    fn repr(&self, element: &Self::Element) -> Tensor {
+        Tensor::of_slice(element)
+    }
+
+    #[inline]
+    fn batch_repr<'a, I>(&self, elements: I) -> Tensor
+    where
+        I: IntoIterator<Item = &'a Self::Element>,
+        I::IntoIter: ExactSizeIterator + Clone,
+        Self::Element: 'a,
+    {
+        let elements = elements.into_iter();
+        let shape = [elements.len().try_into().unwrap(), N.try_into().unwrap()];
+        let flat: Vec<_> = elements.flat_map(|e| e.iter().cloned()).collect();
+        Tensor::of_slice(&flat).reshape(&shape)
+    }
+}
+
+/// Independent tanh-squashed normal distributions over each interval.
+///
+/// The parameters are `N` pre-squash means followed by `N` log standard deviations.
+/// All inner intervals must be bounded.
+impl<T, const N: usize> ParameterizedDistributionSpace<Tensor> for ArraySpace<IntervalSpace<T>, N>
+where
+    T: Float + Bounded + tch::kind::Element + Send + 'static,
+{
+    type Distribution = TanhNormal;
+
+    #[inline]
+    fn num_distribution_params(&self) -> usize {
+        2 * N
+    }
+
+    #[inline]
+    fn sample_element(&self, params: &Tensor) -> Self::Element {
+        let values: Vec<f64> = self.distribution(params).sample().into();
+        let mut values = values.into_iter();
+        array_init::array_init(|i| {
+            let space = &self.inner_spaces[i];
+            // Guard against rounding outside of the interval
+            T::from(values.next().unwrap())
+                .unwrap()
+                .max(space.low)
+                .min(space.high)
+        })
+    }
+
+    #[inline]
+    fn distribution(&self, params: &Tensor) -> Self::Distribution {
+        let (low, high): (Vec<f64>, Vec<f64>) = self
+            .inner_spaces
+            .iter()
+            .map(IntervalSpace::distribution_bounds)
+            .unzip();
+        TanhNormal::new(
+            Normal::from_params(params, &[N.try_into().unwrap()]),
+            &Tensor::of_slice(&low),
+            &Tensor::of_slice(&high),
+        )
+    }
+}
+
 impl<S: Space, const N: usize> LogElementSpace for ArraySpace<S, N> {
     #[inline]
     fn log_element<L: StatsLogger + ?Sized>(
@@ -403,6 +474,84 @@ mod finite_space {
     }
 }
 
+#[cfg(test)]
+mod repr_space_tensor {
+    use super::*;
+
+    #[test]
+    fn repr() {
+        let space = ArraySpace::new([IntervalSpace::new(0.0_f32, 1.0); 3]);
+        assert_eq!(
+            space.repr(&[0.0, 0.5, 1.0]),
+            Tensor::of_slice(&[0.0_f32, 0.5, 1.0])
+        );
+    }
+
+    #[test]
+    fn batch_repr() {
+        let space = ArraySpace::new([IntervalSpace::new(0.0_f32, 1.0); 2]);
+        let elements = [[0.0, 0.5], [1.0, 0.25]];
+        assert_eq!(
+            space.batch_repr(&elements),
+            Tensor::of_slice(&[0.0_f32, 0.5, 1.0, 0.25]).reshape(&[2, 2])
+        );
+    }
+}
+
+#[cfg(test)]
+mod parameterized_sample_space_tensor {
+    use super::*;
+    use tch::{Device, Kind};
+
+    fn space() -> ArraySpace<IntervalSpace<f64>, 2> {
+        ArraySpace::new([
+            IntervalSpace::new(-1.0, 1.0),
+            IntervalSpace::new(10.0, 20.0),
+        ])
+    }
+
+    #[test]
+    fn num_sample_params() {
+        assert_eq!(4, space().num_distribution_params());
+    }
+
+    #[test]
+    fn sample_element_in_bounds() {
+        let space = space();
+        let params = Tensor::of_slice(&[0.0_f32, 3.0, 1.0, 1.0]);
+        for _ in 0..10 {
+            assert!(space.contains(&space.sample_element(&params)));
+        }
+    }
+
+    #[test]
+    fn sample_element_deterministic() {
+        let space = space();
+        let params = Tensor::of_slice(&[0.0_f32, 0.0, -20.0, -20.0]);
+        let [a, b] = space.sample_element(&params);
+        assert!(a.abs() < 1e-4);
+        assert!((b - 15.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn distribution_shapes() {
+        let params = Tensor::zeros(&[5, 4], (Kind::Float, Device::Cpu));
+        let distribution = space().distribution(&params);
+        assert_eq!(distribution.batch_shape(), [5]);
+        assert_eq!(distribution.element_shape(), [2]);
+    }
+
+    #[test]
+    fn distribution_log_probs_of_repr() {
+        let space = space();
+        let params = Tensor::zeros(&[2, 4], (Kind::Float, Device::Cpu));
+        let elements = space.batch_repr(&[[0.0, 15.0], [0.5, 12.0]]);
+        let log_probs = space.distribution(&params).log_probs(&elements);
+        assert_eq!(log_probs.size(), [2]);
+        assert!(bool::from(log_probs.isfinite().all()));
+    }
+}
+
 #[cfg(test)]
 mod feature_space {
     use super::super::IndexSpace;
@@ -1,6 +1,11 @@
 //! `IntervalSpace` definition
-use super::{FeatureSpace, LogElementSpace, NonEmptySpace, ReprSpace, Space, SubsetOrd};
+use super::{
+    FeatureSpace, LogElementSpace, NonEmptySpace, ParameterizedDistributionSpace, ReprSpace, Space,
+    SubsetOrd,
+};
 use crate::logging::{LogError, LogValue, StatsLogger};
+use crate::torch::distributions::{Normal, TanhNormal};
+use crate::utils::distributions::ArrayDistribution;
 use num_traits::{Bounded, Float, ToPrimitive};
 use rand::distributions::Distribution;
 use rand::Rng;
@@ -52,6 +57,28 @@ impl<T: Bounded + PartialOrd + Clone + Send> Space for IntervalSpace<T> {
     }
 }
 
+impl<T: Bounded + PartialOrd> IntervalSpace<T> {
+    /// Whether both the lower and upper bounds are finite.
+    #[inline]
+    pub fn is_bounded(&self) -> bool {
+        self.low > T::min_value() && self.high < T::max_value()
+    }
+}
+
+impl<T: Bounded + PartialOrd + ToPrimitive> IntervalSpace<T> {
+    /// The interval bounds as `f64`, for parameterizing a squashed distribution.
+    ///
+    /// # Panics
+    /// If the interval is not [bounded](IntervalSpace::is_bounded).
+    pub(super) fn distribution_bounds(&self) -> (f64, f64) {
+        assert!(
+            self.is_bounded(),
+            "distribution requires a bounded interval"
+        );
+        (self.low.to_f64().unwrap(), self.high.to_f64().unwrap())
+    }
+}
+
 impl<T: PartialOrd> SubsetOrd for IntervalSpace<T> {
     #[inline]
     fn subset_cmp(&self, other: &Self) -> Option<Ordering> {
@@ -97,6 +124,39 @@ where
     }
 }
 
+/// Tanh-squashed normal distribution over the interval.
+///
+/// The parameters are `[mean, log_std]` of the pre-squash normal distribution.
+/// Only supported for bounded intervals.
+impl<T> ParameterizedDistributionSpace<Tensor> for IntervalSpace<T>
+where
+    T: Float + Bounded + tch::kind::Element + Send,
+{
+    type Distribution = TanhNormal;
+
+    #[inline]
+    fn num_distribution_params(&self) -> usize {
+        2
+    }
+
+    #[inline]
+    fn sample_element(&self, params: &Tensor) -> Self::Element {
+        let value = T::from(self.distribution(params).sample().double_value(&[])).unwrap();
+        // Guard against rounding outside of the interval
+        value.max(self.low).min(self.high)
+    }
+
+    #[inline]
+    fn distribution(&self, params: &Tensor) -> Self::Distribution {
+        let (low, high) = self.distribution_bounds();
+        TanhNormal::new(
+            Normal::from_params(params, &[]),
+            &Tensor::from(low),
+            &Tensor::from(high),
+        )
+    }
+}
+
 /// Features are `[f]` for element `f`.
 impl<T: Bounded + PartialOrd + ToPrimitive + Clone + Send> FeatureSpace for IntervalSpace<T> {
     #[inline]
@@ -154,7 +214,27 @@ impl<T: Bounded + PartialOrd + Into<f64> + Clone + Send> LogElementSpace for Int
         element: &Self::Element,
         logger: &mut L,
     ) -> Result<(), LogError> {
-        logger.log(name.into(), LogValue::Scalar(element.clone().into()))
+        // Log as a vector so that the values are summarized as a histogram
+        logger.log(name.into(), LogValue::Vector(vec![element.clone().into()]))
+    }
+
+    /// Logs all elements together as a vector (summarized as a histogram).
+    #[inline]
+    fn log_elements<'a, L, I>(
+        &self,
+        name: &'static str,
+        elements: I,
+        logger: &mut L,
+    ) -> Result<(), LogError>
+    where
+        L: StatsLogger + ?Sized,
+        I: IntoIterator<Item = &'a Self::Element>,
+        Self::Element: 'a,
+    {
+        logger.log(
+            name.into(),
+            LogValue::Vector(elements.into_iter().map(|x| x.clone().into()).collect()),
+        )
     }
 }
 
@@ -326,6 +406,61 @@ mod subset_ord {
     }
 }
 
+#[cfg(test)]
+mod parameterized_sample_space_tensor {
+    use super::*;
+
+    #[test]
+    fn num_sample_params() {
+        assert_eq!(2, IntervalSpace::new(-1.0, 1.0).num_distribution_params());
+    }
+
+    #[test]
+    fn sample_element_in_bounds() {
+        let space = IntervalSpace::new(-1.0_f32, 2.0);
+        let params = Tensor::of_slice(&[5.0_f32, 1.0]);
+        for _ in 0..10 {
+            assert!(space.contains(&space.sample_element(&params)));
+        }
+    }
+
+    #[test]
+    fn sample_element_deterministic() {
+        let space = IntervalSpace::new(-1.0_f64, 3.0);
+        // Very small standard deviation around a pre-squash mean of 0 -> centre of the interval
+        let params = Tensor::of_slice(&[0.0_f32, -20.0]);
+        for _ in 0..10 {
+            assert!((space.sample_element(&params) - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn distribution_batch_shape() {
+        let space = IntervalSpace::new(0.0_f32, 1.0);
+        let params = Tensor::zeros(&[4, 3, 2], (tch::Kind::Float, tch::Device::Cpu));
+        let distribution = space.distribution(&params);
+        assert_eq!(distribution.batch_shape(), [4, 3]);
+        assert_eq!(distribution.element_shape(), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn distribution_log_probs_of_repr() {
+        let space = IntervalSpace::new(0.0_f64, 1.0);
+        let params = Tensor::zeros(&[3, 2], (tch::Kind::Float, tch::Device::Cpu));
+        let elements = space.batch_repr(&[0.0, 0.5, 1.0]);
+        let log_probs = space.distribution(&params).log_probs(&elements);
+        assert_eq!(log_probs.size(), [3]);
+        assert!(bool::from(log_probs.isfinite().all()));
+    }
+
+    #[test]
+    #[should_panic(expected = "bounded")]
+    fn unbounded_distribution_panics() {
+        let space = IntervalSpace::<f32>::default();
+        let _ = space.distribution(&Tensor::zeros(&[2], (tch::Kind::Float, tch::Device::Cpu)));
+    }
+}
+
 #[cfg(test)]
 mod feature_space {
     use super::*;
@@ -452,4 +452,23 @@ pub trait LogElementSpace: Space {
         element: &Self::Element,
         logger: &mut L,
     ) -> Result<(), LogError>;
+
+    /// Log a collection of elements of the space together.
+    ///
+    /// Used by spaces with a variable number of inner elements.
+    /// The default implementation logs nothing.
+    #[inline]
+    fn log_elements<'a, L, I>(
+        &self,
+        _name: &'static str,
+        _elements: I,
+        _logger: &mut L,
+    ) -> Result<(), LogError>
+    where
+        L: StatsLogger + ?Sized,
+        I: IntoIterator<Item = &'a Self::Element>,
+        Self::Element: 'a,
+    {
+        Ok(())
+    }
 }
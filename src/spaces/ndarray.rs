@@ -1,11 +1,17 @@
-use super::{FeatureSpace, LogElementSpace, NonEmptySpace, Space, SubsetOrd};
+use super::{
+    FeatureSpace, IntervalSpace, LogElementSpace, NonEmptySpace, ParameterizedDistributionSpace,
+    ReprSpace, Space, SubsetOrd,
+};
 use crate::logging::{LogError, StatsLogger};
+use crate::torch::distributions::{Normal, TanhNormal};
+use crate::utils::distributions::ArrayDistribution;
 use ndarray::{Array, Dimension, IntoDimension, Ix1, Ix2, Ix3};
-use num_traits::Float;
+use num_traits::{Bounded, Float};
 use rand::distributions::Distribution;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use tch::Tensor;
 
 /// A space of n-dimensional [`Array`s](Array) of elements from a single space.
 ///
@@ -107,16 +113,92 @@ impl<S: FeatureSpace, D: Dimension> FeatureSpace for NdArraySpace<S, D> {
     }
 }
 
+impl<S, D: Dimension> NdArraySpace<S, D> {
+    /// Array shape as a tensor shape.
+    fn tensor_shape(&self) -> Vec<i64> {
+        self.dim
+            .slice()
+            .iter()
+            .map(|&d| d.try_into().unwrap())
+            .collect()
+    }
+}
+
+/// Represent elements as tensors with the same shape.
+impl<T, D> ReprSpace<Tensor> for NdArraySpace<IntervalSpace<T>, D>
+where
+    T: Bounded + PartialOrd + tch::kind::Element + Clone + Send + 'static,
+    D: Dimension + 'static,
+{
+    #[inline]
+    fn repr(&self, element: &Self::Element) -> Tensor {
+        let values: Vec<_> = element.iter().cloned().collect();
+        Tensor::of_slice(&values).reshape(&self.tensor_shape())
+    }
+
+    #[inline]
+    fn batch_repr<'a, I>(&self, elements: I) -> Tensor
+    where
+        I: IntoIterator<Item = &'a Self::Element>,
+        I::IntoIter: ExactSizeIterator + Clone,
+        Self::Element: 'a,
+    {
+        let elements = elements.into_iter();
+        let mut shape = vec![elements.len().try_into().unwrap()];
+        shape.extend(self.tensor_shape());
+        let values: Vec<_> = elements.flat_map(|e| e.iter().cloned()).collect();
+        Tensor::of_slice(&values).reshape(&shape)
+    }
+}
+
+/// Independent tanh-squashed normal distributions over each array element.
+///
+/// The parameters are the (row-major flattened) pre-squash means followed by the log standard
+/// deviations. The inner interval must be bounded.
+impl<T, D> ParameterizedDistributionSpace<Tensor> for NdArraySpace<IntervalSpace<T>, D>
+where
+    T: Float + Bounded + tch::kind::Element + Send + 'static,
+    D: Dimension + 'static,
+{
+    type Distribution = TanhNormal;
+
+    #[inline]
+    fn num_distribution_params(&self) -> usize {
+        2 * self.dim.size()
+    }
+
+    #[inline]
+    fn sample_element(&self, params: &Tensor) -> Self::Element {
+        let values: Vec<f64> = self.distribution(params).sample().into();
+        let values = values
+            .into_iter()
+            // Guard against rounding outside of the interval
+            .map(|v| T::from(v).unwrap().max(self.inner.low).min(self.inner.high))
+            .collect();
+        Array::from_shape_vec(self.dim.clone(), values).unwrap()
+    }
+
+    #[inline]
+    fn distribution(&self, params: &Tensor) -> Self::Distribution {
+        let (low, high) = self.inner.distribution_bounds();
+        TanhNormal::new(
+            Normal::from_params(params, &self.tensor_shape()),
+            &Tensor::from(low),
+            &Tensor::from(high),
+        )
+    }
+}
+
+/// Logs all array elements together with [`LogElementSpace::log_elements`] of the inner space.
 impl<S: LogElementSpace, D: Dimension> LogElementSpace for NdArraySpace<S, D> {
     #[inline]
     fn log_element<L: StatsLogger + ?Sized>(
         &self,
-        _name: &'static str,
-        _element: &Self::Element,
-        _logger: &mut L,
+        name: &'static str,
+        element: &Self::Element,
+        logger: &mut L,
     ) -> Result<(), LogError> {
-        // Too complex to log
-        Ok(())
+        self.inner.log_elements(name, element.iter(), logger)
     }
 }
 
@@ -229,6 +311,82 @@ mod subset_cmp {
     }
 }
 
+#[cfg(test)]
+mod repr_space_tensor {
+    use super::super::IntervalSpace;
+    use super::*;
+
+    #[test]
+    fn d2_repr() {
+        let space = NdArraySpace::new(IntervalSpace::new(0.0_f32, 1.0), (2, 2));
+        let element = Array::from_vec(vec![0.0, 0.25, 0.5, 0.75])
+            .into_shape((2, 2))
+            .unwrap();
+        assert_eq!(
+            space.repr(&element),
+            Tensor::of_slice(&[0.0_f32, 0.25, 0.5, 0.75]).reshape(&[2, 2])
+        );
+    }
+
+    #[test]
+    fn d1_batch_repr() {
+        let space = NdArraySpace::new(IntervalSpace::new(0.0_f32, 1.0), (2,));
+        let elements = [
+            Array::from_vec(vec![0.0, 0.25]),
+            Array::from_vec(vec![0.5, 0.75]),
+            Array::from_vec(vec![1.0, 0.0]),
+        ];
+        assert_eq!(
+            space.batch_repr(&elements),
+            Tensor::of_slice(&[0.0_f32, 0.25, 0.5, 0.75, 1.0, 0.0]).reshape(&[3, 2])
+        );
+    }
+}
+
+#[cfg(test)]
+mod parameterized_sample_space_tensor {
+    use super::super::IntervalSpace;
+    use super::*;
+    use tch::{Device, Kind};
+
+    #[test]
+    fn num_sample_params() {
+        let space = NdArraySpace::new(IntervalSpace::new(0.0_f32, 1.0), (2, 3));
+        assert_eq!(12, space.num_distribution_params());
+    }
+
+    #[test]
+    fn sample_element_in_space() {
+        let space = NdArraySpace::new(IntervalSpace::new(-2.0_f32, 1.0), (2, 3));
+        let params = Tensor::zeros(&[12], (Kind::Float, Device::Cpu));
+        for _ in 0..10 {
+            assert!(space.contains(&space.sample_element(&params)));
+        }
+    }
+
+    #[test]
+    fn distribution_shapes() {
+        let space = NdArraySpace::new(IntervalSpace::new(0.0_f64, 1.0), (2, 3));
+        let params = Tensor::zeros(&[4, 12], (Kind::Float, Device::Cpu));
+        let distribution = space.distribution(&params);
+        assert_eq!(distribution.batch_shape(), [4]);
+        assert_eq!(distribution.element_shape(), [2, 3]);
+    }
+
+    #[test]
+    fn distribution_log_probs_of_repr() {
+        let space = NdArraySpace::new(IntervalSpace::new(0.0_f64, 1.0), (2,));
+        let params = Tensor::zeros(&[2, 4], (Kind::Float, Device::Cpu));
+        let elements = space.batch_repr(&[
+            Array::from_vec(vec![0.0, 0.25]),
+            Array::from_vec(vec![0.5, 1.0]),
+        ]);
+        let log_probs = space.distribution(&params).log_probs(&elements);
+        assert_eq!(log_probs.size(), [2]);
+        assert!(bool::from(log_probs.isfinite().all()));
+    }
+}
+
 #[cfg(test)]
 mod feature_space {
     use super::super::{BooleanSpace, IndexSpace, IntervalSpace};
@@ -312,3 +470,45 @@ mod feature_space {
         [[0.0, 0.25, 0.5, 0.75], [0.1, 0.2, 0.3, 0.4]]
     );
 }
+
+#[cfg(test)]
+mod log_element_space {
+    use super::super::test_derive::{MockLogCall, MockLogger};
+    use super::super::{BooleanSpace, IntervalSpace};
+    use super::*;
+    use crate::logging::LogValue;
+
+    #[test]
+    fn d2_interval_log_element() {
+        let space = NdArraySpace::new(IntervalSpace::new(0.0, 1.0), (2, 2));
+        let element = Array::from_vec(vec![0.0, 0.25, 0.5, 0.75])
+            .into_shape((2, 2))
+            .unwrap();
+        let mut logger = MockLogger::default();
+        space.log_element("foo", &element, &mut logger).unwrap();
+        assert_eq!(
+            logger.calls,
+            [
+                MockLogCall::GroupStart,
+                MockLogCall::Log {
+                    id: "foo".into(),
+                    value: LogValue::Vector(vec![0.0, 0.25, 0.5, 0.75])
+                },
+                MockLogCall::GroupEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn d1_boolean_log_element() {
+        let space = NdArraySpace::new(BooleanSpace, 3);
+        let mut logger = MockLogger::default();
+        space
+            .log_element("foo", &Array::from_elem(3, true), &mut logger)
+            .unwrap();
+        assert!(logger
+            .calls
+            .iter()
+            .all(|call| !matches!(call, MockLogCall::Log { .. })));
+    }
+}
@@ -7,12 +7,12 @@ use std::cmp::Ordering;
 
 /// Mock logger for testing `LogElementSpace`
 #[derive(Debug, Default)]
-struct MockLogger {
-    calls: Vec<MockLogCall>,
+pub(super) struct MockLogger {
+    pub calls: Vec<MockLogCall>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
-enum MockLogCall {
+pub(super) enum MockLogCall {
     GroupStart,
     Log { id: Id, value: LogValue },
     GroupEnd,
@@ -9,7 +9,9 @@ use crate::envs::EnvStructure;
 use crate::feedback::Reward;
 use crate::logging::StatsLogger;
 use crate::spaces::{FeatureSpace, NonEmptyFeatures, ParameterizedDistributionSpace, Space};
+use crate::torch::modules::{AsModule, Module, SeqPacked};
 use crate::torch::serialize::DeviceDef;
+use crate::utils::distributions::ArrayDistribution;
 use crate::Prng;
 use log::info;
 use serde::{Deserialize, Serialize};
@@ -178,12 +180,33 @@ where
         buffers: &mut [&mut VecBuffer<OS::Element, AS::Element>],
         mut logger: &mut dyn StatsLogger,
     ) {
-        let features = LazyHistoryFeatures::new(
-            buffers.iter_mut().flat_map(|b| b.episodes()),
+        let policy_module = self.policy.as_module().shallow_clone();
+        let action_space = &self.action_space;
+        let mut features = LazyHistoryFeatures::new(
+            buffers.iter().flat_map(|b| b.episodes()),
             &self.observation_space,
             &self.action_space,
             self.policy.device,
         );
+        // Behaviour log probabilities are only available if recorded for every buffer.
+        if let Some(log_probs) = buffers
+            .iter()
+            .map(|b| b.behaviour_log_probs())
+            .collect::<Option<Vec<_>>>()
+        {
+            features = features.with_behaviour_log_probs(&log_probs.concat());
+        }
+        // For off-policy corrections; only evaluated if requested by the critic.
+        let features = features.with_target_log_probs(move |observation_features, actions| {
+            let _no_grad = tch::no_grad_guard();
+            policy_module
+                .seq_packed(observation_features)
+                .batch_map(|params| {
+                    action_space
+                        .distribution(&params)
+                        .log_probs(actions.tensor())
+                })
+        });
         if features.is_empty() {
             info!("skipping model update; history buffer is empty");
             return;
@@ -213,17 +236,21 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::super::critics::{RewardToGoConfig, StepValueTarget, ValuesOptConfig};
-    use super::super::policies::{PpoConfig, ReinforceConfig, TrpoConfig};
+    use super::super::critics::{AdvantageFn, RewardToGoConfig, StepValueTarget, ValuesOptConfig};
+    use super::super::policies::{MinibatchUnit, PpoConfig, ReinforceConfig, TrpoConfig};
+    use super::super::schedules::ExplorationRateSchedule;
     use super::*;
     use crate::agents::testing;
     use crate::torch::modules::{BuildModule, GruMlpConfig, MlpConfig, SeqIterative, SeqPacked};
     use crate::torch::optimizers::AdamConfig;
     use rstest::rstest;
     use std::marker::PhantomData;
+    use std::num::NonZeroUsize;
 
     trait FromModuleConfig<MB> {
         fn from_module_config(module_config: MB) -> Self;
+
+        fn with_entropy_coeff(self, entropy_coeff: ExplorationRateSchedule) -> Self;
     }
 
     impl<MB> FromModuleConfig<MB> for ReinforceConfig<MB> {
@@ -234,6 +261,14 @@ mod tests {
                     learning_rate: 0.1,
                     ..AdamConfig::default()
                 },
+                entropy_coeff: ExplorationRateSchedule::Constant(0.0),
+            }
+        }
+
+        fn with_entropy_coeff(self, entropy_coeff: ExplorationRateSchedule) -> Self {
+            Self {
+                entropy_coeff,
+                ..self
             }
         }
     }
@@ -254,9 +289,16 @@ mod tests {
                 ..Self::default()
             }
         }
+
+        fn with_entropy_coeff(self, entropy_coeff: ExplorationRateSchedule) -> Self {
+            Self {
+                entropy_coeff,
+                ..self
+            }
+        }
     }
 
-    const fn ppo<MB>() -> PhantomData<ReinforceConfig<MB>> {
+    const fn ppo<MB>() -> PhantomData<PpoConfig<MB>> {
         PhantomData
     }
 
@@ -267,9 +309,16 @@ mod tests {
                 ..Self::default()
             }
         }
+
+        fn with_entropy_coeff(self, entropy_coeff: ExplorationRateSchedule) -> Self {
+            Self {
+                entropy_coeff,
+                ..self
+            }
+        }
     }
 
-    const fn trpo<MB>() -> PhantomData<ReinforceConfig<MB>> {
+    const fn trpo<MB>() -> PhantomData<TrpoConfig<MB>> {
         PhantomData
     }
 
@@ -314,7 +363,15 @@ mod tests {
     fn learns_deterministic_bandit_values_gae<MB, PB>(
         #[values(MlpConfig::default(), GruMlpConfig::default())] module: MB,
         #[values(reinforce(), ppo(), trpo())] _policy_alg: PhantomData<PB>,
-        #[values(StepValueTarget::RewardToGo, StepValueTarget::OneStepTd)]
+        #[values(
+            StepValueTarget::RewardToGo,
+            StepValueTarget::OneStepTd,
+            StepValueTarget::NStepTd {
+                n: NonZeroUsize::new(3).unwrap()
+            },
+            StepValueTarget::TdLambda { lambda: 0.9 },
+            StepValueTarget::VTrace { rho_bar: 1.0, c_bar: 1.0 }
+        )]
         value_target: StepValueTarget,
         #[values(Device::Cpu, Device::cuda_if_available())] device: Device,
     ) where
@@ -330,4 +387,105 @@ mod tests {
         };
         testing::train_deterministic_bandit(&config, 10, 0.9);
     }
+
+    #[rstest]
+    #[allow(clippy::used_underscore_binding)] // confused by used of _policy_alg in macro expansion
+    fn learns_deterministic_bandit_vtrace<PB>(
+        #[values(reinforce(), ppo(), trpo())] _policy_alg: PhantomData<PB>,
+    ) where
+        PB: FromModuleConfig<MlpConfig> + BuildPolicy,
+    {
+        let config = ActorCriticConfig {
+            policy_config: PB::from_module_config(MlpConfig::default()),
+            critic_config: ValuesOptConfig {
+                advantage_fn: AdvantageFn::VTrace {
+                    rho_bar: 1.0,
+                    c_bar: 1.0,
+                },
+                ..values_opt_config(
+                    MlpConfig::default(),
+                    StepValueTarget::VTrace {
+                        rho_bar: 1.0,
+                        c_bar: 1.0,
+                    },
+                )
+            },
+            min_batch_size: HistoryDataBound::new(25, 1),
+            device: Device::Cpu,
+        };
+        testing::train_deterministic_bandit(&config, 10, 0.9);
+    }
+
+    #[rstest]
+    #[allow(clippy::used_underscore_binding)] // confused by used of _policy_alg in macro expansion
+    fn learns_deterministic_bandit_entropy_bonus<PB>(
+        #[values(reinforce(), ppo(), trpo())] _policy_alg: PhantomData<PB>,
+        #[values(
+            ExplorationRateSchedule::Constant(0.01),
+            ExplorationRateSchedule::LinearAnnealed { start: 0.1, end: 0.0, period: 100 }
+        )]
+        entropy_coeff: ExplorationRateSchedule,
+    ) where
+        PB: FromModuleConfig<MlpConfig> + BuildPolicy,
+    {
+        let config = ActorCriticConfig {
+            policy_config: PB::from_module_config(MlpConfig::default())
+                .with_entropy_coeff(entropy_coeff),
+            critic_config: RewardToGoConfig,
+            min_batch_size: HistoryDataBound::new(25, 1),
+            device: Device::Cpu,
+        };
+        testing::train_deterministic_bandit(&config, 10, 0.9);
+    }
+
+    #[rstest]
+    #[case::mlp_steps(MlpConfig::default(), MinibatchUnit::Steps)]
+    #[case::mlp_episodes(MlpConfig::default(), MinibatchUnit::Episodes)]
+    #[case::gru_mlp_episodes(GruMlpConfig::default(), MinibatchUnit::Episodes)]
+    fn learns_deterministic_bandit_ppo_minibatches<MB>(
+        #[case] module: MB,
+        #[case] minibatch_unit: MinibatchUnit,
+    ) where
+        MB: BuildModule + Default + Clone,
+        MB::Module: SeqPacked + SeqIterative,
+    {
+        let config = ActorCriticConfig {
+            policy_config: PpoConfig {
+                policy_fn_config: module.clone(),
+                optimizer_config: AdamConfig {
+                    learning_rate: 0.05,
+                    ..AdamConfig::default()
+                },
+                opt_steps_per_update: 4,
+                minibatch_steps: 8,
+                minibatch_unit,
+                entropy_coeff: ExplorationRateSchedule::Constant(0.01),
+                max_approx_kl: Some(0.5),
+                ..PpoConfig::default()
+            },
+            critic_config: ValuesOptConfig {
+                clip_distance: Some(1.0),
+                ..values_opt_config(module, StepValueTarget::RewardToGo)
+            },
+            min_batch_size: HistoryDataBound::new(25, 1),
+            device: Device::Cpu,
+        };
+        testing::train_deterministic_bandit(&config, 10, 0.9);
+    }
+
+    #[rstest]
+    #[allow(clippy::used_underscore_binding)] // confused by used of _policy_alg in macro expansion
+    fn learns_continuous_target_bandit<PB>(
+        #[values(reinforce(), ppo(), trpo())] _policy_alg: PhantomData<PB>,
+    ) where
+        PB: FromModuleConfig<MlpConfig> + BuildPolicy,
+    {
+        let config = ActorCriticConfig {
+            policy_config: PB::from_module_config(MlpConfig::default()),
+            critic_config: values_opt_config(MlpConfig::default(), StepValueTarget::RewardToGo),
+            min_batch_size: HistoryDataBound::new(100, 1),
+            device: Device::Cpu,
+        };
+        testing::train_continuous_target_bandit(&config, 20, 0.3);
+    }
 }
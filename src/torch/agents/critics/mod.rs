@@ -10,8 +10,11 @@ use super::features::HistoryFeatures;
 use crate::logging::StatsLogger;
 use crate::torch::modules::SeqPacked;
 use crate::torch::packed::PackedTensor;
+use log::warn;
 use serde::{Deserialize, Serialize};
-use tch::Device;
+use std::num::NonZeroUsize;
+use std::sync::Once;
+use tch::{Device, Tensor};
 
 /// A critic for an [actor-critic agent][super::ActorCriticAgent].
 ///
@@ -71,6 +74,22 @@ pub enum AdvantageFn {
         /// incorrect.
         lambda: f32,
     },
+    /// V-trace off-policy corrected advantages
+    ///
+    /// The importance-weighted one-step temporal difference of the V-trace targets:
+    /// `ρ_t (r_t + γ v_{t+1} - V(s_t))`. See [`vtrace_values`].
+    ///
+    /// # Reference
+    /// "[IMPALA: Scalable Distributed Deep-RL with Importance Weighted Actor-Learner
+    /// Architectures][impala]" by Espeholt et al.
+    ///
+    /// [impala]: https://arxiv.org/abs/1802.01561
+    VTrace {
+        /// Truncation level of the importance weights `ρ_t` applied to each temporal difference.
+        rho_bar: f32,
+        /// Truncation level of the importance weights `c_t` that propagate future corrections.
+        c_bar: f32,
+    },
 }
 
 impl Default for AdvantageFn {
@@ -89,6 +108,9 @@ impl AdvantageFn {
     ) -> PackedTensor {
         match self {
             Self::Gae { lambda } => gae(state_value_fn, discount_factor, *lambda, features),
+            Self::VTrace { rho_bar, c_bar } => {
+                vtrace_advantages(state_value_fn, discount_factor, *rho_bar, *c_bar, features)
+            }
         }
     }
 }
@@ -198,6 +220,259 @@ pub fn gae<M: SeqPacked + ?Sized>(
     residuals.discounted_cumsum_from_end(lambda * discount_factor)
 }
 
+/// N-step targets of a state value function.
+///
+/// The discounted sum of the next `n` rewards bootstrapped with the estimated value of the state
+/// `n` steps ahead: `sum_{k<n}(γ^k r_{t+k}) + γ^n V(s_{t+n})`. Truncated to the end of the episode,
+/// where the bootstrap value is `0` if the episode ended and the next state value if interrupted.
+///
+/// # Args:
+/// * `state_value_fn` - State value function estimator using past & present episode observations.
+/// * `discount_factor` - Discount factor on future rewards. In `[0, 1]`.
+/// * `n` - Number of steps of rewards to sum before bootstrapping.
+/// * `features` - Experience features.
+pub fn n_step_values<M: SeqPacked + ?Sized>(
+    state_value_fn: &M,
+    discount_factor: f32,
+    n: NonZeroUsize,
+    features: &dyn HistoryFeatures,
+) -> PackedTensor {
+    let extended_state_values = eval_extended_state_values(state_value_fn, features);
+    let extended_batch_sizes: Vec<i64> = extended_state_values.batch_sizes_tensor().into();
+    let device = extended_state_values.device();
+
+    // Discounted reward-to-go on the extended structure, which is 0 at the end of each sequence.
+    let (step_indices, _) = lookahead_indices(&extended_batch_sizes, 0);
+    let step_indices = Tensor::of_slice(&step_indices).to(device);
+    let rewards = features.rewards();
+    let extended_returns = PackedTensor::from_parts(
+        rewards
+            .tensor()
+            .new_zeros(
+                &extended_state_values.tensor().size(),
+                (rewards.kind(), device),
+            )
+            .index_copy(0, &step_indices, rewards.tensor()),
+        extended_state_values.structure().clone(),
+    )
+    .discounted_cumsum_from_end(discount_factor);
+
+    // target_t = G_t + γ^m * (V(s_{t+m}) - G_{t+m}) where m = min(n, episode_len - t)
+    let (lookahead_indices, lookahead_steps) = lookahead_indices(&extended_batch_sizes, n.get());
+    let lookahead_indices = Tensor::of_slice(&lookahead_indices).to(device);
+    let lookahead_discounts: Vec<f32> = lookahead_steps
+        .into_iter()
+        .map(|m| discount_factor.powi(m))
+        .collect();
+    let bootstrap = (extended_state_values.tensor() - extended_returns.tensor())
+        .index_select(0, &lookahead_indices)
+        * Tensor::of_slice(&lookahead_discounts).to(device);
+    PackedTensor::from_parts(
+        extended_returns.tensor().index_select(0, &step_indices) + bootstrap,
+        rewards.structure().clone(),
+    )
+}
+
+/// Packed indices of the step some number of steps ahead in each sequence.
+///
+/// # Args:
+/// * `extended_batch_sizes` - Batch sizes of an extended packed structure in which each sequence
+///     has one more element than the corresponding episode.
+/// * `n` - Number of steps to look ahead.
+///
+/// # Returns
+/// For each episode step `t` in packed order, the index in the extended packed structure of step
+/// `t + m` of the same sequence along with `m`, where `m = min(n, episode_len - t)`.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap,
+    clippy::cast_sign_loss
+)]
+fn lookahead_indices(extended_batch_sizes: &[i64], n: usize) -> (Vec<i64>, Vec<i32>) {
+    let mut offsets = Vec::with_capacity(extended_batch_sizes.len());
+    let mut offset = 0;
+    for &batch_size in extended_batch_sizes {
+        offsets.push(offset);
+        offset += batch_size;
+    }
+
+    // Last index within each extended sequence
+    let mut last_steps = vec![0; extended_batch_sizes.first().map_or(0, |&b| b as usize)];
+    for (t, &batch_size) in extended_batch_sizes.iter().enumerate() {
+        for last_step in &mut last_steps[..batch_size as usize] {
+            *last_step = t;
+        }
+    }
+
+    // Episode step t of sequence i exists iff extended step t + 1 does.
+    let mut indices = Vec::new();
+    let mut steps = Vec::new();
+    for (t, &batch_size) in extended_batch_sizes.iter().enumerate().skip(1) {
+        let t = t - 1;
+        for (i, &last_step) in last_steps[..batch_size as usize].iter().enumerate() {
+            let target_step = (t + n).min(last_step);
+            indices.push(offsets[target_step] + i as i64);
+            steps.push((target_step - t) as i32);
+        }
+    }
+    (indices, steps)
+}
+
+/// TD(λ) targets of a state value function.
+///
+/// The λ-return: an exponentially-weighted average of the n-step targets for all `n`.
+/// Equal to the state value estimates plus the [generalized advantage estimates][gae].
+///
+/// # Args:
+/// * `state_value_fn` - State value function estimator using past & present episode observations.
+/// * `discount_factor` - Discount factor on future rewards. In `[0, 1]`.
+/// * `lambda` - Parameter prioritizing sampled reward-to-go over the value module. In `[0, 1]`.
+/// * `features` - Experience features.
+pub fn lambda_values<M: SeqPacked + ?Sized>(
+    state_value_fn: &M,
+    discount_factor: f32,
+    lambda: f32,
+    features: &dyn HistoryFeatures,
+) -> PackedTensor {
+    let extended_state_values = eval_extended_state_values(state_value_fn, features);
+    let estimated_values = extended_state_values.trim_end(1);
+    let estimated_next_values = extended_state_values.view_trim_start(1);
+
+    let residuals = features.rewards().batch_map_ref(|rewards| {
+        rewards + discount_factor * estimated_next_values.tensor() - estimated_values.tensor()
+    });
+    residuals
+        .discounted_cumsum_from_end(lambda * discount_factor)
+        .batch_map(|advantages| advantages + estimated_values.tensor())
+}
+
+/// V-trace targets of a state value function.
+///
+/// Off-policy corrected n-step targets for experience collected by a behaviour policy that may
+/// differ from the current (target) policy:
+/// `v_t = V(s_t) + sum_{k>=t}(γ^{k-t} (prod_{t<=i<k} c_i) δ_k)`
+/// where `δ_k = ρ_k (r_k + γ V(s_{k+1}) - V(s_k))`, `ρ_k = min(ρ̄, π(a_k|h_k) / μ(a_k|h_k))` and
+/// `c_i = min(c̄, π(a_i|h_i) / μ(a_i|h_i))`.
+///
+/// The importance ratios are taken from [`HistoryFeatures::importance_log_ratios`] and are treated
+/// as `1` if unavailable, in which case the targets are the TD(1) targets.
+/// This is only correct for on-policy experience so a warning is logged the first time it happens.
+///
+/// # Reference
+/// "[IMPALA: Scalable Distributed Deep-RL with Importance Weighted Actor-Learner
+/// Architectures][impala]" by Espeholt et al.
+///
+/// [impala]: https://arxiv.org/abs/1802.01561
+///
+/// # Args:
+/// * `state_value_fn` - State value function estimator using past & present episode observations.
+/// * `discount_factor` - Discount factor on future rewards. In `[0, 1]`.
+/// * `rho_bar` - Truncation level of the importance weights `ρ`.
+/// * `c_bar` - Truncation level of the importance weights `c`.
+/// * `features` - Experience features.
+pub fn vtrace_values<M: SeqPacked + ?Sized>(
+    state_value_fn: &M,
+    discount_factor: f32,
+    rho_bar: f32,
+    c_bar: f32,
+    features: &dyn HistoryFeatures,
+) -> PackedTensor {
+    vtrace(state_value_fn, discount_factor, rho_bar, c_bar, features).targets
+}
+
+/// V-trace off-policy corrected advantages.
+///
+/// `ρ_t (r_t + γ v_{t+1} - V(s_t))` where `v_{t+1}` is the [V-trace target][vtrace_values] of the
+/// next state.
+///
+/// # Args:
+/// * `state_value_fn` - State value function estimator using past & present episode observations.
+/// * `discount_factor` - Discount factor on future rewards. In `[0, 1]`.
+/// * `rho_bar` - Truncation level of the importance weights `ρ`.
+/// * `c_bar` - Truncation level of the importance weights `c`.
+/// * `features` - Experience features.
+pub fn vtrace_advantages<M: SeqPacked + ?Sized>(
+    state_value_fn: &M,
+    discount_factor: f32,
+    rho_bar: f32,
+    c_bar: f32,
+    features: &dyn HistoryFeatures,
+) -> PackedTensor {
+    vtrace(state_value_fn, discount_factor, rho_bar, c_bar, features).advantages
+}
+
+/// V-trace targets and advantages.
+struct VTrace {
+    targets: PackedTensor,
+    advantages: PackedTensor,
+}
+
+fn vtrace<M: SeqPacked + ?Sized>(
+    state_value_fn: &M,
+    discount_factor: f32,
+    rho_bar: f32,
+    c_bar: f32,
+    features: &dyn HistoryFeatures,
+) -> VTrace {
+    let extended_state_values =
+        tch::no_grad(|| eval_extended_state_values(state_value_fn, features));
+    let estimated_values = extended_state_values.trim_end(1);
+    let estimated_next_values = extended_state_values.view_trim_start(1);
+    let rewards = features.rewards();
+
+    let ratios = match features.importance_log_ratios() {
+        Some(log_ratios) => log_ratios.tensor().exp(),
+        None => {
+            static MISSING_RATIOS: Once = Once::new();
+            MISSING_RATIOS.call_once(|| {
+                warn!(
+                    "V-trace importance ratios are unavailable because the behaviour log \
+                     probabilities were not recorded; treating the experience as on-policy"
+                );
+            });
+            rewards.tensor().ones_like()
+        }
+    };
+    let rhos = ratios.clamp_max(f64::from(rho_bar));
+    let trace_discounts =
+        rewards.batch_map_ref(|_| discount_factor * ratios.clamp_max(f64::from(c_bar)));
+
+    // v_t - V(s_t)
+    let corrections = rewards
+        .batch_map_ref(|rewards| {
+            &rhos
+                * (rewards + discount_factor * estimated_next_values.tensor()
+                    - estimated_values.tensor())
+        })
+        .discounted_cumsum_from_end_with::<f32>(&trace_discounts);
+
+    // v_{t+1} - V(s_{t+1}), which is 0 past the end of each episode
+    let extended_batch_sizes: Vec<i64> = extended_state_values.batch_sizes_tensor().into();
+    let (step_indices, _) = lookahead_indices(&extended_batch_sizes, 0);
+    let step_indices = Tensor::of_slice(&step_indices).to(extended_state_values.device());
+    let next_corrections = PackedTensor::from_parts(
+        extended_state_values.tensor().zeros_like().index_copy(
+            0,
+            &step_indices,
+            corrections.tensor(),
+        ),
+        extended_state_values.structure().clone(),
+    )
+    .view_trim_start(1);
+
+    let advantages = rewards.batch_map_ref(|rewards| {
+        &rhos
+            * (rewards
+                + discount_factor * (estimated_next_values.tensor() + next_corrections.tensor())
+                - estimated_values.tensor())
+    });
+    let targets = corrections.batch_map(|corrections| corrections + estimated_values.tensor());
+    VTrace {
+        targets,
+        advantages,
+    }
+}
+
 /// Target function for per-step selected-action value estimates.
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum StepValueTarget {
@@ -205,6 +480,30 @@ pub enum StepValueTarget {
     RewardToGo,
     /// One-step temporal-difference targets: `r_i + γ * V(s_{i+1})`
     OneStepTd,
+    /// N-step temporal-difference targets: `sum_{k<n}(γ^k r_{i+k}) + γ^n * V(s_{i+n})`
+    ///
+    /// Truncated at the end of the episode.
+    NStepTd {
+        /// Number of steps of rewards to sum before bootstrapping from the value estimate.
+        n: NonZeroUsize,
+    },
+    /// TD(λ) targets: exponentially-weighted average of the n-step targets.
+    TdLambda {
+        /// Weight decay parameter in `[0, 1]`.
+        ///
+        /// * `lambda = 0` is equivalent to [`StepValueTarget::OneStepTd`].
+        /// * `lambda = 1` is the reward-to-go bootstrapped from interrupted episodes.
+        lambda: f32,
+    },
+    /// V-trace off-policy corrected targets. See [`vtrace_values`].
+    ///
+    /// Equivalent to [`StepValueTarget::TdLambda`] with `lambda = 1` for on-policy experience.
+    VTrace {
+        /// Truncation level of the importance weights `ρ_t` applied to each temporal difference.
+        rho_bar: f32,
+        /// Truncation level of the importance weights `c_t` that propagate future corrections.
+        c_bar: f32,
+    },
 }
 
 impl Default for StepValueTarget {
@@ -224,6 +523,155 @@ impl StepValueTarget {
         match self {
             Self::RewardToGo => reward_to_go(discount_factor, features),
             Self::OneStepTd => one_step_values(state_value_fn, discount_factor, features),
+            Self::NStepTd { n } => n_step_values(state_value_fn, discount_factor, *n, features),
+            Self::TdLambda { lambda } => {
+                lambda_values(state_value_fn, discount_factor, *lambda, features)
+            }
+            Self::VTrace { rho_bar, c_bar } => {
+                vtrace_values(state_value_fn, discount_factor, *rho_bar, *c_bar, features)
+            }
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::needless_pass_by_value)]
+mod tests {
+    use super::super::features::tests::{history, StoredHistory};
+    use super::*;
+    use crate::spaces::{BooleanSpace, IndexSpace};
+    use crate::torch::modules::{BuildModule, MlpConfig};
+    use rstest::rstest;
+    use tch::Kind;
+
+    fn assert_same_targets(a: StepValueTarget, b: StepValueTarget, features: &dyn HistoryFeatures) {
+        tch::manual_seed(0);
+        let state_value_fn = MlpConfig::default().build_module(1, 1, Device::Cpu);
+        let discount_factor = 0.9;
+        let a_targets = a.targets(&state_value_fn, discount_factor, features);
+        let b_targets = b.targets(&state_value_fn, discount_factor, features);
+        assert_eq!(a_targets.structure(), b_targets.structure());
+        assert!(
+            a_targets
+                .tensor()
+                .allclose(b_targets.tensor(), 1e-5, 1e-6, false),
+            "{:?} != {:?}",
+            a_targets.tensor(),
+            b_targets.tensor()
+        );
+    }
+
+    #[rstest]
+    fn n_step_1_is_one_step(history: StoredHistory<BooleanSpace, IndexSpace>) {
+        assert_same_targets(
+            StepValueTarget::NStepTd {
+                n: NonZeroUsize::new(1).unwrap(),
+            },
+            StepValueTarget::OneStepTd,
+            &history.features(),
+        );
+    }
+
+    #[rstest]
+    fn lambda_0_is_one_step(history: StoredHistory<BooleanSpace, IndexSpace>) {
+        assert_same_targets(
+            StepValueTarget::TdLambda { lambda: 0.0 },
+            StepValueTarget::OneStepTd,
+            &history.features(),
+        );
+    }
+
+    #[rstest]
+    fn lambda_1_is_n_step_full(history: StoredHistory<BooleanSpace, IndexSpace>) {
+        assert_same_targets(
+            StepValueTarget::TdLambda { lambda: 1.0 },
+            StepValueTarget::NStepTd {
+                n: NonZeroUsize::new(100).unwrap(),
+            },
+            &history.features(),
+        );
+    }
+
+    #[rstest]
+    fn vtrace_on_policy_is_lambda_1(history: StoredHistory<BooleanSpace, IndexSpace>) {
+        assert_same_targets(
+            StepValueTarget::VTrace {
+                rho_bar: 1.0,
+                c_bar: 1.0,
+            },
+            StepValueTarget::TdLambda { lambda: 1.0 },
+            &history.features(),
+        );
+    }
+
+    #[rstest]
+    fn vtrace_truncated_is_lambda_1(mut history: StoredHistory<BooleanSpace, IndexSpace>) {
+        // Target policy is twice as likely as the behaviour policy to select each action.
+        history.set_behaviour_log_probs(|_| 0.5f64.ln());
+        assert_same_targets(
+            StepValueTarget::VTrace {
+                rho_bar: 1.0,
+                c_bar: 1.0,
+            },
+            StepValueTarget::TdLambda { lambda: 1.0 },
+            &history.features().with_target_log_probs(|_, actions| {
+                actions.batch_map_ref(|a| a.zeros_like().to_kind(Kind::Float))
+            }),
+        );
+    }
+
+    #[rstest]
+    fn vtrace_zero_truncation_is_value_estimate(history: StoredHistory<BooleanSpace, IndexSpace>) {
+        tch::manual_seed(0);
+        let state_value_fn = MlpConfig::default().build_module(1, 1, Device::Cpu);
+        let features = history.features();
+        let targets = tch::no_grad(|| {
+            StepValueTarget::VTrace {
+                rho_bar: 0.0,
+                c_bar: 0.0,
+            }
+            .targets(&state_value_fn, 0.9, &features)
+        });
+        let values =
+            tch::no_grad(|| eval_extended_state_values(&state_value_fn, &features).trim_end(1));
+        assert!(targets
+            .tensor()
+            .allclose(values.tensor(), 1e-5, 1e-6, false));
+    }
+
+    #[rstest]
+    fn vtrace_advantages_on_policy(history: StoredHistory<BooleanSpace, IndexSpace>) {
+        tch::manual_seed(0);
+        let state_value_fn = MlpConfig::default().build_module(1, 1, Device::Cpu);
+        let features = history.features();
+        let vtrace = AdvantageFn::VTrace {
+            rho_bar: 1.0,
+            c_bar: 1.0,
+        }
+        .advantages(&state_value_fn, 0.9, &features);
+        let gae = AdvantageFn::Gae { lambda: 1.0 }.advantages(&state_value_fn, 0.9, &features);
+        assert_eq!(vtrace.structure(), gae.structure());
+        assert!(vtrace.tensor().allclose(gae.tensor(), 1e-5, 1e-6, false));
+    }
+
+    #[test]
+    fn lookahead_indices_0() {
+        // Episode lengths [6, 4, 3, 1]
+        let extended_batch_sizes = [4, 4, 3, 3, 2, 1, 1];
+        let (indices, steps) = lookahead_indices(&extended_batch_sizes, 0);
+        assert_eq!(indices, [0, 1, 2, 3, 4, 5, 6, 8, 9, 10, 11, 12, 14, 16]);
+        assert_eq!(steps, [0; 14]);
+    }
+
+    #[test]
+    fn lookahead_indices_2() {
+        // Episode lengths [6, 4, 3, 1]
+        let extended_batch_sizes = [4, 4, 3, 3, 2, 1, 1];
+        let (indices, steps) = lookahead_indices(&extended_batch_sizes, 2);
+        assert_eq!(
+            indices,
+            [8, 9, 10, 7, 11, 12, 13, 14, 15, 13, 16, 15, 17, 17]
+        );
+        assert_eq!(steps, [2, 2, 2, 1, 2, 2, 2, 2, 2, 1, 2, 1, 2, 1]);
+    }
+}
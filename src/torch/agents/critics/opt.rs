@@ -6,7 +6,7 @@ use super::{
 use crate::torch::modules::{BuildModule, Module};
 use crate::torch::optimizers::{AdamConfig, BuildOptimizer, Optimizer};
 use serde::{Deserialize, Serialize};
-use tch::{COptimizer, Reduction};
+use tch::{COptimizer, Kind, Reduction};
 
 /// Configuration for [`ValuesOpt`]
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
@@ -34,6 +34,12 @@ pub struct ValuesOptConfig<MB, OC = AdamConfig> {
     /// Effectively sets a maximum horizon on the number of steps of future reward considered.
     /// Low values bias the value estimates but reduce variance.
     pub max_discount_factor: f64,
+    /// Clip each update of the state value estimates to within `clip_distance` of the estimates
+    /// from before the update.
+    ///
+    /// The value function clipping used in common implementations of
+    /// [PPO][crate::torch::agents::policies::Ppo].
+    pub clip_distance: Option<f64>,
 }
 
 impl<MB: Default, OC: Default> Default for ValuesOptConfig<MB, OC> {
@@ -45,6 +51,7 @@ impl<MB: Default, OC: Default> Default for ValuesOptConfig<MB, OC> {
             target: StepValueTarget::default(),
             opt_steps_per_update: 80,
             max_discount_factor: 0.99,
+            clip_distance: None,
         }
     }
 }
@@ -72,6 +79,7 @@ where
             target: self.target,
             discount_factor: self.max_discount_factor.min(discount_factor) as f32,
             opt_steps_per_update: self.opt_steps_per_update,
+            clip_distance: self.clip_distance,
         }
     }
 }
@@ -85,6 +93,7 @@ pub struct ValuesOpt<M, O = COptimizer> {
     target: StepValueTarget,
     discount_factor: f32,
     opt_steps_per_update: u64,
+    clip_distance: Option<f64>,
 }
 
 impl<M, O> Critic for ValuesOpt<M, O>
@@ -103,15 +112,35 @@ where
                 .targets(&self.state_value_fn, self.discount_factor, features)
         });
         let observations = features.observation_features();
+        let initial_values = self.clip_distance.map(|clip_distance| {
+            let values = tch::no_grad(|| {
+                self.state_value_fn
+                    .seq_packed(observations)
+                    .into_tensor()
+                    .squeeze_dim(-1)
+            });
+            (values, clip_distance)
+        });
 
         let sample_minibatch = || {};
 
         let loss_fn = |_| {
-            self.state_value_fn
+            let values = self
+                .state_value_fn
                 .seq_packed(observations)
-                .tensor()
-                .squeeze_dim(-1)
-                .mse_loss(targets.tensor(), Reduction::Mean)
+                .into_tensor()
+                .squeeze_dim(-1);
+            match &initial_values {
+                Some((initial_values, clip_distance)) => {
+                    let clipped_values = initial_values
+                        + (&values - initial_values).clamp(-clip_distance, *clip_distance);
+                    (values - targets.tensor())
+                        .square()
+                        .max_other(&(clipped_values - targets.tensor()).square())
+                        .mean(Kind::Float)
+                }
+                None => values.mse_loss(targets.tensor(), Reduction::Mean),
+            }
         };
 
         n_backward_steps(
@@ -1,25 +1,34 @@
 use super::critics::StepValueTarget;
 use super::features::{HistoryFeatures, LazyHistoryFeatures};
 use super::schedules::{DataCollectionSchedule, ExplorationRateSchedule};
-use super::{n_backward_steps, ToLog, WithCpuCopy};
-use crate::agents::buffers::{HistoryDataBound, ReplayBuffer};
-use crate::agents::{Actor, ActorMode, Agent, BatchUpdate, BuildAgent, BuildAgentError};
+use super::{n_backward_steps, polyak_update, ToLog, WithCpuCopy};
+use crate::agents::buffers::{
+    sample_prioritized, Episodes, HistoryDataBound, PrioritizedEpisodes, PrioritizedReplayBuffer,
+    ReplayBuffer, WriteExperience, WriteExperienceError, WriteExperienceIncremental,
+};
+use crate::agents::{
+    Actor, ActorMode, Agent, BatchActor, BatchUpdate, BuildAgent, BuildAgentError,
+};
 use crate::envs::EnvStructure;
 use crate::feedback::Reward;
 use crate::logging::StatsLogger;
+use crate::simulation::PartialStep;
 use crate::spaces::{FeatureSpace, FiniteSpace, NonEmptyFeatures, ReprSpace, SampleSpace, Space};
 use crate::torch::modules::{AsModule, BuildModule, Module, SeqIterative, SeqPacked};
 use crate::torch::optimizers::{AdamConfig, BuildOptimizer, Optimizer};
 use crate::torch::packed::PackedTensor;
 use crate::torch::serialize::DeviceDef;
 use crate::utils::sequence::Sequence;
+use crate::utils::slice::SplitSlice;
 use crate::Prng;
 use rand::distributions::{Distribution, Uniform};
 use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
 use std::iter;
+use std::num::NonZeroU64;
 use std::rc::Rc;
-use tch::{Device, Reduction, Tensor};
+use tch::{Device, Kind, Reduction, Tensor};
 
 /// Configuration for [`DqnAgent`]
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
@@ -28,10 +37,19 @@ pub struct DqnConfig<VB, OB = AdamConfig> {
     pub optimizer_config: OB,
 
     pub target: StepValueTarget,
+    /// Update strategy for the target action value module used in bootstrapped targets.
+    pub target_update: TargetUpdate,
+    /// Use Double Q-learning for bootstrapped targets.
+    ///
+    /// The greedy next action is selected by the trained action value module and evaluated by
+    /// the target module. Otherwise, the target module both selects and evaluates the action.
+    pub double_dqn: bool,
     pub exploration_rate: ExplorationRateSchedule,
     pub minibatch_steps: usize,
     pub opt_steps_per_update: usize,
     pub buffer_capacity: usize,
+    /// Prioritized experience replay configuration. Episodes are sampled uniformly if `None`.
+    pub prioritized_replay: Option<PrioritizedReplayConfig>,
     pub update_size: DataCollectionSchedule,
 
     #[serde(with = "DeviceDef")]
@@ -58,11 +76,14 @@ where
         Self {
             action_value_fn_config: VB::default(),
             optimizer_config: OB::default(),
-            target: StepValueTarget::default(), // TODO: Default OneStepTD; might need double DQN
+            target: StepValueTarget::default(), // TODO: Default OneStepTD
+            target_update: TargetUpdate::default(),
+            double_dqn: true,
             exploration_rate: ExplorationRateSchedule::default(),
             minibatch_steps: 100_000,
             opt_steps_per_update: 50,
             buffer_capacity: 10_000_000,
+            prioritized_replay: None,
             update_size: DataCollectionSchedule::FirstRest {
                 first: 1_000_000,
                 rest: 100_000,
@@ -72,6 +93,70 @@ where
     }
 }
 
+/// Update strategy for the target action value module of a [`DqnAgent`].
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TargetUpdate {
+    /// Copy the action value module into the target module every `period` optimization steps.
+    ///
+    /// A period of `1` is equivalent to bootstrapping from the trained module.
+    Hard { period: NonZeroU64 },
+    /// Polyak averaging on every optimization step: `target = (1 - rate) * target + rate * module`
+    Polyak { rate: f64 },
+}
+
+impl Default for TargetUpdate {
+    fn default() -> Self {
+        Self::Hard {
+            period: NonZeroU64::new(1_000).unwrap(),
+        }
+    }
+}
+
+impl TargetUpdate {
+    /// Update a target module given the number of optimization steps taken so far.
+    fn update<M: Module + ?Sized>(&self, target: &M, module: &M, opt_steps: u64) {
+        match self {
+            Self::Hard { period } => {
+                if opt_steps.is_multiple_of(period.get()) {
+                    polyak_update(target, module, 1.0);
+                }
+            }
+            Self::Polyak { rate } => polyak_update(target, module, *rate),
+        }
+    }
+}
+
+/// Configuration for prioritized experience replay in a [`DqnAgent`].
+///
+/// Episodes are prioritized by the mean absolute temporal-difference error of their steps.
+/// See [`PrioritizedReplayBuffer`].
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrioritizedReplayConfig {
+    /// Priority exponent. `0` is uniform sampling, `1` is fully prioritized.
+    pub alpha: ExplorationRateSchedule,
+    /// Importance sampling exponent. `1` fully corrects for the prioritization bias.
+    pub beta: ExplorationRateSchedule,
+    /// Constant added to the priorities so that no episode has zero probability.
+    pub epsilon: f64,
+}
+
+/// Default initialization based on the [Rainbow DQN][rdqn] paper.
+///
+/// [rdqn]: https://arxiv.org/pdf/1710.02298.pdf
+impl Default for PrioritizedReplayConfig {
+    fn default() -> Self {
+        Self {
+            alpha: ExplorationRateSchedule::Constant(0.5),
+            beta: ExplorationRateSchedule::LinearAnnealed {
+                start: 0.4,
+                end: 1.0,
+                period: 10_000_000,
+            },
+            epsilon: 1e-6,
+        }
+    }
+}
+
 impl<OS, AS, FS, VB, OB> BuildAgent<OS, AS, FS> for DqnConfig<VB, OB>
 where
     OS: FeatureSpace + Clone,
@@ -104,7 +189,16 @@ where
 /// "[Rainbow: Combining Improvements in Deep Reinforcement Learning][rainbow]"
 /// by Hessel et al. (2017)
 ///
+/// with target networks from
+/// "[Human-level control through deep reinforcement learning][nature]"
+/// by Volodymyr Mnih et al. (2015)
+/// and double Q-learning from
+/// "[Deep Reinforcement Learning with Double Q-learning][ddqn]"
+/// by van Hasselt et al. (2015)
+///
 /// [dqn]: https://arxiv.org/pdf/1312.5602.pdf
+/// [nature]: https://www.nature.com/articles/nature14236
+/// [ddqn]: https://arxiv.org/abs/1509.06461
 /// [rainbow]: https://arxiv.org/pdf/1710.02298.pdf
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DqnAgent<OS, AS, V: AsModule, O> {
@@ -112,19 +206,26 @@ pub struct DqnAgent<OS, AS, V: AsModule, O> {
     action_space: AS,
 
     action_value_fn: WithCpuCopy<V>,
+    /// Lagged copy of `action_value_fn` used for bootstrapped targets.
+    target_action_value_fn: V,
     optimizer: O,
 
     target: StepValueTarget,
+    target_update: TargetUpdate,
+    double_dqn: bool,
     exploration_rate: ExplorationRateSchedule,
     minibatch_steps: usize,
     opt_steps_per_update: usize,
     /// Capacity of each individual buffer
     buffer_capacity: usize,
+    prioritized_replay: Option<PrioritizedReplayConfig>,
     update_size: DataCollectionSchedule,
     discount_factor: f32,
 
     /// Total number of collected steps in all updates.
     global_steps: u64,
+    /// Total number of optimization steps in all updates.
+    opt_steps: u64,
 
     // Tensors will deserialize to CPU
     #[serde(skip, default = "cpu_device")]
@@ -153,10 +254,19 @@ where
         let num_observation_features = observation_space.num_features();
         let num_actions = action_space.size();
 
-        let action_value_fn = config.action_value_fn_config.build_module(
-            num_observation_features,
-            num_actions,
-            config.device,
+        let build_action_value_fn = || {
+            config.action_value_fn_config.build_module(
+                num_observation_features,
+                num_actions,
+                config.device,
+            )
+        };
+        let action_value_fn = build_action_value_fn();
+        let target_action_value_fn = build_action_value_fn();
+        polyak_update(
+            target_action_value_fn.as_module(),
+            action_value_fn.as_module(),
+            1.0,
         );
 
         let optimizer = config
@@ -168,15 +278,20 @@ where
             observation_space,
             action_space,
             action_value_fn: WithCpuCopy::new(action_value_fn, config.device),
+            target_action_value_fn,
             optimizer,
             target: config.target,
+            target_update: config.target_update,
+            double_dqn: config.double_dqn,
             exploration_rate: config.exploration_rate,
             minibatch_steps: config.minibatch_steps,
             opt_steps_per_update: config.opt_steps_per_update,
             buffer_capacity: config.buffer_capacity,
+            prioritized_replay: config.prioritized_replay,
             update_size: config.update_size,
             discount_factor: env.discount_factor() as f32,
             global_steps: 0,
+            opt_steps: 0,
             device: config.device,
             rng,
         }
@@ -223,10 +338,16 @@ where
     O: Optimizer,
 {
     type Feedback = Reward;
-    type HistoryBuffer = ReplayBuffer<OS::Element, AS::Element>;
+    type HistoryBuffer = DqnReplayBuffer<OS::Element, AS::Element>;
 
     fn buffer(&self) -> Self::HistoryBuffer {
-        ReplayBuffer::with_capacity(self.buffer_capacity)
+        match self.prioritized_replay {
+            Some(config) => DqnReplayBuffer::Prioritized(PrioritizedReplayBuffer::with_capacity(
+                self.buffer_capacity,
+                config.alpha.value(self.global_steps),
+            )),
+            None => DqnReplayBuffer::Uniform(ReplayBuffer::with_capacity(self.buffer_capacity)),
+        }
     }
 
     fn min_update_size(&self) -> HistoryDataBound {
@@ -262,7 +383,7 @@ where
     /// Batch update given a slice of buffer references
     fn batch_update_slice_refs(
         &mut self,
-        buffers: &mut [&mut ReplayBuffer<OS::Element, AS::Element>],
+        buffers: &mut [&mut DqnReplayBuffer<OS::Element, AS::Element>],
         logger: &mut dyn StatsLogger,
     ) {
         logger.log_scalar(
@@ -274,55 +395,98 @@ where
         // Update the global step count.
         self.global_steps = buffers.iter().map(|b| b.total_step_count()).sum();
 
+        let priority_beta = self.prioritized_replay.map(|config| {
+            let alpha = config.alpha.value(self.global_steps);
+            for buffer in buffers.iter_mut() {
+                buffer.prioritized_mut().set_alpha(alpha);
+            }
+            config.beta.value(self.global_steps)
+        });
+        if let Some(beta) = priority_beta {
+            logger.log_scalar("priority_beta", beta);
+        }
+
         // Mutably borrow the action value fn to invalidate any CPU copy
         let _ = self.action_value_fn.as_module_mut();
 
         let sample_minibatch = || {
-            let sampled_episodes = iter::repeat(&*buffers).flatten().map(|buf| {
-                buf.episodes()
-                    .get(Uniform::new(0usize, buf.num_episodes()).sample(&mut self.rng))
-                    .unwrap()
-            });
-            let mut total_steps = 0;
-            let minibatch_episodes = sampled_episodes.take_while(|ep| {
-                let take = total_steps < self.minibatch_steps;
-                total_steps += ep.len();
-                take
-            });
-            let features = LazyHistoryFeatures::new(
-                minibatch_episodes,
-                &self.observation_space,
-                &self.action_space,
-                self.device,
-            );
-
-            // TODO: Separate policy/target networks (double DQN)
-            let targets = tch::no_grad(|| {
-                self.target.targets(
-                    &self
-                        .action_value_fn
-                        .as_module()
-                        .batch_map(|action_values| action_values.amax(&[-1], false)),
-                    self.discount_factor,
-                    &features,
+            // The target module is updated prior to each optimization step
+            let action_value_fn = self.action_value_fn.as_module();
+            let target_action_value_fn = self.target_action_value_fn.as_module();
+            self.target_update
+                .update(target_action_value_fn, action_value_fn, self.opt_steps);
+            self.opt_steps += 1;
+
+            let (observations, actions, targets, prioritized) = {
+                let (episodes, prioritized) = if let Some(beta) = priority_beta {
+                    let buffer_refs: Vec<_> = buffers.iter().map(|b| b.prioritized()).collect();
+                    let sample = sort_by_length(&sample_prioritized(
+                        &buffer_refs,
+                        self.minibatch_steps,
+                        beta,
+                        &mut self.rng,
+                    ));
+                    let lengths: Vec<_> = sample.episodes.iter().map(Sequence::len).collect();
+                    (
+                        sample.episodes,
+                        Some((sample.locations, sample.weights, lengths)),
+                    )
+                } else {
+                    let episodes = sample_uniform(&*buffers, self.minibatch_steps, &mut self.rng);
+                    (episodes, None)
+                };
+                let features = LazyHistoryFeatures::new(
+                    episodes,
+                    &self.observation_space,
+                    &self.action_space,
+                    self.device,
+                );
+
+                let targets = tch::no_grad(|| {
+                    action_value_targets(
+                        self.target,
+                        action_value_fn,
+                        target_action_value_fn,
+                        self.double_dqn,
+                        self.discount_factor,
+                        &features,
+                    )
+                });
+                let observations = features.observation_features().clone();
+                let actions = features.actions().tensor().unsqueeze(-1);
+                (observations, actions, targets, prioritized)
+            };
+
+            // Update the episode priorities and get importance sampling weights for each step
+            let step_weights = prioritized.map(|(locations, weights, lengths)| {
+                let abs_errors = tch::no_grad(|| {
+                    (selected_action_values(action_value_fn, &observations, &actions)
+                        - targets.tensor())
+                    .abs()
+                });
+                update_priorities(
+                    buffers,
+                    &locations,
+                    &weights,
+                    &lengths,
+                    &abs_errors,
+                    self.prioritized_replay.unwrap().epsilon,
                 )
             });
-            let observations = features.observation_features();
-            let actions = features.actions().tensor().unsqueeze(-1);
 
-            Rc::new((observations.clone(), actions, targets))
+            Rc::new((observations, actions, targets, step_weights))
         };
 
-        let loss_fn = |data: Rc<(PackedTensor, Tensor, PackedTensor)>| {
-            let (observations, actions, targets) = data.as_ref();
-            let action_values = self
-                .action_value_fn
-                .as_module()
-                .seq_packed(observations)
-                .tensor()
-                .gather(-1, actions, false)
-                .squeeze_dim(-1);
-            action_values.mse_loss(targets.tensor(), Reduction::Mean)
+        let loss_fn = |data: Rc<(PackedTensor, Tensor, PackedTensor, Option<Tensor>)>| {
+            let (observations, actions, targets, step_weights) = data.as_ref();
+            let action_values =
+                selected_action_values(self.action_value_fn.as_module(), observations, actions);
+            match step_weights {
+                Some(weights) => {
+                    ((action_values - targets.tensor()).square() * weights).mean(Kind::Float)
+                }
+                None => action_values.mse_loss(targets.tensor(), Reduction::Mean),
+            }
         };
 
         n_backward_steps(
@@ -337,6 +501,253 @@ where
     }
 }
 
+/// Replay buffer of a [`DqnAgent`].
+///
+/// Prioritized if the agent is configured with
+/// [`prioritized_replay`](DqnConfig::prioritized_replay), otherwise a plain [`ReplayBuffer`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DqnReplayBuffer<O, A> {
+    Uniform(ReplayBuffer<O, A>),
+    Prioritized(PrioritizedReplayBuffer<O, A>),
+}
+
+impl<O, A> DqnReplayBuffer<O, A> {
+    /// The number of episodes stored in the buffer.
+    #[must_use]
+    pub fn num_episodes(&self) -> usize {
+        match self {
+            Self::Uniform(buffer) => buffer.num_episodes(),
+            Self::Prioritized(buffer) => buffer.num_episodes(),
+        }
+    }
+
+    /// View all episodes stored in the buffer.
+    #[must_use]
+    pub fn episodes(&self) -> Episodes<'_, O, A, Reward> {
+        match self {
+            Self::Uniform(buffer) => buffer.episodes(),
+            Self::Prioritized(buffer) => buffer.episodes(),
+        }
+    }
+
+    /// Total number of steps consumed by the buffer over its lifetime.
+    #[must_use]
+    pub const fn total_step_count(&self) -> u64 {
+        match self {
+            Self::Uniform(buffer) => buffer.total_step_count(),
+            Self::Prioritized(buffer) => buffer.total_step_count(),
+        }
+    }
+
+    /// The prioritized buffer.
+    ///
+    /// # Panics
+    /// If the buffer is not prioritized.
+    fn prioritized(&self) -> &PrioritizedReplayBuffer<O, A> {
+        match self {
+            Self::Prioritized(buffer) => buffer,
+            Self::Uniform(_) => panic!("prioritized replay requires a prioritized buffer"),
+        }
+    }
+
+    /// The prioritized buffer.
+    ///
+    /// # Panics
+    /// If the buffer is not prioritized.
+    fn prioritized_mut(&mut self) -> &mut PrioritizedReplayBuffer<O, A> {
+        match self {
+            Self::Prioritized(buffer) => buffer,
+            Self::Uniform(_) => panic!("prioritized replay requires a prioritized buffer"),
+        }
+    }
+}
+
+impl<O, A> WriteExperienceIncremental<O, A, Reward> for DqnReplayBuffer<O, A> {
+    fn write_step(&mut self, step: PartialStep<O, A>) -> Result<(), WriteExperienceError> {
+        match self {
+            Self::Uniform(buffer) => buffer.write_step(step),
+            Self::Prioritized(buffer) => buffer.write_step(step),
+        }
+    }
+
+    fn end_experience(&mut self) {
+        match self {
+            Self::Uniform(buffer) => buffer.end_experience(),
+            Self::Prioritized(buffer) => buffer.end_experience(),
+        }
+    }
+}
+
+impl<O, A> WriteExperience<O, A, Reward> for DqnReplayBuffer<O, A> {}
+
+/// Estimated values of the selected actions.
+///
+/// # Args
+/// * `action_value_fn` - Action value module.
+/// * `observations` - Packed observation features.
+/// * `actions` - Selected action indices. Has shape `[NUM_STEPS, 1]`.
+fn selected_action_values<V: SeqPacked + ?Sized>(
+    action_value_fn: &V,
+    observations: &PackedTensor,
+    actions: &Tensor,
+) -> Tensor {
+    action_value_fn
+        .seq_packed(observations)
+        .tensor()
+        .gather(-1, actions, false)
+        .squeeze_dim(-1)
+}
+
+/// Step value targets for the action value module.
+///
+/// Bootstraps from the target module, using the online module for action selection
+/// if `double_dqn` is true.
+fn action_value_targets<V: Module + SeqPacked + ?Sized>(
+    target: StepValueTarget,
+    action_value_fn: &V,
+    target_action_value_fn: &V,
+    double_dqn: bool,
+    discount_factor: f32,
+    features: &dyn HistoryFeatures,
+) -> PackedTensor {
+    if double_dqn {
+        target.targets(
+            &DoubleQStateValues {
+                action_value_fn,
+                target_action_value_fn,
+            },
+            discount_factor,
+            features,
+        )
+    } else {
+        target.targets(
+            &target_action_value_fn.batch_map(|action_values| action_values.amax(&[-1], false)),
+            discount_factor,
+            features,
+        )
+    }
+}
+
+/// Sample episodes uniformly from each buffer in turn until at least `min_steps` are collected.
+fn sample_uniform<'a, O, A, R: Rng + ?Sized>(
+    buffers: &'a [&mut DqnReplayBuffer<O, A>],
+    min_steps: usize,
+    rng: &mut R,
+) -> Vec<SplitSlice<'a, PartialStep<O, A>>> {
+    let sampled_episodes = iter::repeat(buffers).flatten().map(|buf| {
+        buf.episodes()
+            .get(Uniform::new(0usize, buf.num_episodes()).sample(rng))
+            .unwrap()
+    });
+    let mut total_steps = 0;
+    sampled_episodes
+        .take_while(|ep| {
+            let take = total_steps < min_steps;
+            total_steps += ep.len();
+            take
+        })
+        .collect()
+}
+
+/// Set episode priorities from step errors and get importance sampling weights for each step.
+///
+/// # Args
+/// * `buffers` - Buffers from which the episodes were sampled.
+/// * `locations` - Buffer and episode index of each sampled episode.
+/// * `weights` - Importance sampling weight of each sampled episode.
+/// * `lengths` - Length of each sampled episode. Must be in monotonic decreasing order.
+/// * `abs_errors` - Packed absolute errors of each step in the sampled episodes.
+/// * `epsilon` - Constant added to each priority.
+///
+/// # Returns
+/// Packed importance sampling weights of each step in the sampled episodes.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn update_priorities<O, A>(
+    buffers: &mut [&mut DqnReplayBuffer<O, A>],
+    locations: &[(usize, usize)],
+    weights: &[f64],
+    lengths: &[usize],
+    abs_errors: &Tensor,
+    epsilon: f64,
+) -> Tensor {
+    let device = abs_errors.device();
+    let step_episodes = Tensor::of_slice(&packed_episode_indices(lengths)).to(device);
+
+    // Mean absolute error of each episode
+    let lengths: Vec<_> = lengths.iter().map(|&len| len as f32).collect();
+    let priorities = Tensor::zeros(
+        &[lengths.len().try_into().unwrap()],
+        (abs_errors.kind(), device),
+    )
+    .index_add(0, &step_episodes, abs_errors)
+        / Tensor::of_slice(&lengths).to(device);
+    for (&(buffer_index, episode_index), priority) in locations
+        .iter()
+        .zip(Vec::<f64>::from(&priorities.to_kind(Kind::Double)))
+    {
+        buffers[buffer_index]
+            .prioritized_mut()
+            .set_episode_priority(episode_index, priority + epsilon);
+    }
+
+    let weights: Vec<_> = weights.iter().map(|&w| w as f32).collect();
+    Tensor::of_slice(&weights)
+        .to(device)
+        .index_select(0, &step_episodes)
+}
+
+/// Stable sort of sampled episodes in monotonic decreasing order of length.
+fn sort_by_length<'a, O, A, F>(
+    sample: &PrioritizedEpisodes<'a, O, A, F>,
+) -> PrioritizedEpisodes<'a, O, A, F> {
+    let mut order: Vec<usize> = (0..sample.episodes.len()).collect();
+    order.sort_by_key(|&i| Reverse(sample.episodes[i].len()));
+    PrioritizedEpisodes {
+        episodes: order.iter().map(|&i| sample.episodes[i]).collect(),
+        locations: order.iter().map(|&i| sample.locations[i]).collect(),
+        weights: order.iter().map(|&i| sample.weights[i]).collect(),
+    }
+}
+
+/// The episode index of each step when packing episodes sorted in decreasing order of length.
+fn packed_episode_indices(sorted_lengths: &[usize]) -> Vec<i64> {
+    let max_length = sorted_lengths.first().copied().unwrap_or(0);
+    (0..max_length)
+        .flat_map(|offset| {
+            sorted_lengths
+                .iter()
+                .take_while(move |&&len| len > offset)
+                .zip(0..)
+                .map(|(_, episode_index)| episode_index)
+        })
+        .collect()
+}
+
+/// State values of the greedy action for double Q-learning.
+///
+/// The greedy action is selected by `action_value_fn` and evaluated by `target_action_value_fn`.
+struct DoubleQStateValues<'a, V: ?Sized> {
+    action_value_fn: &'a V,
+    target_action_value_fn: &'a V,
+}
+
+impl<V: SeqPacked + ?Sized> SeqPacked for DoubleQStateValues<'_, V> {
+    fn seq_packed(&self, inputs: &PackedTensor) -> PackedTensor {
+        let greedy_actions = self
+            .action_value_fn
+            .seq_packed(inputs)
+            .tensor()
+            .argmax(-1, true);
+        self.target_action_value_fn
+            .seq_packed(inputs)
+            .batch_map(|action_values| {
+                action_values
+                    .gather(-1, &greedy_actions, false)
+                    .squeeze_dim(-1)
+            })
+    }
+}
+
 #[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DqnActor<OS, AS, V> {
     observation_space: NonEmptyFeatures<OS>,
@@ -379,19 +790,90 @@ where
     }
 }
 
+impl<OS, AS, V> BatchActor<OS::Element, AS::Element> for DqnActor<OS, AS, V>
+where
+    OS: FeatureSpace,
+    AS: FiniteSpace + SampleSpace,
+    V: SeqIterative,
+{
+    fn batch_act(
+        &self,
+        episode_states: &mut [&mut Self::EpisodeState],
+        observations: &[&OS::Element],
+        rng: &mut Prng,
+    ) -> Vec<AS::Element> {
+        assert_eq!(
+            episode_states.len(),
+            observations.len(),
+            "mismatched number of episode states and observations"
+        );
+        // Exploratory actions are sampled first, in order, then the remaining greedy actions
+        // are selected with a single forward pass over their episodes.
+        let mut actions: Vec<Option<AS::Element>> = (0..observations.len())
+            .map(|_| {
+                if rng.gen_bool(self.exploration_rate) {
+                    Some(self.action_space.sample(rng))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let greedy_indices: Vec<_> = actions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, action)| if action.is_none() { Some(i) } else { None })
+            .collect();
+        if !greedy_indices.is_empty() {
+            let _no_grad = tch::no_grad_guard();
+            let observation_features: Tensor = self
+                .observation_space
+                .batch_features(greedy_indices.iter().map(|&i| observations[i]));
+            let mut greedy_states: Vec<_> = episode_states
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(i, state)| actions[i].is_none().then_some(&mut **state))
+                .collect();
+            let action_indices: Vec<i64> = self
+                .action_value_fn
+                .batch_step(&mut greedy_states, &observation_features)
+                .argmax(-1, false)
+                .into();
+            for (i, action_index) in greedy_indices.into_iter().zip(action_indices) {
+                actions[i] = Some(
+                    self.action_space
+                        .from_index(action_index.try_into().unwrap())
+                        .unwrap(),
+                );
+            }
+        }
+        actions.into_iter().map(Option::unwrap).collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::critics::StepValueTarget;
     use super::*;
     use crate::agents::testing;
+    use crate::envs::Chain;
     use crate::torch::modules::{BuildModule, GruMlpConfig, MlpConfig, SeqIterative, SeqPacked};
     use crate::torch::optimizers::AdamConfig;
     use rstest::rstest;
+    use std::num::NonZeroUsize;
 
     #[rstest]
     fn learns_deterministic_bandit<MB>(
         #[values(MlpConfig::default(), GruMlpConfig::default())] module: MB,
-        #[values(StepValueTarget::RewardToGo, StepValueTarget::OneStepTd)] target: StepValueTarget,
+        #[values(
+            StepValueTarget::RewardToGo,
+            StepValueTarget::OneStepTd,
+            StepValueTarget::NStepTd {
+                n: NonZeroUsize::new(3).unwrap()
+            },
+            StepValueTarget::TdLambda { lambda: 0.9 }
+        )]
+        target: StepValueTarget,
         #[values(Device::Cpu, Device::cuda_if_available())] device: Device,
     ) where
         MB: BuildModule + Default,
@@ -412,4 +894,114 @@ mod tests {
         };
         testing::train_deterministic_bandit(&config, 10, 0.9);
     }
+
+    #[test]
+    fn batch_act_matches_act() {
+        let env = Chain::default();
+        let mut rng = Prng::seed_from_u64(0);
+        let agent = DqnConfig::<GruMlpConfig>::default()
+            .build_agent(&env, &mut rng)
+            .unwrap();
+        let actor = agent.actor(ActorMode::Evaluation);
+
+        // Observations for 3 episodes over 4 steps
+        let observations = [[0, 1, 2], [3, 3, 0], [1, 4, 4], [2, 0, 1]];
+        let mut batch_states: Vec<_> = (0..3).map(|_| actor.initial_state(&mut rng)).collect();
+        let mut states: Vec<_> = (0..3).map(|_| actor.initial_state(&mut rng)).collect();
+        for step_observations in observations {
+            let mut batch_state_refs: Vec<_> = batch_states.iter_mut().collect();
+            let observation_refs: Vec<_> = step_observations.iter().collect();
+            let batch_actions = actor.batch_act(&mut batch_state_refs, &observation_refs, &mut rng);
+            let actions: Vec<_> = states
+                .iter_mut()
+                .zip(&step_observations)
+                .map(|(state, observation)| actor.act(state, observation, &mut rng))
+                .collect();
+            assert_eq!(batch_actions, actions);
+        }
+    }
+
+    #[rstest]
+    fn learns_deterministic_bandit_target_update(
+        #[values(
+            TargetUpdate::Hard { period: NonZeroU64::new(1).unwrap() },
+            TargetUpdate::Hard { period: NonZeroU64::new(10).unwrap() },
+            TargetUpdate::Polyak { rate: 0.1 }
+        )]
+        target_update: TargetUpdate,
+        #[values(false, true)] double_dqn: bool,
+    ) {
+        let config = DqnConfig {
+            action_value_fn_config: MlpConfig::default(),
+            optimizer_config: AdamConfig {
+                learning_rate: 0.1,
+                ..AdamConfig::default()
+            },
+            target: StepValueTarget::OneStepTd,
+            target_update,
+            double_dqn,
+            minibatch_steps: 4,
+            buffer_capacity: 20,
+            update_size: DataCollectionSchedule::FirstRest { first: 10, rest: 4 },
+            device: Device::Cpu,
+            ..Default::default()
+        };
+        testing::train_deterministic_bandit(&config, 10, 0.9);
+    }
+
+    #[test]
+    fn target_update_rejects_zero_period() {
+        use serde_test::{assert_de_tokens_error, Token};
+        assert_de_tokens_error::<TargetUpdate>(
+            &[
+                Token::StructVariant {
+                    name: "TargetUpdate",
+                    variant: "Hard",
+                    len: 1,
+                },
+                Token::Str("period"),
+                Token::U64(0),
+            ],
+            "invalid value: integer `0`, expected a nonzero u64",
+        );
+    }
+
+    #[rstest]
+    fn learns_deterministic_bandit_prioritized_replay<MB>(
+        #[values(MlpConfig::default(), GruMlpConfig::default())] module: MB,
+    ) where
+        MB: BuildModule + Default,
+        MB::Module: SeqPacked + SeqIterative,
+    {
+        let config = DqnConfig {
+            action_value_fn_config: module,
+            optimizer_config: AdamConfig {
+                learning_rate: 0.1,
+                ..AdamConfig::default()
+            },
+            target: StepValueTarget::OneStepTd,
+            minibatch_steps: 4,
+            buffer_capacity: 20,
+            prioritized_replay: Some(PrioritizedReplayConfig {
+                beta: ExplorationRateSchedule::LinearAnnealed {
+                    start: 0.4,
+                    end: 1.0,
+                    period: 50,
+                },
+                ..PrioritizedReplayConfig::default()
+            }),
+            update_size: DataCollectionSchedule::FirstRest { first: 10, rest: 4 },
+            device: Device::Cpu,
+            ..Default::default()
+        };
+        testing::train_deterministic_bandit(&config, 10, 0.9);
+    }
+
+    #[test]
+    fn packed_episode_indices() {
+        assert_eq!(
+            super::packed_episode_indices(&[3, 2, 2, 1]),
+            vec![0, 1, 2, 3, 0, 1, 2, 0]
+        );
+    }
 }
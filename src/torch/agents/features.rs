@@ -8,6 +8,7 @@ use crate::utils::sequence::Sequence;
 use ndarray::Axis;
 use once_cell::unsync::OnceCell;
 use std::cmp::Reverse;
+use std::fmt;
 use tch::{Device, Tensor};
 
 /// View features of a (mini-)batch of collected history.
@@ -39,15 +40,39 @@ pub trait HistoryFeatures {
     /// Packed rewards. A 1D f32 tensor.
     fn rewards(&self) -> &PackedTensor;
 
+    /// Packed log probabilities with which the behaviour policy selected each action.
+    ///
+    /// A 1D `f32` tensor. `None` if the log probability is unknown for any step.
+    fn behaviour_log_probs(&self) -> Option<&PackedTensor>;
+
+    /// Packed log importance sampling ratios of the target policy to the behaviour policy.
+    ///
+    /// A 1D `f32` tensor of `log π(a_t | h_t) - log μ(a_t | h_t)` where `π` is the target policy
+    /// and `μ` is the behaviour policy that collected the experience.
+    /// `None` if either set of log probabilities is unavailable.
+    fn importance_log_ratios(&self) -> Option<&PackedTensor>;
+
     /// Device on which tensors will be placed.
     fn device(&self) -> Device;
 }
 
+/// Evaluate target policy log probabilities given packed observation features and actions.
+#[allow(clippy::type_complexity)]
+struct TargetLogProbsFn<'a>(Box<dyn Fn(&PackedTensor, &PackedTensor) -> PackedTensor + 'a>);
+
+impl fmt::Debug for TargetLogProbsFn<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("TargetLogProbsFn")
+    }
+}
+
 /// Packed history features with lazy evaluation and caching.
 #[derive(Debug)]
 pub struct LazyHistoryFeatures<'a, OS: Space + ?Sized, AS: Space + ?Sized, E> {
     /// Episodes sorted in monotonic decreasing order of length
     episodes: Vec<E>,
+    /// Original index of each (sorted) episode in the sequence given to `new`.
+    episode_order: Vec<usize>,
     observation_space: &'a OS,
     action_space: &'a AS,
     device: Device,
@@ -59,6 +84,12 @@ pub struct LazyHistoryFeatures<'a, OS: Space + ?Sized, AS: Space + ?Sized, E> {
     cached_extended_observation_features: OnceCell<(PackedTensor, PackedTensor)>,
     cached_actions: OnceCell<PackedTensor>,
     cached_rewards: OnceCell<PackedTensor>,
+    cached_importance_log_ratios: OnceCell<Option<PackedTensor>>,
+
+    /// Packed behaviour policy log probabilities, if set by `with_behaviour_log_probs`.
+    behaviour_log_probs: Option<PackedTensor>,
+    /// Evaluates the target policy log probabilities for [`HistoryFeatures::importance_log_ratios`].
+    target_log_probs_fn: Option<TargetLogProbsFn<'a>>,
 }
 
 impl<'a, OS, AS, E> LazyHistoryFeatures<'a, OS, AS, E>
@@ -76,8 +107,10 @@ where
     where
         I: IntoIterator<Item = E>,
     {
-        let mut episodes: Vec<_> = episodes.into_iter().collect();
-        episodes.sort_unstable_by_key(|ep| Reverse(ep.len()));
+        let mut indexed_episodes: Vec<_> = episodes.into_iter().enumerate().collect();
+        // Stable sort so that episodes given in sorted order keep their relative order.
+        indexed_episodes.sort_by_key(|(_, ep)| Reverse(ep.len()));
+        let (episode_order, episodes): (Vec<_>, Vec<_>) = indexed_episodes.into_iter().unzip();
 
         let extended_structure =
             PackedStructure::from_sorted_sequence_lengths(episodes.iter().map(|ep| ep.len() + 1))
@@ -85,6 +118,7 @@ where
 
         Self {
             episodes,
+            episode_order,
             observation_space,
             action_space,
             device,
@@ -93,7 +127,58 @@ where
             cached_extended_observation_features: OnceCell::new(),
             cached_actions: OnceCell::new(),
             cached_rewards: OnceCell::new(),
+            cached_importance_log_ratios: OnceCell::new(),
+            behaviour_log_probs: None,
+            target_log_probs_fn: None,
+        }
+    }
+
+    /// Set the function used to evaluate target policy action log probabilities.
+    ///
+    /// The function is called (at most once) with the packed observation features and actions
+    /// and must return the packed log probabilities of the actions as a 1D `f32` tensor.
+    #[must_use]
+    pub fn with_target_log_probs<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&PackedTensor, &PackedTensor) -> PackedTensor + 'a,
+    {
+        self.target_log_probs_fn = Some(TargetLogProbsFn(Box::new(f)));
+        self
+    }
+
+    /// Set the log probabilities with which the behaviour policy selected each action.
+    ///
+    /// `log_probs` has one entry per step with the episodes in the order given to `new`.
+    ///
+    /// # Panics
+    /// If the number of log probabilities differs from the number of steps.
+    #[allow(clippy::cast_possible_truncation)]
+    #[must_use]
+    pub fn with_behaviour_log_probs(mut self, log_probs: &[f64]) -> Self {
+        let mut episode_lengths = vec![0; self.episodes.len()];
+        for (&i, episode) in self.episode_order.iter().zip(&self.episodes) {
+            episode_lengths[i] = episode.len();
+        }
+        assert_eq!(
+            log_probs.len(),
+            episode_lengths.iter().sum::<usize>(),
+            "number of log probabilities does not match the number of steps"
+        );
+        let mut episode_log_probs = Vec::with_capacity(episode_lengths.len());
+        let mut rest = log_probs;
+        for len in episode_lengths {
+            let (episode, tail) = rest.split_at(len);
+            episode_log_probs.push(episode);
+            rest = tail;
         }
+
+        let sorted_log_probs: Vec<_> =
+            PackedSeqIter::from_sorted(self.episode_order.iter().map(|&i| episode_log_probs[i]))
+                .map(|&p| p as f32)
+                .collect();
+        let tensor = Tensor::of_slice(&sorted_log_probs).to(self.device);
+        self.behaviour_log_probs = Some(PackedTensor::from_parts(tensor, self.structure()));
+        self
     }
 
     pub fn num_steps(&self) -> usize {
@@ -207,6 +292,22 @@ where
         })
     }
 
+    fn behaviour_log_probs(&self) -> Option<&PackedTensor> {
+        self.behaviour_log_probs.as_ref()
+    }
+
+    fn importance_log_ratios(&self) -> Option<&PackedTensor> {
+        self.cached_importance_log_ratios
+            .get_or_init(|| {
+                let target_log_probs_fn = self.target_log_probs_fn.as_ref()?;
+                let behaviour_log_probs = self.behaviour_log_probs()?;
+                let target_log_probs =
+                    (target_log_probs_fn.0)(self.observation_features(), self.actions());
+                Some(target_log_probs.batch_map(|t| t - behaviour_log_probs.tensor()))
+            })
+            .as_ref()
+    }
+
     fn device(&self) -> Device {
         self.device
     }
@@ -268,9 +369,11 @@ pub(crate) mod tests {
     use crate::feedback::Reward;
     use crate::spaces::{BooleanSpace, IndexSpace};
     use rstest::{fixture, rstest};
+    use tch::Kind;
 
     pub struct StoredHistory<OS: Space, AS: Space> {
         episodes: Vec<Vec<PartialStep<OS::Element, AS::Element>>>,
+        behaviour_log_probs: Option<Vec<f64>>,
         observation_space: OS,
         action_space: AS,
         device: Device,
@@ -281,12 +384,23 @@ pub(crate) mod tests {
         pub fn features(
             &self,
         ) -> LazyHistoryFeatures<OS, AS, &[PartialStep<OS::Element, AS::Element>]> {
-            LazyHistoryFeatures::new(
+            let features = LazyHistoryFeatures::new(
                 self.episodes.iter().map(AsRef::as_ref),
                 &self.observation_space,
                 &self.action_space,
                 self.device,
-            )
+            );
+            match &self.behaviour_log_probs {
+                Some(log_probs) => features.with_behaviour_log_probs(log_probs),
+                None => features,
+            }
+        }
+
+        pub fn set_behaviour_log_probs<F>(&mut self, f: F)
+        where
+            F: Fn(&PartialStep<OS::Element, AS::Element>) -> f64,
+        {
+            self.behaviour_log_probs = Some(self.episodes.iter().flatten().map(f).collect());
         }
     }
 
@@ -325,6 +439,7 @@ pub(crate) mod tests {
 
         StoredHistory {
             episodes,
+            behaviour_log_probs: None,
             observation_space: BooleanSpace::new(),
             action_space: IndexSpace::new(31),
             device: Device::Cpu,
@@ -390,6 +505,43 @@ pub(crate) mod tests {
         );
     }
 
+    #[rstest]
+    fn behaviour_log_probs_unknown(history: StoredHistory<BooleanSpace, IndexSpace>) {
+        assert!(history.features().behaviour_log_probs().is_none());
+    }
+
+    /// Set the behaviour log probability of each step to minus its action.
+    #[allow(clippy::cast_precision_loss)]
+    fn set_behaviour_log_probs(history: &mut StoredHistory<BooleanSpace, IndexSpace>) {
+        history.set_behaviour_log_probs(|step| -(step.action as f64));
+    }
+
+    #[rstest]
+    fn behaviour_log_probs(mut history: StoredHistory<BooleanSpace, IndexSpace>) {
+        set_behaviour_log_probs(&mut history);
+        let features = history.features();
+        let actual = features.behaviour_log_probs().unwrap();
+        let expected = -features.actions().tensor().to_kind(Kind::Float);
+        assert_eq!(actual.tensor(), &expected);
+    }
+
+    #[rstest]
+    fn importance_log_ratios(mut history: StoredHistory<BooleanSpace, IndexSpace>) {
+        set_behaviour_log_probs(&mut history);
+        let features = history
+            .features()
+            .with_target_log_probs(|_, actions| actions.batch_map_ref(|a| a.to_kind(Kind::Float)));
+        let actual = features.importance_log_ratios().unwrap();
+        let expected = 2 * features.actions().tensor().to_kind(Kind::Float);
+        assert_eq!(actual.tensor(), &expected);
+    }
+
+    #[rstest]
+    fn importance_log_ratios_no_target(mut history: StoredHistory<BooleanSpace, IndexSpace>) {
+        set_behaviour_log_probs(&mut history);
+        assert!(history.features().importance_log_ratios().is_none());
+    }
+
     #[rstest]
     fn rewards(history: StoredHistory<BooleanSpace, IndexSpace>) {
         let features = history.features();
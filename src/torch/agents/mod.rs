@@ -3,10 +3,14 @@ pub mod critics;
 mod dqn;
 pub mod features;
 pub mod policies;
+mod sac;
 pub mod schedules;
 
 pub use actor_critic::{ActorCriticAgent, ActorCriticConfig};
-pub use dqn::{DqnActor, DqnAgent, DqnConfig};
+pub use dqn::{
+    DqnActor, DqnAgent, DqnConfig, DqnReplayBuffer, PrioritizedReplayConfig, TargetUpdate,
+};
+pub use sac::{EntropyTemperature, SacAgent, SacConfig};
 
 use crate::logging::StatsLogger;
 use crate::torch::modules::{AsModule, Module};
@@ -71,6 +75,22 @@ fn n_backward_steps<O, G, F, L, D>(
     }
 }
 
+/// Move the variables of a target module towards those of a source module by Polyak averaging.
+///
+/// Sets `target = (1 - tau) * target + tau * source` for each variable.
+/// With `tau = 1` the source variables are copied into the target.
+///
+/// The modules must have the same structure.
+fn polyak_update<M: Module + ?Sized>(target: &M, source: &M, tau: f64) {
+    tch::no_grad(|| {
+        for (target_var, source_var) in target.variables().zip(source.variables()) {
+            let mut target_var = target_var.shallow_clone();
+            let updated = &target_var * (1.0 - tau) + source_var * tau;
+            target_var.copy_(&updated);
+        }
+    });
+}
+
 /// Wraps a module to have a lazily-initialized CPU copy if not already in CPU memory.
 ///
 /// This is useful for models used both in training and in simulation because large batch size
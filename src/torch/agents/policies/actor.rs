@@ -1,8 +1,10 @@
-use crate::agents::Actor;
+use crate::agents::{Actor, BatchActor};
 use crate::spaces::{FeatureSpace, NonEmptyFeatures, ParameterizedDistributionSpace};
 use crate::torch::modules::SeqIterative;
+use crate::utils::distributions::ArrayDistribution;
 use crate::Prng;
 use serde::{Deserialize, Serialize};
+use std::iter;
 use tch::Tensor;
 
 /// An [`Actor`] that samples actions according to a policy module.
@@ -53,4 +55,60 @@ where
         self.action_space
             .sample_element(&action_distribution_params)
     }
+
+    fn act_with_log_prob(
+        &self,
+        episode_state: &mut Self::EpisodeState,
+        observation: &OS::Element,
+        _: &mut Prng,
+    ) -> (AS::Element, Option<f64>) {
+        let _no_grad = tch::no_grad_guard();
+        let observation_features: Tensor = self.observation_space.features(observation);
+        let action_distribution_params = self
+            .policy_module
+            .step(episode_state, &observation_features);
+        let action = self
+            .action_space
+            .sample_element(&action_distribution_params);
+        let log_prob = self
+            .action_space
+            .distribution(&action_distribution_params.unsqueeze(0))
+            .log_probs(&self.action_space.batch_repr(iter::once(&action)));
+        (action, Some(f64::from(&log_prob)))
+    }
+}
+
+impl<OS, AS, P> BatchActor<OS::Element, AS::Element> for PolicyActor<OS, AS, P>
+where
+    OS: FeatureSpace,
+    AS: ParameterizedDistributionSpace<Tensor>,
+    P: SeqIterative,
+{
+    fn batch_act(
+        &self,
+        episode_states: &mut [&mut Self::EpisodeState],
+        observations: &[&OS::Element],
+        _: &mut Prng,
+    ) -> Vec<AS::Element> {
+        assert_eq!(
+            episode_states.len(),
+            observations.len(),
+            "mismatched number of episode states and observations"
+        );
+        if observations.is_empty() {
+            return Vec::new();
+        }
+        let _no_grad = tch::no_grad_guard();
+        let observation_features: Tensor = self
+            .observation_space
+            .batch_features(observations.iter().copied());
+        let action_distribution_params = self
+            .policy_module
+            .batch_step(episode_states, &observation_features);
+        action_distribution_params
+            .unbind(0)
+            .iter()
+            .map(|params| self.action_space.sample_element(params))
+            .collect()
+    }
 }
@@ -5,7 +5,7 @@ mod reinforce;
 mod trpo;
 
 pub use actor::PolicyActor;
-pub use ppo::{Ppo, PpoConfig};
+pub use ppo::{MinibatchUnit, Ppo, PpoConfig};
 pub use reinforce::{Reinforce, ReinforceConfig};
 pub use trpo::{Trpo, TrpoConfig};
 
@@ -52,6 +52,18 @@ pub trait Policy: AsModule<Module = Self::PolicyModule> {
     }
 }
 
+/// Log policy entropy statistics.
+///
+/// # Args
+/// * `logger`        - Statistics logger.
+/// * `entropy`       - Mean entropy of the policy action distributions.
+/// * `entropy_coeff` - Coefficient of the entropy bonus added to the policy objective.
+fn log_entropy(logger: &mut dyn StatsLogger, entropy: f64, entropy_coeff: f64) {
+    logger.log_scalar("entropy", entropy);
+    logger.log_scalar("entropy_coeff", entropy_coeff);
+    logger.log_scalar("entropy_bonus", entropy_coeff * entropy);
+}
+
 pub trait BuildPolicy {
     type Policy: Policy;
 
@@ -1,12 +1,15 @@
+use super::super::schedules::ExplorationRateSchedule;
 use super::super::{n_backward_steps, ToLog};
 use super::{
-    BuildPolicy, HistoryFeatures, PackedTensor, ParameterizedDistributionSpace, Policy,
-    SeqIterative, SeqPacked, StatsLogger,
+    log_entropy, BuildPolicy, HistoryFeatures, PackedTensor, ParameterizedDistributionSpace,
+    Policy, SeqIterative, SeqPacked, StatsLogger,
 };
 use crate::torch::modules::{AsModule, BuildModule, Module};
 use crate::torch::optimizers::{AdamConfig, BuildOptimizer, Optimizer};
+use crate::torch::packed::PackedStructure;
 use crate::utils::distributions::ArrayDistribution;
 use serde::{Deserialize, Serialize};
+use std::rc::Rc;
 use tch::{COptimizer, Device, Kind, Tensor};
 
 /// Configuration for [`Ppo`]
@@ -14,14 +17,29 @@ use tch::{COptimizer, Device, Kind, Tensor};
 pub struct PpoConfig<MB, OC = AdamConfig> {
     pub policy_fn_config: MB,
     pub optimizer_config: OC,
-    /// Number of optimization steps per update.
+    /// Number of passes (epochs) through the collected experience per update.
+    ///
+    /// Each pass takes one optimization step per minibatch so with the default full-batch
+    /// minibatches this is the number of optimization steps per update.
     pub opt_steps_per_update: u64,
-    // TODO: Support minibatches
-    // pub minibatch_size: usize,
+    /// Minimum number of steps per minibatch.
+    ///
+    /// Each pass partitions the collected experience into random minibatches and takes one
+    /// optimization step per minibatch. Defaults to `usize::MAX`: optimize on the full batch.
+    pub minibatch_steps: usize,
+    /// Unit of experience that is shuffled into minibatches.
+    ///
+    /// [`MinibatchUnit::Steps`] must only be used with feed-forward policy modules.
+    pub minibatch_unit: MinibatchUnit,
     /// Clip the surrogate objective to `1 ± clip_distance`.
     ///
     /// This is ε (epsilon) in the paper.
     pub clip_distance: f64,
+    /// Coefficient of the policy entropy bonus as a function of the number of training steps.
+    pub entropy_coeff: ExplorationRateSchedule,
+    /// Stop the update early once the approximate KL divergence from the initial policy exceeds
+    /// this value. Checked at the end of each epoch.
+    pub max_approx_kl: Option<f64>,
 }
 
 impl<MB, OC> Default for PpoConfig<MB, OC>
@@ -34,7 +52,11 @@ where
             policy_fn_config: MB::default(),
             optimizer_config: OC::default(),
             opt_steps_per_update: 10,
+            minibatch_steps: usize::MAX,
+            minibatch_unit: MinibatchUnit::default(),
             clip_distance: 0.2,
+            entropy_coeff: ExplorationRateSchedule::Constant(0.0),
+            max_approx_kl: None,
         }
     }
 }
@@ -58,23 +80,113 @@ where
             policy_fn,
             optimizer,
             opt_steps_per_update: self.opt_steps_per_update,
+            minibatch_steps: self.minibatch_steps,
+            minibatch_unit: self.minibatch_unit,
             clip_distance: self.clip_distance,
+            entropy_coeff: self.entropy_coeff,
+            max_approx_kl: self.max_approx_kl,
+            update_steps: 0,
+        }
+    }
+}
+
+/// Unit of experience that is shuffled into [`Ppo`] minibatches.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MinibatchUnit {
+    /// Whole episodes. Required for recurrent policy modules.
+    #[default]
+    Episodes,
+    /// Individual steps. Only valid for policy modules without memory (feed-forward modules).
+    ///
+    /// Each step is evaluated as a separate sequence of length 1 so a recurrent policy module
+    /// would be trained without its memory, unlike how it acts. This is not checked.
+    Steps,
+}
+
+impl MinibatchUnit {
+    /// Randomly partition packed data into minibatches.
+    ///
+    /// # Args
+    /// * `structure` - Structure of the packed data.
+    /// * `min_steps` - Minimum number of steps per minibatch.
+    ///     The last minibatch may be smaller.
+    ///
+    /// # Returns
+    /// For each minibatch, the sequences (for `Episodes`) or the packed steps (for `Steps`) that
+    /// the minibatch contains. Sequences are sorted in increasing order.
+    fn partition(self, structure: &PackedStructure, min_steps: usize) -> Vec<Vec<usize>> {
+        match self {
+            Self::Episodes => {
+                let lengths = structure.sequence_lengths();
+                let mut minibatches = Vec::new();
+                let mut minibatch = Vec::new();
+                let mut minibatch_steps = 0;
+                for i in random_permutation(lengths.len()) {
+                    minibatch.push(i);
+                    minibatch_steps += lengths[i];
+                    if minibatch_steps >= min_steps {
+                        minibatch.sort_unstable();
+                        minibatches.push(minibatch);
+                        minibatch = Vec::new();
+                        minibatch_steps = 0;
+                    }
+                }
+                if !minibatch.is_empty() {
+                    minibatch.sort_unstable();
+                    minibatches.push(minibatch);
+                }
+                minibatches
+            }
+            Self::Steps => random_permutation(structure.len())
+                .chunks(min_steps.max(1))
+                .map(<[_]>::to_vec)
+                .collect(),
         }
     }
 }
 
+/// Random permutation of `0..n` drawn from the torch random number generator.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn random_permutation(n: usize) -> Vec<usize> {
+    let permutation: Vec<i64> =
+        Tensor::randperm(n.try_into().unwrap(), (Kind::Int64, Device::Cpu)).into();
+    permutation.into_iter().map(|i| i as usize).collect()
+}
+
 /// Proximal Policy Optimization (PPO) with a clipped objective.
 ///
 /// # Reference
 /// "[Proximal Policy Optimization Algorithms][ppo]" by Schulman et al.
 ///
 /// [ppo]: https://arxiv.org/abs/1707.06347
-#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Ppo<M, O = COptimizer> {
     policy_fn: M,
     optimizer: O,
     opt_steps_per_update: u64,
+    minibatch_steps: usize,
+    minibatch_unit: MinibatchUnit,
     clip_distance: f64,
+    entropy_coeff: ExplorationRateSchedule,
+    max_approx_kl: Option<f64>,
+    /// Total number of experience steps used in updates so far.
+    update_steps: u64,
+}
+
+impl<M: Default, O: Default> Default for Ppo<M, O> {
+    fn default() -> Self {
+        Self {
+            policy_fn: M::default(),
+            optimizer: O::default(),
+            opt_steps_per_update: 0,
+            minibatch_steps: 0,
+            minibatch_unit: MinibatchUnit::default(),
+            clip_distance: 0.0,
+            entropy_coeff: ExplorationRateSchedule::Constant(0.0),
+            max_approx_kl: None,
+            update_steps: 0,
+        }
+    }
 }
 
 impl<M: Module, O> AsModule for Ppo<M, O> {
@@ -87,6 +199,97 @@ impl<M: Module, O> AsModule for Ppo<M, O> {
     }
 }
 
+/// Packed data for a PPO policy update. All fields have the same packed structure.
+struct PpoBatch {
+    observation_features: PackedTensor,
+    actions: PackedTensor,
+    advantages: PackedTensor,
+    initial_log_probs: PackedTensor,
+}
+
+impl PpoBatch {
+    /// Select a subset of the batch.
+    fn select(&self, unit: MinibatchUnit, indices: &[usize]) -> Self {
+        let select = |packed: &PackedTensor| match unit {
+            MinibatchUnit::Episodes => packed.select_sequences(indices),
+            MinibatchUnit::Steps => {
+                let indices: Vec<i64> = indices.iter().map(|&i| i.try_into().unwrap()).collect();
+                PackedTensor::from_parts(
+                    packed
+                        .tensor()
+                        .index_select(0, &Tensor::of_slice(&indices).to(packed.device())),
+                    PackedStructure::Aligned {
+                        sequence_length: 1,
+                        batch_size: indices.len(),
+                    },
+                )
+            }
+        };
+        Self {
+            observation_features: select(&self.observation_features),
+            actions: select(&self.actions),
+            advantages: select(&self.advantages),
+            initial_log_probs: select(&self.initial_log_probs),
+        }
+    }
+}
+
+/// PPO policy statistics relative to the initial policy.
+struct PpoStats {
+    /// Mean clipped surrogate objective
+    surrogate: Tensor,
+    /// Mean policy entropy
+    entropy: Tensor,
+    /// Approximate KL divergence from the initial policy
+    approx_kl: Tensor,
+    /// Fraction of steps with a clipped likelihood ratio
+    clip_fraction: Tensor,
+}
+
+/// Evaluate PPO statistics of a policy module on a batch.
+fn ppo_stats<M, AS>(
+    policy_fn: &M,
+    clip_distance: f64,
+    batch: &PpoBatch,
+    action_space: &AS,
+) -> PpoStats
+where
+    M: SeqPacked + ?Sized,
+    AS: ParameterizedDistributionSpace<Tensor> + ?Sized,
+{
+    let policy_output = policy_fn.seq_packed(&batch.observation_features);
+    let distribution = action_space.distribution(policy_output.tensor());
+    let log_probs = distribution.log_probs(batch.actions.tensor());
+    let entropy = distribution.entropy().mean(Kind::Float);
+
+    let log_ratio = log_probs - batch.initial_log_probs.tensor();
+    let likelihood_ratio = log_ratio.exp();
+    let clipped_likelihood_ratio = likelihood_ratio.clip(1.0 - clip_distance, 1.0 + clip_distance);
+    let advantages = batch.advantages.tensor();
+
+    // Low-variance unbiased estimator of KL(initial || current)
+    // http://joschu.net/blog/kl-approx.html
+    let approx_kl =
+        tch::no_grad(|| (likelihood_ratio.shallow_clone() - 1 - &log_ratio).mean(Kind::Float));
+    let clip_fraction = tch::no_grad(|| {
+        (likelihood_ratio.shallow_clone() - 1)
+            .abs()
+            .gt(clip_distance)
+            .to_kind(Kind::Float)
+            .mean(Kind::Float)
+    });
+    let surrogate = (likelihood_ratio * advantages)
+        .min_other(&(clipped_likelihood_ratio * advantages))
+        .mean(Kind::Float);
+
+    PpoStats {
+        surrogate,
+        entropy,
+        approx_kl,
+        clip_fraction,
+    }
+}
+
 impl<M, O> Policy for Ppo<M, O>
 where
     M: Module + SeqPacked + SeqIterative,
@@ -102,46 +305,79 @@ where
         logger: &mut dyn StatsLogger,
     ) {
         let observation_features = features.observation_features();
-        let actions = features.actions().tensor();
+        let actions = features.actions();
+        let entropy_coeff = self.entropy_coeff.value(self.update_steps);
+        self.update_steps += observation_features.structure().len() as u64;
 
         let initial_log_probs = {
             let _no_grad = tch::no_grad_guard();
 
             let policy_output = self.policy_fn.seq_packed(observation_features);
             let distribution = action_space.distribution(policy_output.tensor());
-            let log_probs = distribution.log_probs(actions);
+            let log_probs = distribution.log_probs(actions.tensor());
             let entropy = distribution.entropy().mean(Kind::Float);
-            logger.log_scalar("entropy", entropy.into());
+            log_entropy(logger, entropy.into(), entropy_coeff);
 
             log_probs
         };
 
-        // TODO Sample a minibatch on each update.
-        let sample_minibatch = || {};
+        let batch = PpoBatch {
+            observation_features: observation_features.clone(),
+            actions: actions.clone(),
+            advantages,
+            initial_log_probs: PackedTensor::from_parts(
+                initial_log_probs,
+                observation_features.structure().clone(),
+            ),
+        };
 
-        let policy_surrogate_loss_fn = |_| {
-            let policy_output = self.policy_fn.seq_packed(observation_features);
-            let distribution = action_space.distribution(policy_output.tensor());
-            let log_probs = distribution.log_probs(actions);
+        let mut epochs = 0;
+        for _ in 0..self.opt_steps_per_update {
+            let mut minibatches = self
+                .minibatch_unit
+                .partition(observation_features.structure(), self.minibatch_steps)
+                .into_iter();
+            let num_minibatches = minibatches.len() as u64;
 
-            let likelihood_ratio = (log_probs - &initial_log_probs).exp();
-            let clipped_likelihood_ratio =
-                likelihood_ratio.clip(1.0 - self.clip_distance, 1.0 + self.clip_distance);
+            let unit = self.minibatch_unit;
+            let batch = &batch;
+            let sample_minibatch = || Rc::new(batch.select(unit, &minibatches.next().unwrap()));
 
-            (likelihood_ratio * advantages.tensor())
-                .min_other(&(clipped_likelihood_ratio * advantages.tensor()))
-                .mean(Kind::Float)
-                .neg()
-        };
+            let policy_loss_fn = |minibatch: Rc<PpoBatch>| {
+                let stats = ppo_stats(
+                    &self.policy_fn,
+                    self.clip_distance,
+                    &minibatch,
+                    action_space,
+                );
+                (stats.surrogate + stats.entropy * entropy_coeff).neg()
+            };
+
+            n_backward_steps(
+                &mut self.optimizer,
+                sample_minibatch,
+                policy_loss_fn,
+                num_minibatches,
+                &mut *logger,
+                ToLog::NoAbsLoss, // loss value is offset by a meaningless constant
+                "policy update error",
+            );
+            epochs += 1;
 
-        n_backward_steps(
-            &mut self.optimizer,
-            sample_minibatch,
-            policy_surrogate_loss_fn,
-            self.opt_steps_per_update,
-            logger,
-            ToLog::NoAbsLoss, // loss value is offset by a meaningless constant
-            "policy update error",
-        );
+            let stats = tch::no_grad(|| {
+                ppo_stats(&self.policy_fn, self.clip_distance, batch, action_space)
+            });
+            let approx_kl = f64::from(stats.approx_kl);
+            let mut epoch_logger = (&mut *logger).with_scope("epoch");
+            epoch_logger.log_scalar("approx_kl", approx_kl);
+            epoch_logger.log_scalar("clip_fraction", stats.clip_fraction.into());
+            epoch_logger.log_scalar("entropy", stats.entropy.into());
+            epoch_logger.log_scalar("surrogate", stats.surrogate.into());
+
+            if matches!(self.max_approx_kl, Some(max_kl) if approx_kl > max_kl) {
+                break;
+            }
+        }
+        logger.log_scalar("num_epochs", f64::from(epochs));
     }
 }
@@ -1,6 +1,7 @@
+use super::super::schedules::ExplorationRateSchedule;
 use super::{
-    BuildPolicy, HistoryFeatures, PackedTensor, ParameterizedDistributionSpace, Policy,
-    SeqIterative, SeqPacked, StatsLogger,
+    log_entropy, BuildPolicy, HistoryFeatures, PackedTensor, ParameterizedDistributionSpace,
+    Policy, SeqIterative, SeqPacked, StatsLogger,
 };
 use crate::torch::modules::{AsModule, BuildModule, Module};
 use crate::torch::optimizers::{AdamConfig, BuildOptimizer, Optimizer};
@@ -9,10 +10,22 @@ use serde::{Deserialize, Serialize};
 use tch::{COptimizer, Device, Kind, Tensor};
 
 /// Configuration for [`Reinforce`]
-#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ReinforceConfig<MB, OC = AdamConfig> {
     pub policy_fn_config: MB,
     pub optimizer_config: OC,
+    /// Coefficient of the policy entropy bonus as a function of the number of training steps.
+    pub entropy_coeff: ExplorationRateSchedule,
+}
+
+impl<MB: Default, OC: Default> Default for ReinforceConfig<MB, OC> {
+    fn default() -> Self {
+        Self {
+            policy_fn_config: MB::default(),
+            optimizer_config: OC::default(),
+            entropy_coeff: ExplorationRateSchedule::Constant(0.0),
+        }
+    }
 }
 
 impl<MB, OC> BuildPolicy for ReinforceConfig<MB, OC>
@@ -33,15 +46,31 @@ where
         Reinforce {
             policy_fn,
             optimizer,
+            entropy_coeff: self.entropy_coeff,
+            update_steps: 0,
         }
     }
 }
 
 /// REINFORCE policy gradient
-#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Reinforce<M, O = COptimizer> {
     policy_fn: M,
     optimizer: O,
+    entropy_coeff: ExplorationRateSchedule,
+    /// Total number of experience steps used in updates so far.
+    update_steps: u64,
+}
+
+impl<M: Default, O: Default> Default for Reinforce<M, O> {
+    fn default() -> Self {
+        Self {
+            policy_fn: M::default(),
+            optimizer: O::default(),
+            entropy_coeff: ExplorationRateSchedule::Constant(0.0),
+            update_steps: 0,
+        }
+    }
 }
 
 impl<M: Module, O> AsModule for Reinforce<M, O> {
@@ -68,14 +97,18 @@ where
         action_space: &AS,
         logger: &mut dyn StatsLogger,
     ) {
-        let mut entropies = None;
+        let entropy_coeff = self.entropy_coeff.value(self.update_steps);
+        self.update_steps += features.observation_features().structure().len() as u64;
+
+        let mut initial_entropy = None;
         let mut policy_loss_fn = || {
             let action_dist_params = self.policy_fn.seq_packed(features.observation_features());
 
             let action_distributions = action_space.distribution(action_dist_params.tensor());
             let log_probs = action_distributions.log_probs(features.actions().tensor());
-            entropies.get_or_insert_with(|| action_distributions.entropy());
-            -(log_probs * advantages.tensor()).mean(Kind::Float)
+            let entropy = action_distributions.entropy().mean(Kind::Float);
+            initial_entropy.get_or_insert_with(|| f64::from(&entropy));
+            -(log_probs * advantages.tensor()).mean(Kind::Float) - entropy * entropy_coeff
         };
 
         let _ = self
@@ -83,8 +116,8 @@ where
             .backward_step(&mut policy_loss_fn, logger)
             .unwrap();
 
-        if let Some(entropy) = entropies.map(|e| e.mean(Kind::Float).into()) {
-            logger.log_scalar("entropy", entropy);
+        if let Some(entropy) = initial_entropy {
+            log_entropy(logger, entropy, entropy_coeff);
         }
     }
 }
@@ -1,6 +1,7 @@
+use super::super::schedules::ExplorationRateSchedule;
 use super::{
-    BuildPolicy, HistoryFeatures, PackedTensor, ParameterizedDistributionSpace, Policy,
-    SeqIterative, SeqPacked, StatsLogger,
+    log_entropy, BuildPolicy, HistoryFeatures, PackedTensor, ParameterizedDistributionSpace,
+    Policy, SeqIterative, SeqPacked, StatsLogger,
 };
 use crate::torch::backends::WithCudnnEnabled;
 use crate::torch::modules::{AsModule, BuildModule, Module};
@@ -23,6 +24,8 @@ pub struct TrpoConfig<MB, OC = ConjugateGradientOptimizerConfig> {
     /// Specifically, this is the mean KL divergence of the action distributions across all
     /// observed states.
     pub max_policy_step_kl: f64,
+    /// Coefficient of the policy entropy bonus as a function of the number of training steps.
+    pub entropy_coeff: ExplorationRateSchedule,
 }
 
 impl<MB, OC> Default for TrpoConfig<MB, OC>
@@ -36,6 +39,7 @@ where
             optimizer_config: OC::default(),
             // This step size was used by all experiments in Schulman's TRPO paper.
             max_policy_step_kl: 0.01,
+            entropy_coeff: ExplorationRateSchedule::Constant(0.0),
         }
     }
 }
@@ -59,6 +63,8 @@ where
             policy_fn,
             optimizer,
             max_policy_step_kl: self.max_policy_step_kl,
+            entropy_coeff: self.entropy_coeff,
+            update_steps: 0,
         }
     }
 }
@@ -69,12 +75,27 @@ where
 /// "[Trust Region Policy Optimization][trpo]" by Schulman et al.
 ///
 /// [trpo]: https://arxiv.org/abs/1502.05477
-#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Trpo<M, O = ConjugateGradientOptimizer> {
     policy_fn: M,
     optimizer: O,
     /// Maximum policy KL-divergence per update
     max_policy_step_kl: f64,
+    entropy_coeff: ExplorationRateSchedule,
+    /// Total number of experience steps used in updates so far.
+    update_steps: u64,
+}
+
+impl<M: Default, O: Default> Default for Trpo<M, O> {
+    fn default() -> Self {
+        Self {
+            policy_fn: M::default(),
+            optimizer: O::default(),
+            max_policy_step_kl: 0.0,
+            entropy_coeff: ExplorationRateSchedule::Constant(0.0),
+            update_steps: 0,
+        }
+    }
 }
 
 impl<M: Module, O> AsModule for Trpo<M, O> {
@@ -108,6 +129,8 @@ where
         };
         let observation_features = features.observation_features();
         let actions = features.actions().tensor();
+        let entropy_coeff = self.entropy_coeff.value(self.update_steps);
+        self.update_steps += observation_features.structure().len() as u64;
 
         let (initial_distribution, initial_log_probs) = {
             let _no_grad = tch::no_grad_guard();
@@ -116,7 +139,7 @@ where
             let distribution = action_space.distribution(policy_output.tensor());
             let log_probs = distribution.log_probs(actions);
             let entropy = distribution.entropy().mean(Kind::Float);
-            logger.log_scalar("entropy", entropy.into());
+            log_entropy(logger, entropy.into(), entropy_coeff);
 
             (distribution, log_probs)
         };
@@ -127,7 +150,9 @@ where
 
             let log_probs = distribution.log_probs(actions);
             let likelihood_ratio = (log_probs - &initial_log_probs).exp();
-            let loss = -(likelihood_ratio * advantages.tensor()).mean(Kind::Float);
+            let entropy = distribution.entropy().mean(Kind::Float);
+            let loss = -(likelihood_ratio * advantages.tensor()).mean(Kind::Float)
+                - entropy * entropy_coeff;
 
             // NOTE:
             // The [TRPO paper] and [Garage] use `KL(old_policy || new_policy)` while
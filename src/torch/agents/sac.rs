@@ -0,0 +1,593 @@
+//! Soft actor-critic agent
+use super::features::{HistoryFeatures, LazyHistoryFeatures};
+use super::policies::PolicyActor;
+use super::schedules::DataCollectionSchedule;
+use super::{polyak_update, WithCpuCopy};
+use crate::agents::buffers::{HistoryDataBound, ReplayBuffer};
+use crate::agents::{ActorMode, Agent, BatchUpdate, BuildAgent, BuildAgentError};
+use crate::envs::EnvStructure;
+use crate::feedback::Reward;
+use crate::logging::StatsLogger;
+use crate::spaces::{FeatureSpace, NonEmptyFeatures, ParameterizedDistributionSpace, Space};
+use crate::torch::distributions::ReparameterizedDistribution;
+use crate::torch::modules::{AsModule, BuildModule, Module, SeqIterative, SeqPacked};
+use crate::torch::optimizers::{opt_expect_ok_log, AdamConfig, BuildOptimizer, Optimizer};
+use crate::torch::packed::PackedTensor;
+use crate::torch::serialize::{DeviceDef, TensorDef};
+use crate::utils::sequence::Sequence;
+use crate::Prng;
+use rand::distributions::{Distribution, Uniform};
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use std::iter;
+use tch::{Device, Kind, Reduction, Tensor};
+
+/// Configuration for [`SacAgent`]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SacConfig<PB, QB, OB = AdamConfig> {
+    /// Configuration for the policy module. Outputs action distribution parameters.
+    pub policy_fn_config: PB,
+    /// Configuration for each of the twin action value modules.
+    ///
+    /// The module input is the observation features concatenated with the action features.
+    pub action_value_fn_config: QB,
+    /// Configuration for the policy, action value, and entropy temperature optimizers.
+    pub optimizer_config: OB,
+
+    pub entropy_temperature: EntropyTemperature,
+    /// Polyak averaging rate of the target action value modules. In `(0, 1]`.
+    ///
+    /// The target modules are updated after every optimization step.
+    pub target_update_rate: f64,
+    pub minibatch_steps: usize,
+    pub opt_steps_per_update: usize,
+    pub buffer_capacity: usize,
+    pub update_size: DataCollectionSchedule,
+
+    #[serde(with = "DeviceDef")]
+    pub device: Device,
+}
+
+impl<PB, QB, OB> Default for SacConfig<PB, QB, OB>
+where
+    PB: Default,
+    QB: Default,
+    OB: Default,
+{
+    // Based on the values used in the SAC paper:
+    //
+    // target_update_rate: 0.005
+    // minibatch_size: 256 steps
+    // buffer_capacity: 1M
+    //
+    // The paper takes one optimization step per environment step. Here, data is collected in
+    // larger batches to reduce the cost of synchronizing the policy with the actors.
+    fn default() -> Self {
+        Self {
+            policy_fn_config: PB::default(),
+            action_value_fn_config: QB::default(),
+            optimizer_config: OB::default(),
+            entropy_temperature: EntropyTemperature::default(),
+            target_update_rate: 0.005,
+            minibatch_steps: 256,
+            opt_steps_per_update: 1_000,
+            buffer_capacity: 1_000_000,
+            update_size: DataCollectionSchedule::FirstRest {
+                first: 10_000,
+                rest: 1_000,
+            },
+            device: Device::cuda_if_available(),
+        }
+    }
+}
+
+/// Entropy regularization temperature of a [`SacAgent`].
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EntropyTemperature {
+    /// A fixed temperature.
+    Fixed(f64),
+    /// A temperature that is tuned to keep the policy entropy near a target value.
+    Auto {
+        /// Initial temperature.
+        initial: f64,
+        /// Target policy entropy. Defaults to the negative number of action features.
+        target_entropy: Option<f64>,
+    },
+}
+
+impl Default for EntropyTemperature {
+    fn default() -> Self {
+        Self::Auto {
+            initial: 1.0,
+            target_entropy: None,
+        }
+    }
+}
+
+impl<OS, AS, FS, PB, QB, OB> BuildAgent<OS, AS, FS> for SacConfig<PB, QB, OB>
+where
+    OS: FeatureSpace + Clone,
+    OS::Element: 'static,
+    AS: ParameterizedDistributionSpace<Tensor> + FeatureSpace + Clone,
+    AS::Element: 'static,
+    AS::Distribution: ReparameterizedDistribution,
+    FS: Space<Element = Reward>,
+    PB: BuildModule,
+    PB::Module: SeqPacked + SeqIterative,
+    QB: BuildModule,
+    QB::Module: SeqPacked,
+    OB: BuildOptimizer,
+    OB::Optimizer: Optimizer,
+{
+    type Agent = SacAgent<OS, AS, PB::Module, QB::Module, OB::Optimizer>;
+
+    fn build_agent(
+        &self,
+        env: &dyn EnvStructure<ObservationSpace = OS, ActionSpace = AS, FeedbackSpace = FS>,
+        rng: &mut Prng,
+    ) -> Result<Self::Agent, BuildAgentError> {
+        Ok(SacAgent::new(env, self, Prng::from_rng(rng).unwrap()))
+    }
+}
+
+/// Soft Actor-Critic Agent
+///
+/// Based on
+/// "[Soft Actor-Critic: Off-Policy Maximum Entropy Deep Reinforcement Learning with a Stochastic
+/// Actor][sac]"
+/// by Haarnoja et al. (2018)
+/// and
+/// "[Soft Actor-Critic Algorithms and Applications][sacaa]"
+/// by Haarnoja et al. (2018)
+///
+/// Uses twin action value modules with Polyak-averaged target copies and (optionally) automatic
+/// tuning of the entropy temperature.
+/// Recurrent action value modules are evaluated on sequences of observations paired with the
+/// actions taken, or, for bootstrap targets, the actions sampled from the current policy.
+///
+/// [sac]: https://arxiv.org/abs/1801.01290
+/// [sacaa]: https://arxiv.org/abs/1812.05905
+#[serde_as]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SacAgent<OS, AS, P: AsModule, Q, O> {
+    observation_space: NonEmptyFeatures<OS>,
+    action_space: AS,
+
+    policy_fn: WithCpuCopy<P>,
+    policy_optimizer: O,
+
+    /// Twin action value modules.
+    action_value_fns: [Q; 2],
+    /// Polyak-averaged copies of `action_value_fns` used for bootstrap targets.
+    target_action_value_fns: [Q; 2],
+    action_value_optimizer: O,
+
+    /// Log of the entropy temperature. A scalar `f32` tensor.
+    #[serde_as(as = "TensorDef")]
+    log_temperature: Tensor,
+    /// Optimizer for `log_temperature`. `None` if the temperature is fixed.
+    temperature_optimizer: Option<O>,
+    target_entropy: f64,
+
+    target_update_rate: f64,
+    minibatch_steps: usize,
+    opt_steps_per_update: usize,
+    /// Capacity of each individual buffer
+    buffer_capacity: usize,
+    update_size: DataCollectionSchedule,
+    discount_factor: f32,
+
+    /// Total number of collected steps in all updates.
+    global_steps: u64,
+
+    // Tensors will deserialize to CPU
+    #[serde(skip, default = "cpu_device")]
+    device: Device,
+
+    /// Prngs for sampling batches in updates.
+    rng: Prng,
+}
+
+impl<OS, AS, P, Q, O> SacAgent<OS, AS, P, Q, O>
+where
+    OS: FeatureSpace,
+    AS: ParameterizedDistributionSpace<Tensor> + FeatureSpace,
+    P: AsModule,
+    Q: Module,
+{
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn new<E, PB, QB, OB>(env: &E, config: &SacConfig<PB, QB, OB>, rng: Prng) -> Self
+    where
+        E: EnvStructure<ObservationSpace = OS, ActionSpace = AS> + ?Sized,
+        E::FeedbackSpace: Space<Element = Reward>,
+        PB: BuildModule<Module = P>,
+        QB: BuildModule<Module = Q>,
+        OB: BuildOptimizer<Optimizer = O>,
+    {
+        let observation_space = NonEmptyFeatures::new(env.observation_space());
+        let action_space = env.action_space();
+        let num_observation_features = observation_space.num_features();
+        let num_action_features = action_space.num_features();
+
+        let policy_fn = config.policy_fn_config.build_module(
+            num_observation_features,
+            action_space.num_distribution_params(),
+            config.device,
+        );
+        let policy_optimizer = config
+            .optimizer_config
+            .build_optimizer(policy_fn.as_module().trainable_variables())
+            .unwrap();
+
+        let build_action_value_fn = || {
+            config.action_value_fn_config.build_module(
+                num_observation_features + num_action_features,
+                1,
+                config.device,
+            )
+        };
+        let action_value_fns = [build_action_value_fn(), build_action_value_fn()];
+        let target_action_value_fns = [build_action_value_fn(), build_action_value_fn()];
+        for (target, source) in target_action_value_fns.iter().zip(&action_value_fns) {
+            polyak_update(target, source, 1.0);
+        }
+        let action_value_optimizer = config
+            .optimizer_config
+            .build_optimizer(
+                action_value_fns
+                    .iter()
+                    .flat_map(Module::trainable_variables),
+            )
+            .unwrap();
+
+        let (initial_temperature, target_entropy, tune_temperature) =
+            match config.entropy_temperature {
+                EntropyTemperature::Fixed(temperature) => (temperature, 0.0, false),
+                EntropyTemperature::Auto {
+                    initial,
+                    target_entropy,
+                } => (
+                    initial,
+                    target_entropy.unwrap_or(-(num_action_features as f64)),
+                    true,
+                ),
+            };
+        let log_temperature =
+            Tensor::full(&[], initial_temperature.ln(), (Kind::Float, config.device))
+                .set_requires_grad(tune_temperature);
+        let temperature_optimizer = if tune_temperature {
+            Some(
+                config
+                    .optimizer_config
+                    .build_optimizer(iter::once(&log_temperature))
+                    .unwrap(),
+            )
+        } else {
+            None
+        };
+
+        Self {
+            observation_space,
+            action_space,
+            policy_fn: WithCpuCopy::new(policy_fn, config.device),
+            policy_optimizer,
+            action_value_fns,
+            target_action_value_fns,
+            action_value_optimizer,
+            log_temperature,
+            temperature_optimizer,
+            target_entropy,
+            target_update_rate: config.target_update_rate,
+            minibatch_steps: config.minibatch_steps,
+            opt_steps_per_update: config.opt_steps_per_update,
+            buffer_capacity: config.buffer_capacity,
+            update_size: config.update_size,
+            discount_factor: env.discount_factor() as f32,
+            global_steps: 0,
+            device: config.device,
+            rng,
+        }
+    }
+}
+
+const fn cpu_device() -> Device {
+    Device::Cpu
+}
+
+impl<OS, AS, P, Q, O> Agent<OS::Element, AS::Element> for SacAgent<OS, AS, P, Q, O>
+where
+    OS: FeatureSpace + Clone,
+    AS: ParameterizedDistributionSpace<Tensor> + Clone,
+    P: AsModule,
+    P::Module: SeqIterative,
+{
+    type Actor = PolicyActor<OS, AS, P::Module>;
+
+    fn actor(&self, _: ActorMode) -> Self::Actor {
+        PolicyActor::new(
+            self.observation_space.clone(),
+            self.action_space.clone(),
+            self.policy_fn.shallow_clone_module_cpu(),
+        )
+    }
+}
+
+impl<OS, AS, P, Q, O> BatchUpdate<OS::Element, AS::Element> for SacAgent<OS, AS, P, Q, O>
+where
+    OS: FeatureSpace,
+    OS::Element: 'static,
+    AS: ParameterizedDistributionSpace<Tensor>,
+    AS::Element: 'static,
+    AS::Distribution: ReparameterizedDistribution,
+    P: AsModule,
+    P::Module: SeqPacked,
+    Q: Module + SeqPacked,
+    O: Optimizer,
+{
+    type Feedback = Reward;
+    type HistoryBuffer = ReplayBuffer<OS::Element, AS::Element>;
+
+    fn buffer(&self) -> Self::HistoryBuffer {
+        ReplayBuffer::with_capacity(self.buffer_capacity)
+    }
+
+    fn min_update_size(&self) -> HistoryDataBound {
+        self.update_size.update_size(self.global_steps)
+    }
+
+    fn batch_update<'a, I>(&mut self, buffers: I, logger: &mut dyn StatsLogger)
+    where
+        Self: Sized,
+        I: IntoIterator<Item = &'a mut Self::HistoryBuffer>,
+        Self::HistoryBuffer: 'a,
+    {
+        self.batch_update_slice_refs(&mut buffers.into_iter().collect::<Vec<_>>(), logger)
+    }
+}
+
+impl<OS, AS, P, Q, O> SacAgent<OS, AS, P, Q, O>
+where
+    OS: FeatureSpace,
+    OS::Element: 'static,
+    AS: ParameterizedDistributionSpace<Tensor>,
+    AS::Element: 'static,
+    AS::Distribution: ReparameterizedDistribution,
+    P: AsModule,
+    P::Module: SeqPacked,
+    Q: Module + SeqPacked,
+    O: Optimizer,
+{
+    /// Batch update given a slice of buffer references
+    fn batch_update_slice_refs(
+        &mut self,
+        buffers: &mut [&mut ReplayBuffer<OS::Element, AS::Element>],
+        logger: &mut dyn StatsLogger,
+    ) {
+        // Update the global step count.
+        self.global_steps = buffers.iter().map(|b| b.total_step_count()).sum();
+
+        // Mutably borrow the policy fn to invalidate any CPU copy
+        let _ = self.policy_fn.as_module_mut();
+
+        for _ in 0..self.opt_steps_per_update {
+            let sampled_episodes = iter::repeat(&*buffers).flatten().map(|buf| {
+                buf.episodes()
+                    .get(Uniform::new(0usize, buf.num_episodes()).sample(&mut self.rng))
+                    .unwrap()
+            });
+            let mut total_steps = 0;
+            let minibatch_episodes = sampled_episodes.take_while(|ep| {
+                let take = total_steps < self.minibatch_steps;
+                total_steps += ep.len();
+                take
+            });
+            let features = LazyHistoryFeatures::new(
+                minibatch_episodes,
+                &self.observation_space,
+                &self.action_space,
+                self.device,
+            );
+            let temperature = self.log_temperature.exp().detach();
+
+            // Action value (critic) update
+            let targets = tch::no_grad(|| {
+                soft_one_step_values(
+                    self.policy_fn.as_module(),
+                    &self.target_action_value_fns,
+                    &self.action_space,
+                    &temperature,
+                    self.discount_factor,
+                    &features,
+                )
+            });
+            let action_value_inputs =
+                action_value_input(features.observation_features(), features.actions().tensor());
+            let mut action_value_loss_fn = || {
+                let [q1, q2] = &self.action_value_fns;
+                let loss = |q: &Q| {
+                    q.seq_packed(&action_value_inputs)
+                        .tensor()
+                        .squeeze_dim(-1)
+                        .mse_loss(targets.tensor(), Reduction::Mean)
+                };
+                loss(q1) + loss(q2)
+            };
+            let result = self.action_value_optimizer.backward_step(
+                &mut action_value_loss_fn,
+                &mut (&mut *logger).with_scope("critic"),
+            );
+            if let Some(loss) = opt_expect_ok_log(result, "action value update error") {
+                logger.log_scalar("critic_loss", loss.into());
+            }
+
+            // Policy (actor) update
+            let observations = features.observation_features();
+            let mut log_probs = None;
+            let mut policy_loss_fn = || {
+                let action_distributions = self
+                    .action_space
+                    .distribution(self.policy_fn.as_module().seq_packed(observations).tensor());
+                let (actions, action_log_probs) = action_distributions.rsample_with_log_probs();
+                let action_values = min_action_values(
+                    &self.action_value_fns,
+                    &action_value_input(observations, &actions),
+                );
+                let loss = (&temperature * &action_log_probs - action_values).mean(Kind::Float);
+                log_probs = Some(action_log_probs.detach());
+                loss
+            };
+            let result = self.policy_optimizer.backward_step(
+                &mut policy_loss_fn,
+                &mut (&mut *logger).with_scope("policy"),
+            );
+            if let Some(loss) = opt_expect_ok_log(result, "policy update error") {
+                logger.log_scalar("policy_loss", loss.into());
+            }
+            let log_probs = log_probs.expect("policy loss was not evaluated");
+            logger.log_scalar("entropy", (-log_probs.mean(Kind::Float)).into());
+
+            // Entropy temperature update
+            if let Some(temperature_optimizer) = &mut self.temperature_optimizer {
+                let mut temperature_loss_fn = || {
+                    -(&self.log_temperature * (&log_probs + self.target_entropy)).mean(Kind::Float)
+                };
+                let result = temperature_optimizer.backward_step(
+                    &mut temperature_loss_fn,
+                    &mut (&mut *logger).with_scope("temperature"),
+                );
+                opt_expect_ok_log(result, "entropy temperature update error");
+            }
+            logger.log_scalar("temperature", temperature.into());
+
+            for (target, source) in self
+                .target_action_value_fns
+                .iter()
+                .zip(&self.action_value_fns)
+            {
+                polyak_update(target, source, self.target_update_rate);
+            }
+        }
+    }
+}
+
+/// Action value module input: observation features concatenated with flattened action features.
+///
+/// # Args
+/// * `observations` - Packed observation features. A 2D `f32` tensor.
+/// * `actions` - Action representations with the same number of rows as `observations`.
+fn action_value_input(observations: &PackedTensor, actions: &Tensor) -> PackedTensor {
+    let action_features = actions
+        .to_kind(Kind::Float)
+        .reshape(&[actions.size()[0], -1]);
+    observations.batch_map_ref(|obs| Tensor::cat(&[obs, &action_features], -1))
+}
+
+/// Elementwise minimum of the estimates of twin action value modules.
+fn min_action_values<Q: SeqPacked>(action_value_fns: &[Q; 2], inputs: &PackedTensor) -> Tensor {
+    let [q1, q2] = action_value_fns;
+    q1.seq_packed(inputs)
+        .tensor()
+        .minimum(q2.seq_packed(inputs).tensor())
+        .squeeze_dim(-1)
+}
+
+/// One-step soft action value targets: `r_i + γ * (Q(s_{i+1}, a') - α * log π(a' | s_{i+1}))`
+///
+/// where `a' ~ π(s_{i+1})` and `Q` is the minimum of the twin action value modules.
+///
+/// # Args:
+/// * `policy_fn` - Policy module outputting action distribution parameters.
+/// * `action_value_fns` - Twin action value modules.
+/// * `action_space` - Environment action space.
+/// * `temperature` - Entropy temperature `α`. A scalar tensor.
+/// * `discount_factor` - Discount factor on future rewards. In `[0, 1]`.
+/// * `features` - Experience features.
+fn soft_one_step_values<P, Q, AS>(
+    policy_fn: &P,
+    action_value_fns: &[Q; 2],
+    action_space: &AS,
+    temperature: &Tensor,
+    discount_factor: f32,
+    features: &dyn HistoryFeatures,
+) -> PackedTensor
+where
+    P: SeqPacked + ?Sized,
+    Q: Module + SeqPacked,
+    AS: ParameterizedDistributionSpace<Tensor> + ?Sized,
+    AS::Distribution: ReparameterizedDistribution,
+{
+    let (extended_observations, is_invalid) = features.extended_observation_features();
+    let action_distributions =
+        action_space.distribution(policy_fn.seq_packed(extended_observations).tensor());
+    let (actions, log_probs) = action_distributions.rsample_with_log_probs();
+    let extended_values = min_action_values(
+        action_value_fns,
+        &action_value_input(extended_observations, &actions),
+    ) - temperature * log_probs;
+
+    let mut extended_values =
+        PackedTensor::from_parts(extended_values, extended_observations.structure().clone());
+    let _ = extended_values
+        .tensor_mut()
+        .masked_fill_(is_invalid.tensor(), 0.0);
+
+    // Estimated value for each of `step.next.into_inner().observation`
+    let next_values = extended_values.view_trim_start(1);
+    features
+        .rewards()
+        .batch_map_ref(|rewards| rewards + discount_factor * next_values.tensor())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::testing;
+    use crate::envs::testing::ContinuousTargetBandit;
+    use crate::torch::modules::MlpConfig;
+    use crate::torch::optimizers::AdamConfig;
+    use rstest::rstest;
+
+    fn config(
+        entropy_temperature: EntropyTemperature,
+        device: Device,
+    ) -> SacConfig<MlpConfig, MlpConfig> {
+        SacConfig {
+            optimizer_config: AdamConfig {
+                learning_rate: 0.01,
+                ..AdamConfig::default()
+            },
+            entropy_temperature,
+            target_update_rate: 0.1,
+            minibatch_steps: 32,
+            opt_steps_per_update: 20,
+            buffer_capacity: 1000,
+            update_size: DataCollectionSchedule::FirstRest {
+                first: 64,
+                rest: 16,
+            },
+            device,
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    fn learns_continuous_target_bandit(
+        #[values(EntropyTemperature::default(), EntropyTemperature::Fixed(0.01))]
+        entropy_temperature: EntropyTemperature,
+        #[values(Device::Cpu, Device::cuda_if_available())] device: Device,
+    ) {
+        testing::train_continuous_target_bandit(&config(entropy_temperature, device), 30, 0.3);
+    }
+
+    #[test]
+    fn target_action_value_fns_start_as_copies() {
+        let env = ContinuousTargetBandit::new(0.5);
+        let agent = SacAgent::new(
+            &env,
+            &config(EntropyTemperature::default(), Device::Cpu),
+            Prng::seed_from_u64(0),
+        );
+        assert_eq!(agent.target_action_value_fns, agent.action_value_fns);
+    }
+}
@@ -1,9 +1,14 @@
 ///! Parameter schedules --- functions of the global step count during training.
 use crate::agents::{buffers::HistoryDataBound, ActorMode};
 use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
 
 /// Selects the exploration rate as a function the elapsed step count.
-#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+///
+/// Also used for other scalar parameters that are annealed during training.
+///
+/// Schedules are compared and hashed by the bit patterns of their values.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum ExplorationRateSchedule {
     Constant(f64),
     LinearAnnealed {
@@ -30,14 +35,46 @@ impl Default for ExplorationRateSchedule {
     }
 }
 
+impl PartialEq for ExplorationRateSchedule {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits() == other.bits()
+    }
+}
+
+impl Eq for ExplorationRateSchedule {}
+
+impl Hash for ExplorationRateSchedule {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bits().hash(state);
+    }
+}
+
 impl ExplorationRateSchedule {
+    /// The variant index and the bit patterns of the parameters.
+    const fn bits(&self) -> (u8, u64, u64, u64) {
+        match self {
+            Self::Constant(value) => (0, value.to_bits(), 0, 0),
+            Self::LinearAnnealed { start, end, period } => {
+                (1, start.to_bits(), end.to_bits(), *period)
+            }
+        }
+    }
+
     #[must_use]
     pub fn exploration_rate(&self, global_steps: u64, mode: ActorMode) -> f64 {
+        match mode {
+            ActorMode::Evaluation => 0.0,
+            ActorMode::Training => self.value(global_steps),
+        }
+    }
+
+    /// The scheduled value after `global_steps` steps.
+    #[must_use]
+    pub fn value(&self, global_steps: u64) -> f64 {
         use ExplorationRateSchedule::{Constant, LinearAnnealed};
-        match (mode, self) {
-            (ActorMode::Evaluation, _) => 0.0,
-            (ActorMode::Training, Constant(rate)) => *rate,
-            (ActorMode::Training, LinearAnnealed { start, end, period }) => {
+        match self {
+            Constant(value) => *value,
+            LinearAnnealed { start, end, period } => {
                 (global_steps as f64 / *period as f64).min(1.0) * (end - start) + start
             }
         }
@@ -5,13 +5,32 @@
 mod bernoulli;
 mod categorical;
 mod deterministic;
+mod normal;
 
 pub use bernoulli::Bernoulli;
 pub use categorical::Categorical;
 pub use deterministic::DeterministicEmptyVec;
+pub use normal::{Normal, TanhNormal, LOG_STD_MAX, LOG_STD_MIN};
 
+use crate::utils::distributions::ArrayDistribution;
 use tch::{Kind, Tensor};
 
+/// A distribution that supports reparameterized sampling.
+///
+/// Samples are a differentiable function of the distribution parameters so that gradients of
+/// sample-based objectives can be propagated back into the parameters.
+pub trait ReparameterizedDistribution: ArrayDistribution<Tensor, Tensor> {
+    /// Sample a batch of elements along with their log probabilities.
+    ///
+    /// Both the samples and the log probabilities are differentiable with respect to the
+    /// distribution parameters.
+    ///
+    /// # Returns
+    /// * `samples` - An array of shape `[BATCH_SHAPE..., ELEMENT_SHAPE...]`.
+    /// * `log_probs` - An array of shape `[BATCH_SHAPE...]`.
+    fn rsample_with_log_probs(&self) -> (Tensor, Tensor);
+}
+
 /// Clamp float values to be finite
 fn clamp_float_finite(x: &Tensor) -> Result<Tensor, Kind> {
     match x.kind() {
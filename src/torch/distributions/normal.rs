@@ -0,0 +1,566 @@
+//! Normal (Gaussian) distributions
+use super::ReparameterizedDistribution;
+use crate::utils::distributions::ArrayDistribution;
+use std::f64::consts::{LN_2, PI};
+use tch::Tensor;
+
+/// Lower bound applied to log standard deviations parsed by [`Normal::from_params`].
+pub const LOG_STD_MIN: f64 = -20.0;
+/// Upper bound applied to log standard deviations parsed by [`Normal::from_params`].
+pub const LOG_STD_MAX: f64 = 2.0;
+
+/// Multivariate normal distribution(s) with diagonal covariance.
+///
+/// The trailing `event_dims` dimensions of the parameter tensors form a single event (element).
+/// With `event_dims = 0` this is a batch of univariate normal distributions.
+#[derive(Debug, PartialEq)]
+pub struct Normal {
+    /// Distribution means.
+    ///
+    /// An f32 or f64 tensor of shape `[BATCH_SHAPE.., EVENT_SHAPE..]`.
+    mean: Tensor,
+    /// Natural logarithm of the standard deviations. Same shape as `mean`.
+    log_std: Tensor,
+    /// Number of trailing dimensions of `mean` that make up the event shape.
+    event_dims: usize,
+}
+
+impl Normal {
+    /// Initialize from means and log standard deviations.
+    ///
+    /// # Args
+    /// * `mean` - Distribution means. Shape `[BATCH_SHAPE.., EVENT_SHAPE..]`.
+    /// * `log_std` - Log standard deviations. Same shape as `mean`.
+    /// * `event_dims` - Length of `EVENT_SHAPE`.
+    #[must_use]
+    pub fn new(mean: Tensor, log_std: Tensor, event_dims: usize) -> Self {
+        assert_eq!(
+            mean.size(),
+            log_std.size(),
+            "mean and log_std shapes must match"
+        );
+        assert!(
+            mean.dim() >= event_dims,
+            "event_dims ({}) exceeds the number of mean dimensions ({})",
+            event_dims,
+            mean.dim()
+        );
+        Self {
+            mean,
+            log_std,
+            event_dims,
+        }
+    }
+
+    /// Initialize from a parameter tensor with means and log standard deviations concatenated.
+    ///
+    /// Log standard deviations are clamped to `[LOG_STD_MIN, LOG_STD_MAX]`.
+    ///
+    /// # Args
+    /// * `params` - A tensor of shape `[BATCH_SHAPE.., 2 * EVENT_SIZE]` where `EVENT_SIZE` is the
+    ///              product of `event_shape`. The first half of the final dimension contains the
+    ///              (flattened) means and the second half contains the log standard deviations.
+    /// * `event_shape` - Shape of a single event.
+    #[must_use]
+    pub fn from_params(params: &Tensor, event_shape: &[i64]) -> Self {
+        let event_size: i64 = event_shape.iter().product();
+        let (last_dim, batch_shape) = params.size().split_last().map_or_else(
+            || panic!("params must have at least one dimension"),
+            |(&last, batch)| (last, batch.to_vec()),
+        );
+        assert_eq!(
+            last_dim,
+            2 * event_size,
+            "expected {} distribution parameters",
+            2 * event_size
+        );
+        let shape: Vec<i64> = batch_shape
+            .into_iter()
+            .chain(event_shape.iter().copied())
+            .collect();
+        let mean = params.narrow(-1, 0, event_size).reshape(&shape);
+        let log_std = params
+            .narrow(-1, event_size, event_size)
+            .clamp(LOG_STD_MIN, LOG_STD_MAX)
+            .reshape(&shape);
+        Self::new(mean, log_std, event_shape.len())
+    }
+
+    /// Distribution means.
+    pub const fn mean(&self) -> &Tensor {
+        &self.mean
+    }
+
+    /// Log standard deviations.
+    pub const fn log_std(&self) -> &Tensor {
+        &self.log_std
+    }
+
+    /// Number of trailing dimensions that form the event shape.
+    #[must_use]
+    pub const fn event_dims(&self) -> usize {
+        self.event_dims
+    }
+
+    /// Sample a batch of elements using the reparameterization trick.
+    ///
+    /// The samples are differentiable with respect to the distribution parameters.
+    pub fn rsample(&self) -> Tensor {
+        &self.mean + self.log_std.exp() * self.mean.randn_like()
+    }
+
+    /// Sum over the event dimensions.
+    fn sum_events(&self, x: Tensor) -> Tensor {
+        sum_trailing_dims(x, self.event_dims)
+    }
+
+    /// Log probability densities of each scalar component (not summed over the event).
+    fn component_log_probs(&self, elements: &Tensor) -> Tensor {
+        let z = (elements.to_kind(self.mean.kind()) - &self.mean) * (-&self.log_std).exp();
+        z.square() * -0.5 - &self.log_std - 0.5 * (2.0 * PI).ln()
+    }
+}
+
+/// Sum a tensor over its last `n` dimensions.
+fn sum_trailing_dims(x: Tensor, n: usize) -> Tensor {
+    if n == 0 {
+        // An empty dimension list would reduce over all dimensions.
+        return x;
+    }
+    let dims: Vec<i64> = (1..=i64::try_from(n).unwrap()).map(|i| -i).collect();
+    let kind = x.kind();
+    x.sum_dim_intlist(&dims, false, kind)
+}
+
+impl ArrayDistribution<Tensor, Tensor> for Normal {
+    fn batch_shape(&self) -> Vec<usize> {
+        let shape = self.mean.size();
+        shape[..shape.len() - self.event_dims]
+            .iter()
+            .map(|&s| s.try_into().unwrap())
+            .collect()
+    }
+
+    fn element_shape(&self) -> Vec<usize> {
+        let shape = self.mean.size();
+        shape[shape.len() - self.event_dims..]
+            .iter()
+            .map(|&s| s.try_into().unwrap())
+            .collect()
+    }
+
+    fn sample(&self) -> Tensor {
+        self.rsample().detach()
+    }
+
+    fn log_probs(&self, elements: &Tensor) -> Tensor {
+        self.sum_events(self.component_log_probs(elements))
+    }
+
+    fn entropy(&self) -> Tensor {
+        self.sum_events(&self.log_std + 0.5 * (1.0 + (2.0 * PI).ln()))
+    }
+
+    fn kl_divergence_from(&self, other: &Self) -> Tensor {
+        assert_eq!(self.event_dims, other.event_dims, "mismatched event dims");
+        let var_ratio = ((&self.log_std - &other.log_std) * 2.0).exp();
+        let mean_term = ((&self.mean - &other.mean) * (-&other.log_std).exp()).square();
+        self.sum_events((var_ratio + mean_term - 1.0) * 0.5 - &self.log_std + &other.log_std)
+    }
+}
+
+/// Normal distribution(s) squashed by `tanh` and affinely scaled to the box `[low, high]`.
+///
+/// Elements are `(high + low) / 2 + (high - low) / 2 * tanh(x)` where `x` is drawn from a
+/// [`Normal`] base distribution.
+/// The bounds must be finite.
+#[derive(Debug, PartialEq)]
+pub struct TanhNormal {
+    /// Base normal distribution.
+    base: Normal,
+    /// Lower bounds. Broadcastable to the event shape.
+    low: Tensor,
+    /// Upper bounds. Broadcastable to the event shape.
+    high: Tensor,
+    /// Center of the bounds: `(high + low) / 2`.
+    loc: Tensor,
+    /// Half-width of the bounds: `(high - low) / 2`.
+    scale: Tensor,
+}
+
+impl TanhNormal {
+    /// Initialize from a base distribution and output bounds.
+    ///
+    /// # Args
+    /// * `base` - Base normal distribution of the pre-`tanh` values.
+    /// * `low` - Lower bounds. Broadcastable to the event shape of `base`.
+    /// * `high` - Upper bounds. Broadcastable to the event shape of `base`.
+    #[must_use]
+    pub fn new(base: Normal, low: &Tensor, high: &Tensor) -> Self {
+        let options = (base.mean.kind(), base.mean.device());
+        let low = low.to_kind(options.0).to_device(options.1);
+        let high = high.to_kind(options.0).to_device(options.1);
+        let loc = (&high + &low) * 0.5;
+        let scale = (&high - &low) * 0.5;
+        Self {
+            base,
+            low,
+            high,
+            loc,
+            scale,
+        }
+    }
+
+    /// The base normal distribution.
+    #[must_use]
+    pub const fn base(&self) -> &Normal {
+        &self.base
+    }
+
+    /// The mode of the base distribution mapped into the bounds. A deterministic action choice.
+    pub fn mode(&self) -> Tensor {
+        self.squash(&self.base.mean)
+    }
+
+    /// Sample a batch of elements using the reparameterization trick.
+    pub fn rsample(&self) -> Tensor {
+        self.squash(&self.base.rsample())
+    }
+
+    /// Map pre-squash values into the bounds.
+    fn squash(&self, x: &Tensor) -> Tensor {
+        (&self.loc + &self.scale * x.tanh()).clamp_tensor(Some(&self.low), Some(&self.high))
+    }
+
+    /// Log absolute derivative of the squashing function for each component.
+    fn component_log_abs_det_jacobian(&self, x: &Tensor) -> Tensor {
+        // log(1 - tanh(x)^2) = 2 * (log(2) - x - softplus(-2x)) is numerically stable
+        ((-x - (x * -2.0).softplus() + LN_2) * 2.0) + self.scale.log()
+    }
+}
+
+impl ArrayDistribution<Tensor, Tensor> for TanhNormal {
+    fn batch_shape(&self) -> Vec<usize> {
+        self.base.batch_shape()
+    }
+
+    fn element_shape(&self) -> Vec<usize> {
+        self.base.element_shape()
+    }
+
+    fn sample(&self) -> Tensor {
+        self.rsample().detach()
+    }
+
+    fn log_probs(&self, elements: &Tensor) -> Tensor {
+        // Keep strictly within (-1, 1) so that atanh is finite at the bounds.
+        let bound = 1.0 - 1e-6;
+        let unit =
+            ((elements.to_kind(self.loc.kind()) - &self.loc) / &self.scale).clamp(-bound, bound);
+        let x = unit.atanh();
+        self.base
+            .sum_events(self.base.component_log_probs(&x) - self.component_log_abs_det_jacobian(&x))
+    }
+
+    /// Single-sample estimate of the entropy.
+    ///
+    /// The entropy does not have a closed form. It is `H(base) + E[log |det J|]` where the
+    /// expectation is estimated with one reparameterized sample (so it can be differentiated).
+    fn entropy(&self) -> Tensor {
+        let x = self.base.rsample();
+        self.base.entropy()
+            + self
+                .base
+                .sum_events(self.component_log_abs_det_jacobian(&x))
+    }
+
+    /// KL divergence is invariant to the (shared) squashing transformation.
+    ///
+    /// Assumes that both distributions have the same bounds.
+    fn kl_divergence_from(&self, other: &Self) -> Tensor {
+        self.base.kl_divergence_from(&other.base)
+    }
+}
+
+impl ReparameterizedDistribution for Normal {
+    fn rsample_with_log_probs(&self) -> (Tensor, Tensor) {
+        let samples = self.rsample();
+        let log_probs = self.log_probs(&samples);
+        (samples, log_probs)
+    }
+}
+
+impl ReparameterizedDistribution for TanhNormal {
+    /// More accurate than calling `log_probs` on the samples because it avoids inverting `tanh`
+    /// near the boundaries.
+    fn rsample_with_log_probs(&self) -> (Tensor, Tensor) {
+        let x = self.base.rsample();
+        let log_probs = self.base.sum_events(
+            self.base.component_log_probs(&x) - self.component_log_abs_det_jacobian(&x),
+        );
+        (self.squash(&x), log_probs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tch::{IndexOp, Kind};
+
+    fn normal_log_prob(x: f64, mean: f64, std: f64) -> f64 {
+        let z = (x - mean) / std;
+        -0.5 * z * z - std.ln() - 0.5 * (2.0 * PI).ln()
+    }
+
+    fn assert_allclose(actual: &Tensor, expected: &[f64]) {
+        let expected = Tensor::of_slice(expected).to_kind(actual.kind());
+        assert!(
+            actual.allclose(&expected, 1e-4, 1e-5, false),
+            "\nactual: {actual:?}\nexpected: {expected:?}\n"
+        );
+    }
+
+    mod normal {
+        use super::*;
+
+        fn normal_3() -> Normal {
+            Normal::new(
+                Tensor::of_slice(&[0.0_f32, 1.0, -2.0]),
+                Tensor::of_slice(&[0.0_f32, 0.5, -1.0]),
+                0,
+            )
+        }
+
+        #[test]
+        fn batch_shape_0d() {
+            let d = Normal::new(Tensor::from(0.0_f32), Tensor::from(0.0_f32), 0);
+            assert_eq!(d.batch_shape(), [] as [usize; 0]);
+        }
+
+        #[test]
+        fn batch_shape_1d() {
+            assert_eq!(normal_3().batch_shape(), [3]);
+        }
+
+        #[test]
+        fn element_shape_scalar() {
+            assert_eq!(normal_3().element_shape(), [] as [usize; 0]);
+        }
+
+        #[test]
+        fn batch_and_element_shape_multivariate() {
+            let d = Normal::new(
+                Tensor::zeros(&[4, 2, 3], (Kind::Float, tch::Device::Cpu)),
+                Tensor::zeros(&[4, 2, 3], (Kind::Float, tch::Device::Cpu)),
+                2,
+            );
+            assert_eq!(d.batch_shape(), [4]);
+            assert_eq!(d.element_shape(), [2, 3]);
+        }
+
+        #[test]
+        fn from_params() {
+            let params = Tensor::of_slice(&[
+                0.0_f32, 1.0, 2.0, -1.0, -2.0, 100.0, //
+                3.0, 4.0, 5.0, -3.0, -4.0, -100.0, //
+            ])
+            .reshape(&[2, 6]);
+            let d = Normal::from_params(&params, &[3]);
+            assert_eq!(d.batch_shape(), [2]);
+            assert_eq!(d.element_shape(), [3]);
+            assert_allclose(&d.mean().reshape(&[-1]), &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+            assert_allclose(
+                &d.log_std().reshape(&[-1]),
+                &[-1.0, -2.0, LOG_STD_MAX, -3.0, -4.0, LOG_STD_MIN],
+            );
+        }
+
+        #[test]
+        fn sample() {
+            let d = Normal::new(
+                Tensor::of_slice(&[0.0_f32, 10.0, -10.0]),
+                Tensor::of_slice(&[-20.0_f32, -20.0, 0.0]),
+                0,
+            );
+            let samples = d.sample();
+            assert_eq!(samples.size(), [3]);
+            assert_allclose(&samples.i(..2), &[0.0, 10.0]);
+            let last = f64::from(samples.i(2));
+            assert!((-20.0..0.0).contains(&last));
+        }
+
+        #[test]
+        fn log_probs() {
+            let d = normal_3();
+            let actual = d.log_probs(&Tensor::of_slice(&[0.0_f32, 2.0, -2.5]));
+            assert_allclose(
+                &actual,
+                &[
+                    normal_log_prob(0.0, 0.0, 1.0),
+                    normal_log_prob(2.0, 1.0, 0.5_f64.exp()),
+                    normal_log_prob(-2.5, -2.0, (-1.0_f64).exp()),
+                ],
+            );
+        }
+
+        #[test]
+        fn log_probs_multivariate() {
+            let d = Normal::new(
+                Tensor::of_slice(&[0.0_f32, 1.0, -2.0]).reshape(&[1, 3]),
+                Tensor::of_slice(&[0.0_f32, 0.5, -1.0]).reshape(&[1, 3]),
+                1,
+            );
+            let actual = d.log_probs(&Tensor::of_slice(&[0.0_f32, 2.0, -2.5]).reshape(&[1, 3]));
+            assert_allclose(
+                &actual,
+                &[normal_log_prob(0.0, 0.0, 1.0)
+                    + normal_log_prob(2.0, 1.0, 0.5_f64.exp())
+                    + normal_log_prob(-2.5, -2.0, (-1.0_f64).exp())],
+            );
+        }
+
+        #[test]
+        fn entropies() {
+            let d = normal_3();
+            let h = |log_std: f64| 0.5 * (2.0 * PI * 1.0_f64.exp()).ln() + log_std;
+            assert_allclose(&d.entropy(), &[h(0.0), h(0.5), h(-1.0)]);
+        }
+
+        #[test]
+        fn kl_divergence() {
+            let p = normal_3();
+            let q = Normal::new(
+                Tensor::of_slice(&[0.0_f32, 0.0, 0.0]),
+                Tensor::of_slice(&[0.0_f32, 0.0, 1.0]),
+                0,
+            );
+            let kl = |mp: f64, sp: f64, mq: f64, sq: f64| {
+                (sq / sp).ln() + (sp * sp + (mp - mq).powi(2)) / (2.0 * sq * sq) - 0.5
+            };
+            assert_allclose(
+                &p.kl_divergence_from(&q),
+                &[
+                    0.0,
+                    kl(1.0, 0.5_f64.exp(), 0.0, 1.0),
+                    kl(-2.0, (-1.0_f64).exp(), 0.0, 1.0_f64.exp()),
+                ],
+            );
+        }
+
+        #[test]
+        fn kl_divergence_self_zero() {
+            let p = normal_3();
+            assert_allclose(&p.kl_divergence_from(&normal_3()), &[0.0, 0.0, 0.0]);
+        }
+    }
+
+    mod tanh_normal {
+        use super::*;
+
+        fn tanh_normal_3() -> TanhNormal {
+            TanhNormal::new(
+                Normal::new(
+                    Tensor::of_slice(&[0.0_f32, 1.0, -2.0]),
+                    Tensor::of_slice(&[0.0_f32, 0.5, -1.0]),
+                    0,
+                ),
+                &Tensor::from(-1.0),
+                &Tensor::from(3.0),
+            )
+        }
+
+        #[test]
+        fn shapes() {
+            let d = tanh_normal_3();
+            assert_eq!(d.batch_shape(), [3]);
+            assert_eq!(d.element_shape(), [] as [usize; 0]);
+        }
+
+        #[test]
+        fn samples_in_bounds() {
+            let d = tanh_normal_3();
+            for _ in 0..10 {
+                let samples = d.sample();
+                assert_eq!(samples.size(), [3]);
+                assert!(bool::from(samples.ge(-1.0).all()));
+                assert!(bool::from(samples.le(3.0).all()));
+            }
+        }
+
+        #[test]
+        fn mode() {
+            let d = tanh_normal_3();
+            assert_allclose(
+                &d.mode(),
+                &[
+                    1.0,
+                    1.0 + 2.0 * 1.0_f64.tanh(),
+                    1.0 + 2.0 * (-2.0_f64).tanh(),
+                ],
+            );
+        }
+
+        #[test]
+        #[allow(clippy::cast_possible_truncation)]
+        fn log_probs() {
+            let d = tanh_normal_3();
+            // Pre-squash values
+            let xs = [0.0_f64, 0.3, -1.5];
+            let ys: Vec<f32> = xs.iter().map(|x| (1.0 + 2.0 * x.tanh()) as f32).collect();
+            let actual = d.log_probs(&Tensor::of_slice(&ys));
+            let lp = |x: f64, mean: f64, std: f64| {
+                normal_log_prob(x, mean, std) - (2.0 * (1.0 - x.tanh().powi(2))).ln()
+            };
+            assert_allclose(
+                &actual,
+                &[
+                    lp(xs[0], 0.0, 1.0),
+                    lp(xs[1], 1.0, 0.5_f64.exp()),
+                    lp(xs[2], -2.0, (-1.0_f64).exp()),
+                ],
+            );
+        }
+
+        #[test]
+        fn log_probs_at_bounds_finite() {
+            let d = tanh_normal_3();
+            let log_probs = d.log_probs(&Tensor::of_slice(&[-1.0_f32, 3.0, 3.0]));
+            assert!(bool::from(log_probs.isfinite().all()));
+        }
+
+        #[test]
+        fn rsample_with_log_probs_matches_log_probs() {
+            // Small standard deviation so that samples are not close to the bounds,
+            // where inverting tanh is inaccurate.
+            let d = TanhNormal::new(
+                Normal::new(
+                    Tensor::of_slice(&[0.0_f32, 0.5, -0.5]),
+                    Tensor::of_slice(&[-1.0_f32, -1.0, -1.0]),
+                    0,
+                ),
+                &Tensor::from(-1.0),
+                &Tensor::from(3.0),
+            );
+            let (samples, log_probs) = d.rsample_with_log_probs();
+            assert!(log_probs.allclose(&d.log_probs(&samples), 1e-3, 1e-3, false));
+        }
+
+        #[test]
+        fn kl_divergence_matches_base() {
+            let p = tanh_normal_3();
+            let q = TanhNormal::new(
+                Normal::new(
+                    Tensor::of_slice(&[0.5_f32, 0.0, 0.0]),
+                    Tensor::of_slice(&[0.0_f32, 0.0, 1.0]),
+                    0,
+                ),
+                &Tensor::from(-1.0),
+                &Tensor::from(3.0),
+            );
+            assert_eq!(
+                p.kl_divergence_from(&q),
+                p.base().kl_divergence_from(q.base())
+            );
+        }
+    }
+}
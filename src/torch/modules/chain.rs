@@ -183,6 +183,16 @@ where
         let hidden = self.activation.forward_owned(hidden);
         self.second.step(&mut state.1, &hidden)
     }
+
+    fn batch_step(&self, states: &mut [&mut Self::State], inputs: &Tensor) -> Tensor {
+        let hidden = {
+            let mut first_states: Vec<_> = states.iter_mut().map(|s| &mut s.0).collect();
+            self.first.batch_step(&mut first_states, inputs)
+        };
+        let hidden = self.activation.forward_owned(hidden);
+        let mut second_states: Vec<_> = states.iter_mut().map(|s| &mut s.1).collect();
+        self.second.batch_step(&mut second_states, &hidden)
+    }
 }
 
 impl<M: Module> Module for [M] {
@@ -295,13 +305,23 @@ impl<M: SeqIterative> SeqIterative for [M] {
     }
 
     fn step(&self, state: &mut Self::State, input: &Tensor) -> Tensor {
-        assert_eq!(self.len(), state.len(), "mismatched state lenght");
+        assert_eq!(self.len(), state.len(), "mismatched state length");
         fold_or_clone(
             self.iter().zip(state.iter_mut()),
             input,
             |tensor, (module, module_state)| module.step(module_state, tensor),
         )
     }
+
+    fn batch_step(&self, states: &mut [&mut Self::State], inputs: &Tensor) -> Tensor {
+        for state in states.iter() {
+            assert_eq!(self.len(), state.len(), "mismatched state length");
+        }
+        fold_or_clone(self.iter().enumerate(), inputs, |tensor, (i, module)| {
+            let mut layer_states: Vec<_> = states.iter_mut().map(|s| &mut s[i]).collect();
+            module.batch_step(&mut layer_states, tensor)
+        })
+    }
 }
 
 impl<M: SeqIterative, const N: usize> SeqIterative for [M; N] {
@@ -318,6 +338,17 @@ impl<M: SeqIterative, const N: usize> SeqIterative for [M; N] {
             |tensor, (module, module_state)| module.step(module_state, tensor),
         )
     }
+
+    fn batch_step(&self, states: &mut [&mut Self::State], inputs: &Tensor) -> Tensor {
+        fold_or_clone(
+            <[M]>::iter(self).enumerate(),
+            inputs,
+            |tensor, (i, module)| {
+                let mut layer_states: Vec<_> = states.iter_mut().map(|s| &mut s[i]).collect();
+                module.batch_step(&mut layer_states, tensor)
+            },
+        )
+    }
 }
 
 /// Either fold an iterator over an input or clone the input Tensor if the iterator is empty
@@ -432,6 +463,12 @@ mod tests {
         testing::check_step(&gru_mlp, in_dim, out_dim);
     }
 
+    #[rstest]
+    fn gru_mlp_batch_step_matches_step(gru_mlp: (Chain<Gru, Mlp>, usize, usize)) {
+        let (gru_mlp, in_dim, out_dim) = gru_mlp;
+        testing::check_batch_step_matches_step(&gru_mlp, in_dim, out_dim);
+    }
+
     #[rstest]
     fn gru_mlp_seq_packed_matches_iter_steps(gru_mlp: (Chain<Gru, Mlp>, usize, usize)) {
         let (gru_mlp, in_dim, out_dim) = gru_mlp;
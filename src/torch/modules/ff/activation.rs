@@ -119,6 +119,11 @@ impl SeqIterative for Activation {
     fn step(&self, _: &mut Self::State, input: &Tensor) -> Tensor {
         self.forward(input)
     }
+
+    #[inline]
+    fn batch_step(&self, _: &mut [&mut Self::State], inputs: &Tensor) -> Tensor {
+        self.forward(inputs)
+    }
 }
 
 #[cfg(test)]
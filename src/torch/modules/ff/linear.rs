@@ -149,6 +149,11 @@ impl SeqIterative for Linear {
     fn step(&self, _: &mut Self::State, input: &Tensor) -> Tensor {
         self.forward(input)
     }
+
+    #[inline]
+    fn batch_step(&self, _: &mut [&mut Self::State], inputs: &Tensor) -> Tensor {
+        self.forward(inputs)
+    }
 }
 
 #[cfg(test)]
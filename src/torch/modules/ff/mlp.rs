@@ -172,6 +172,9 @@ impl SeqIterative for Mlp {
     fn step(&self, _: &mut Self::State, input: &Tensor) -> Tensor {
         self.forward(input)
     }
+    fn batch_step(&self, _: &mut [&mut Self::State], inputs: &Tensor) -> Tensor {
+        self.forward(inputs)
+    }
 }
 
 #[cfg(test)]
@@ -222,6 +225,12 @@ mod tests {
         testing::check_step(&module, in_dim, out_dim);
     }
 
+    #[rstest]
+    fn batch_step_matches_step(default_module: (Mlp, usize, usize)) {
+        let (module, in_dim, out_dim) = default_module;
+        testing::check_batch_step_matches_step(&module, in_dim, out_dim);
+    }
+
     #[rstest]
     fn seq_consistent(default_module: (Mlp, usize, usize)) {
         let (module, in_dim, out_dim) = default_module;
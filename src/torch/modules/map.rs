@@ -70,4 +70,7 @@ where
     fn step(&self, state: &mut Self::State, input: &Tensor) -> Tensor {
         (self.f)(self.inner.step(state, input))
     }
+    fn batch_step(&self, states: &mut [&mut Self::State], inputs: &Tensor) -> Tensor {
+        (self.f)(self.inner.batch_step(states, inputs))
+    }
 }
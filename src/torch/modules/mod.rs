@@ -251,6 +251,28 @@ pub trait SeqIterative {
     /// * `state` - A new value for the hidden state.
     fn step(&self, state: &mut Self::State, input: &Tensor) -> Tensor;
 
+    /// Transform the next value for each of a batch of independent sequences.
+    ///
+    /// The default implementation calls [`SeqIterative::step`] on each batch element separately.
+    ///
+    /// # Args
+    /// * `states` - The state of each sequence in the batch.
+    /// * `inputs` - The input for one step of each sequence.
+    ///     A tensor with shape `[BATCH_SIZE, NUM_INPUT_FEATURES]`
+    ///     where `BATCH_SIZE == states.len()`.
+    ///
+    /// # Returns
+    /// * `output` - The output tensor. Has shape `[BATCH_SIZE, NUM_OUT_FEATURES]`
+    /// * `states` - The states are updated in-place.
+    fn batch_step(&self, states: &mut [&mut Self::State], inputs: &Tensor) -> Tensor {
+        let outputs: Vec<_> = states
+            .iter_mut()
+            .zip(inputs.unbind(0))
+            .map(|(state, input)| self.step(state, &input))
+            .collect();
+        Tensor::stack(&outputs, 0)
+    }
+
     /// Iterate over input tensors
     fn iter<I>(&self, inputs: I) -> SeqIterator<&Self, I::IntoIter>
     where
@@ -282,6 +304,9 @@ macro_rules! impl_wrapped_iterative_module {
             fn step(&self, state: &mut Self::State, input: &Tensor) -> Tensor {
                 T::step(self, state, input)
             }
+            fn batch_step(&self, states: &mut [&mut Self::State], inputs: &Tensor) -> Tensor {
+                T::batch_step(self, states, inputs)
+            }
         }
     };
 }
@@ -36,6 +36,14 @@ impl RnnImpl for GruImpl {
         *state = batch_input.gru_cell(state, w.w_ih(), w.w_hh(), w.b_ih(), w.b_hh());
         state.shallow_clone()
     }
+
+    fn cat_cell_states(states: &[&Self::CellState]) -> Self::CellState {
+        Tensor::cat(states, 0)
+    }
+
+    fn split_cell_state(state: &Self::CellState) -> Vec<Self::CellState> {
+        state.split(1, 0)
+    }
 }
 
 impl SeqSerial for Gru {
@@ -143,6 +151,24 @@ mod tests {
         testing::check_step(&gru, in_dim, out_dim);
     }
 
+    #[rstest]
+    fn batch_step_matches_step(gru: (Gru, usize, usize)) {
+        let (gru, in_dim, out_dim) = gru;
+        testing::check_batch_step_matches_step(&gru, in_dim, out_dim);
+    }
+
+    #[test]
+    fn batch_step_matches_step_2layers() {
+        let in_dim: usize = 3;
+        let out_dim: usize = 2;
+        let config = GruConfig {
+            num_layers: 2,
+            ..GruConfig::default()
+        };
+        let gru = Gru::new(in_dim, out_dim, Device::Cpu, &config);
+        testing::check_batch_step_matches_step(&gru, in_dim, out_dim);
+    }
+
     #[rstest]
     fn seq_packed_matches_iter_steps(gru: (Gru, usize, usize)) {
         let (gru, in_dim, out_dim) = gru;
@@ -47,6 +47,21 @@ impl RnnImpl for LstmImpl {
         *state = (new_hidden_state, new_cell_state);
         state.0.shallow_clone()
     }
+
+    fn cat_cell_states(states: &[&Self::CellState]) -> Self::CellState {
+        let hidden_states: Vec<_> = states.iter().map(|(h, _)| h).collect();
+        let cell_states: Vec<_> = states.iter().map(|(_, c)| c).collect();
+        (Tensor::cat(&hidden_states, 0), Tensor::cat(&cell_states, 0))
+    }
+
+    fn split_cell_state(state: &Self::CellState) -> Vec<Self::CellState> {
+        let (hidden_state, cell_state) = state;
+        hidden_state
+            .split(1, 0)
+            .into_iter()
+            .zip(cell_state.split(1, 0))
+            .collect()
+    }
 }
 
 impl SeqSerial for Lstm {
@@ -157,6 +172,24 @@ mod tests {
         testing::check_step(&lstm, in_dim, out_dim);
     }
 
+    #[rstest]
+    fn batch_step_matches_step(lstm: (Lstm, usize, usize)) {
+        let (lstm, in_dim, out_dim) = lstm;
+        testing::check_batch_step_matches_step(&lstm, in_dim, out_dim);
+    }
+
+    #[test]
+    fn batch_step_matches_step_2layers() {
+        let in_dim: usize = 3;
+        let out_dim: usize = 2;
+        let config = LstmConfig {
+            num_layers: 2,
+            ..LstmConfig::default()
+        };
+        let lstm = Lstm::new(in_dim, out_dim, Device::Cpu, &config);
+        testing::check_batch_step_matches_step(&lstm, in_dim, out_dim);
+    }
+
     #[rstest]
     fn seq_packed_matches_iter_steps(lstm: (Lstm, usize, usize)) {
         let (lstm, in_dim, out_dim) = lstm;
@@ -81,6 +81,12 @@ pub trait RnnImpl {
     ) -> Tensor
     where
         Self: Sized;
+
+    /// Concatenate cell states along the batch dimension.
+    fn cat_cell_states(states: &[&Self::CellState]) -> Self::CellState;
+
+    /// Split a cell state along the batch dimension into states of batch size 1.
+    fn split_cell_state(state: &Self::CellState) -> Vec<Self::CellState>;
 }
 
 const fn cpu_device() -> Device {
@@ -181,6 +187,19 @@ impl<T: RnnImpl> SeqIterative for RnnBase<T> {
         }
         hidden.squeeze_dim(0)
     }
+
+    fn batch_step(&self, states: &mut [&mut Self::State], inputs: &Tensor) -> Tensor {
+        let mut hidden = inputs.shallow_clone();
+        for (i, layer_weights) in self.weights.layers().enumerate() {
+            let layer_states: Vec<_> = states.iter().map(|s| &s[i]).collect();
+            let mut batch_state = T::cat_cell_states(&layer_states);
+            hidden = T::cell_batch_step(self, &mut batch_state, &layer_weights, &hidden);
+            for (state, layer_state) in states.iter_mut().zip(T::split_cell_state(&batch_state)) {
+                state[i] = layer_state;
+            }
+        }
+        hidden
+    }
 }
 
 #[serde_as]
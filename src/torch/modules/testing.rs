@@ -155,6 +155,50 @@ where
     }
 }
 
+/// Check that [`SeqIterative::batch_step`] output matches [`SeqIterative::step`].
+///
+/// The sequences in the batch are at different steps so that their states differ.
+pub fn check_batch_step_matches_step<M: SeqIterative>(module: &M, in_dim: usize, out_dim: usize) {
+    let _no_grad_guard = tch::no_grad_guard();
+
+    let num_seqs = 3;
+    let seq_len = 4;
+    let input_data = Tensor::rand(
+        &[seq_len, num_seqs, in_dim as i64],
+        (Kind::Float, Device::Cpu),
+    );
+
+    // Sequence i has a prefix of i steps before the batched steps
+    let prefix_states = || -> Vec<_> {
+        (0..num_seqs)
+            .map(|i| {
+                let mut state = module.initial_state();
+                for _ in 0..i {
+                    let _ = module.step(&mut state, &input_data.i((0, i, ..)));
+                }
+                state
+            })
+            .collect()
+    };
+    let mut batch_states = prefix_states();
+    let mut step_states = prefix_states();
+
+    for j in 0..seq_len {
+        let mut state_refs: Vec<_> = batch_states.iter_mut().collect();
+        let batch_output = module.batch_step(&mut state_refs, &input_data.i(j));
+        assert_eq!(batch_output.size(), vec![num_seqs, out_dim as i64]);
+
+        for (i, state) in step_states.iter_mut().enumerate() {
+            let step_output = module.step(state, &input_data.i((j, i as i64, ..)));
+            let batch_step_output = batch_output.i(i as i64);
+            assert!(
+                step_output.allclose(&batch_step_output, 1e-6, 1e-6, false),
+                "seq {i}, step {j}; {step_output:?} != {batch_step_output:?}",
+            );
+        }
+    }
+}
+
 /// Check that gradient descent improves the output of a model.
 pub fn check_config_gradient_descent<R, MC>(config: &MC)
 where
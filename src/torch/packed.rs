@@ -2,7 +2,7 @@
 use super::serialize::TensorDef;
 use crate::torch::tensors::ExclusiveTensor;
 use crate::utils::sequence::Sequence;
-use ndarray::{azip, ArrayViewMut, Axis, IxDyn, Slice};
+use ndarray::{azip, ArrayView, ArrayViewMut, Axis, IxDyn, Slice};
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
@@ -266,6 +266,51 @@ impl PackedTensor {
         }
     }
 
+    /// Copy a subset of the packed sequences.
+    ///
+    /// # Args
+    /// * `sequences` - Indices of the sequences to select, in strictly increasing order.
+    ///     Sequences are indexed by their packing order (from longest to shortest).
+    ///
+    /// # Panics
+    /// If `sequences` is not strictly increasing.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap,
+        clippy::cast_sign_loss
+    )]
+    pub fn select_sequences(&self, sequences: &[usize]) -> Self {
+        assert!(
+            sequences.windows(2).all(|w| w[0] < w[1]),
+            "sequence indices must be strictly increasing"
+        );
+        let batch_sizes: Vec<i64> = self.batch_sizes_tensor().into();
+        let mut indices = Vec::new();
+        let mut new_batch_sizes = Vec::new();
+        let mut offset = 0;
+        for batch_size in batch_sizes {
+            let start = indices.len();
+            indices.extend(
+                sequences
+                    .iter()
+                    .take_while(|&&i| i < batch_size as usize)
+                    .map(|&i| offset + i as i64),
+            );
+            let new_batch_size = indices.len() - start;
+            if new_batch_size == 0 {
+                break;
+            }
+            new_batch_sizes.push(new_batch_size);
+            offset += batch_size;
+        }
+        Self {
+            tensor: self
+                .tensor
+                .index_select(0, &Tensor::of_slice(&indices).to(self.device())),
+            structure: PackedStructure::from_batch_sizes(new_batch_sizes).unwrap(),
+        }
+    }
+
     /// Discounted cumulative sum from sequence end to start for a tensor.
     ///
     /// For each element `x[i]` in the sequence `x[0] ... x[N]`,
@@ -306,6 +351,51 @@ impl PackedTensor {
             structure: self.structure.clone(),
         }
     }
+
+    /// Discounted cumulative sum from sequence end to start with a per-element discount.
+    ///
+    /// For each element `x[i]` in the sequence `x[0] ... x[N]` with discounts `d[0] ... d[N]`,
+    /// returns `y[i] = x[i] + d[i] * y[i + 1]` where `y[N + 1] = 0`.
+    ///
+    /// # Warning
+    /// Does not preserve gradients.
+    ///
+    /// # Panics
+    /// If `discounts` does not have the same shape and data type as `self`.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn discounted_cumsum_from_end_with<T>(&self, discounts: &Self) -> Self
+    where
+        T: Mul + AddAssign<<T as Mul>::Output> + Copy + Element,
+    {
+        assert_eq!(
+            self.tensor.size(),
+            discounts.tensor.size(),
+            "discounts shape does not match"
+        );
+        let mut out = ExclusiveTensor::<T, _>::try_copy_from(self.tensor()).unwrap();
+        let discounts = ExclusiveTensor::<T, _>::try_copy_from(discounts.tensor()).unwrap();
+        let rev_batch_sizes: Vec<usize> = match &self.structure {
+            PackedStructure::Ragged(batch_sizes) => batch_sizes
+                .as_slice()
+                .iter()
+                .map(|b| *b as usize)
+                .rev()
+                .collect(),
+            PackedStructure::Aligned {
+                sequence_length,
+                batch_size,
+            } => vec![*batch_size; *sequence_length],
+        };
+        inplace_discounted_cumsum_from_end_with(
+            out.array_view_mut(),
+            &discounts.array_view(),
+            rev_batch_sizes,
+        );
+        Self {
+            tensor: out.into_tensor().to_device(self.tensor.device()),
+            structure: self.structure.clone(),
+        }
+    }
 }
 
 #[allow(clippy::cast_possible_wrap)]
@@ -341,6 +431,39 @@ fn inplace_discounted_cumsum_from_end<I, T>(
     );
 }
 
+/// Like [`inplace_discounted_cumsum_from_end`] with a discount for each element of `array`.
+#[allow(clippy::cast_possible_wrap)]
+fn inplace_discounted_cumsum_from_end_with<I, T>(
+    mut array: ArrayViewMut<T, IxDyn>,
+    discounts: &ArrayView<T, IxDyn>,
+    rev_batch_sizes: I, // Batch sizes in reverse order
+) where
+    I: IntoIterator<Item = usize>,
+    T: Mul + AddAssign<<T as Mul>::Output> + Copy,
+{
+    // Everything to the right of offset is complete, to the left is incomplete
+    let mut offset = array.shape()[0]; // Panics if array is 0-dimensional
+    for batch_size in rev_batch_sizes {
+        let (left, prev_batch) = array.split_at(Axis(0), offset);
+        array = left;
+        offset -= batch_size;
+
+        let prev_batch_size = prev_batch.shape()[0];
+        let slice = Slice {
+            start: offset as isize,
+            end: Some((offset + prev_batch_size) as isize),
+            step: 1,
+        };
+        let batch_part = array.slice_axis_mut(Axis(0), slice);
+        let discount_part = discounts.slice_axis(Axis(0), slice);
+        azip!((a in batch_part, b in &prev_batch, d in &discount_part) *a += *b * *d);
+    }
+    assert_eq!(
+        offset, 0,
+        "batch sizes do not match array first dimension length"
+    );
+}
+
 /// Information about a packed tensor structure.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PackedStructure {
@@ -412,6 +535,28 @@ impl PackedStructure {
         }
     }
 
+    /// The length of each sequence in packing order (monotonic decreasing).
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    #[must_use]
+    pub fn sequence_lengths(&self) -> Vec<usize> {
+        match self {
+            Self::Ragged(batch_sizes) => {
+                let batch_sizes = batch_sizes.as_slice();
+                let mut lengths = vec![0; batch_sizes.first().map_or(0, |&b| b as usize)];
+                for &batch_size in batch_sizes {
+                    for length in &mut lengths[..batch_size as usize] {
+                        *length += 1;
+                    }
+                }
+                lengths
+            }
+            Self::Aligned {
+                sequence_length,
+                batch_size,
+            } => vec![*sequence_length; *batch_size],
+        }
+    }
+
     /// The total number of elements across all sequences represented by this structure.
     #[must_use]
     pub fn len(&self) -> usize {
@@ -975,6 +1120,44 @@ mod packed_tensor {
         assert_eq!(packed_tensor, expected);
     }
 
+    #[test]
+    fn discounted_cumsum_from_end_with() {
+        let packed_tensor = PackedTensor::from_sorted_sequences([
+            &[1.0, 2.0, 3.0, 4.0] as &[_],
+            &[5.0, 6.0],
+            &[7.0, 8.0],
+        ])
+        .unwrap();
+        let discounts = PackedTensor::from_sorted_sequences([
+            &[0.1, 0.5, 0.0, 0.3] as &[_],
+            &[0.1, 0.2],
+            &[1.0, 0.1],
+        ])
+        .unwrap();
+
+        let cumsum = packed_tensor.discounted_cumsum_from_end_with::<f64>(&discounts);
+
+        // Sequences: [1.35, 3.5, 3, 4], [5.6, 6], [15, 8]
+        let expected = PackedTensor::from_sorted_sequences([
+            &[1.35, 3.5, 3.0, 4.0] as &[_],
+            &[5.6, 6.0],
+            &[15.0, 8.0],
+        ])
+        .unwrap();
+        assert_eq!(cumsum.structure, expected.structure);
+        assert!(
+            bool::from(
+                cumsum
+                    .tensor
+                    .isclose(&expected.tensor, 1e-8, 1e-8, false)
+                    .all()
+            ),
+            "result: {:?}\nexpected: {:?}",
+            cumsum,
+            expected,
+        );
+    }
+
     #[test]
     fn discounted_cumsum_from_end() {
         let packed_tensor = PackedTensor::from_sorted_sequences([
@@ -1007,6 +1190,33 @@ mod packed_tensor {
         );
     }
 
+    #[rstest]
+    fn select_sequences_first_last(packed_tensor: PackedTensor) {
+        let actual = packed_tensor.select_sequences(&[0, 2]);
+        let expected =
+            PackedTensor::from_sorted_sequences([&[0, 1, 2, 3] as &[_], &[100, 101]]).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    fn select_sequences_short(packed_tensor: PackedTensor) {
+        let actual = packed_tensor.select_sequences(&[1, 2]);
+        let expected =
+            PackedTensor::from_sorted_sequences([&[10, 11] as &[_], &[100, 101]]).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[should_panic(expected = "strictly increasing")]
+    fn select_sequences_unsorted(packed_tensor: PackedTensor) {
+        let _ = packed_tensor.select_sequences(&[2, 0]);
+    }
+
+    #[rstest]
+    fn sequence_lengths(packed_tensor: PackedTensor) {
+        assert_eq!(packed_tensor.structure.sequence_lengths(), [4, 2, 2]);
+    }
+
     #[rstest]
     fn batch_sizes_tensor_values(packed_tensor: PackedTensor) {
         let actual = packed_tensor.structure.batch_sizes_tensor();
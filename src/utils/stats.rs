@@ -216,6 +216,229 @@ impl<T: Real> AddAssign for OnlineMeanVariance<T> {
     }
 }
 
+/// Streaming quantile estimate using the P² algorithm.
+///
+/// Uses constant memory by tracking 5 markers whose heights approximate the minimum,
+/// the `p/2`, `p`, and `(1+p)/2` quantiles, and the maximum.
+/// The estimate is exact for up to 5 values.
+///
+/// # Reference
+/// The P² algorithm for dynamic calculation of quantiles and histograms without storing
+/// observations. Jain and Chlamtac (1985) <https://doi.org/10.1145/4372.4378>
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct P2Quantile {
+    /// Target quantile in `[0, 1]`.
+    p: f64,
+    /// Number of values seen.
+    count: u64,
+    /// Marker heights. Holds the values themselves while `count < 5`.
+    heights: [f64; 5],
+    /// Actual marker positions (1-based).
+    positions: [f64; 5],
+    /// Desired marker positions (1-based).
+    desired: [f64; 5],
+}
+
+impl P2Quantile {
+    /// Create a new estimator of the `p` quantile.
+    ///
+    /// # Panics
+    /// If `p` is not in `[0, 1]`.
+    #[must_use]
+    pub fn new(p: f64) -> Self {
+        assert!((0.0..=1.0).contains(&p), "quantile {p} not in [0, 1]");
+        Self {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+        }
+    }
+
+    /// The target quantile.
+    #[inline]
+    #[must_use]
+    pub const fn p(&self) -> f64 {
+        self.p
+    }
+
+    /// The number of accumulated values.
+    #[inline]
+    #[must_use]
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Add a new value to the estimate.
+    pub fn push(&mut self, value: f64) {
+        if self.count < 5 {
+            #[allow(clippy::cast_possible_truncation)]
+            let n = self.count as usize;
+            self.heights[n] = value;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(f64::total_cmp);
+            }
+            return;
+        }
+        self.count += 1;
+
+        // Cell containing the new value, extending the extreme markers if necessary.
+        let heights = &mut self.heights;
+        let cell = if value < heights[0] {
+            heights[0] = value;
+            0
+        } else if value >= heights[4] {
+            heights[4] = value;
+            3
+        } else {
+            (1..4).find(|&i| value < heights[i]).unwrap_or(4) - 1
+        };
+        for position in &mut self.positions[cell + 1..] {
+            *position += 1.0;
+        }
+        let p = self.p;
+        for (desired, increment) in
+            self.desired
+                .iter_mut()
+                .zip([0.0, p / 2.0, p, 0.5 + p / 2.0, 1.0])
+        {
+            *desired += increment;
+        }
+
+        // Adjust the heights of the middle markers if they are off from their desired positions.
+        let positions = &mut self.positions;
+        for i in 1..4 {
+            let offset = self.desired[i] - positions[i];
+            if (offset >= 1.0 && positions[i + 1] - positions[i] > 1.0)
+                || (offset <= -1.0 && positions[i - 1] - positions[i] < -1.0)
+            {
+                let step = offset.signum();
+                let (prev, next) = (i - 1, i + 1);
+                let parabolic = heights[i]
+                    + step / (positions[next] - positions[prev])
+                        * ((positions[i] - positions[prev] + step) * (heights[next] - heights[i])
+                            / (positions[next] - positions[i])
+                            + (positions[next] - positions[i] - step)
+                                * (heights[i] - heights[prev])
+                                / (positions[i] - positions[prev]));
+                heights[i] = if heights[prev] < parabolic && parabolic < heights[next] {
+                    parabolic
+                } else {
+                    // Linear prediction towards the neighbour in the direction of the step
+                    let neighbour = if step > 0.0 { next } else { prev };
+                    heights[i]
+                        + step * (heights[neighbour] - heights[i])
+                            / (positions[neighbour] - positions[i])
+                };
+                positions[i] += step;
+            }
+        }
+    }
+
+    /// The estimated quantile if at least one value has been accumulated.
+    #[must_use]
+    pub fn quantile(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            n @ 1..=5 => {
+                // Linear interpolation between the closest ranks of the sorted values
+                #[allow(clippy::cast_possible_truncation)]
+                let mut values = self.heights[..n as usize].to_vec();
+                values.sort_by(f64::total_cmp);
+                let rank = self.p * (n - 1) as f64;
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let lower = rank.floor() as usize;
+                let upper = (lower + 1).min(values.len() - 1);
+                let frac = rank - rank.floor();
+                Some(values[lower] + frac * (values[upper] - values[lower]))
+            }
+            _ => Some(self.heights[2]),
+        }
+    }
+}
+
+/// Streaming histogram with a bounded number of bins.
+///
+/// Each bin is a centroid value with the number of values it represents.
+/// When a new value would exceed the maximum number of bins,
+/// the two bins with the closest centroids are merged into their weighted mean.
+///
+/// # Reference
+/// A Streaming Parallel Decision Tree Algorithm. Ben-Haim and Tom-Tov (2010)
+/// <https://jmlr.org/papers/v11/ben-haim10a.html>
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamingHistogram {
+    max_bins: usize,
+    /// `(centroid, count)` pairs sorted by centroid.
+    bins: Vec<(f64, u64)>,
+}
+
+impl StreamingHistogram {
+    /// Create a new empty histogram with at most `max_bins` bins.
+    ///
+    /// # Panics
+    /// If `max_bins` is zero.
+    #[must_use]
+    pub fn new(max_bins: usize) -> Self {
+        assert!(max_bins > 0, "histogram must have at least one bin");
+        Self {
+            max_bins,
+            bins: Vec::with_capacity(max_bins + 1),
+        }
+    }
+
+    /// The bins as `(centroid, count)` pairs in increasing order of centroid.
+    #[inline]
+    #[must_use]
+    pub fn bins(&self) -> &[(f64, u64)] {
+        &self.bins
+    }
+
+    /// The number of accumulated values.
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.bins.iter().map(|(_, count)| count).sum()
+    }
+
+    /// Add a new value to the histogram.
+    pub fn push(&mut self, value: f64) {
+        match self
+            .bins
+            .binary_search_by(|(centroid, _)| centroid.total_cmp(&value))
+        {
+            Ok(i) => {
+                self.bins[i].1 += 1;
+                return;
+            }
+            Err(i) => self.bins.insert(i, (value, 1)),
+        }
+        if self.bins.len() > self.max_bins {
+            // Merge the closest pair of adjacent bins
+            let i = (0..self.bins.len() - 1)
+                .min_by(|&a, &b| {
+                    let gap_a = self.bins[a + 1].0 - self.bins[a].0;
+                    let gap_b = self.bins[b + 1].0 - self.bins[b].0;
+                    gap_a.total_cmp(&gap_b)
+                })
+                .unwrap();
+            let (centroid_2, count_2) = self.bins.remove(i + 1);
+            let (centroid_1, count_1) = self.bins[i];
+            let count = count_1 + count_2;
+            #[allow(clippy::cast_precision_loss)]
+            let centroid =
+                (centroid_1 * count_1 as f64 + centroid_2 * count_2 as f64) / count as f64;
+            self.bins[i] = (centroid, count);
+        }
+    }
+
+    /// Remove all values.
+    pub fn clear(&mut self) {
+        self.bins.clear();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -258,4 +481,101 @@ mod tests {
         let c: OnlineMeanVariance<f64> = [1.0, 2.0, 3.0, 4.0].into_iter().collect();
         assert_eq!([a, b].into_iter().sum::<OnlineMeanVariance<_>>(), c);
     }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn p2_quantile_exact_small() {
+        let mut median = P2Quantile::new(0.5);
+        assert_eq!(median.quantile(), None);
+        for x in [3.0, 1.0, 2.0, 10.0] {
+            median.push(x);
+        }
+        assert_eq!(median.quantile(), Some(2.5));
+        median.push(0.0);
+        assert_eq!(median.quantile(), Some(2.0));
+    }
+
+    #[test]
+    fn p2_quantile_exact_small_tails() {
+        let mut low = P2Quantile::new(0.05);
+        let mut high = P2Quantile::new(0.95);
+        for x in [3.0, 1.0, 4.0, 2.0] {
+            low.push(x);
+            high.push(x);
+        }
+        assert!((low.quantile().unwrap() - 1.15).abs() < 1e-12);
+        assert!((high.quantile().unwrap() - 3.85).abs() < 1e-12);
+        low.push(5.0);
+        high.push(5.0);
+        assert!((low.quantile().unwrap() - 1.2).abs() < 1e-12);
+        assert!((high.quantile().unwrap() - 4.8).abs() < 1e-12);
+    }
+
+    #[test]
+    fn p2_quantile_uniform() {
+        use rand::prelude::*;
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut estimates = [
+            P2Quantile::new(0.05),
+            P2Quantile::new(0.5),
+            P2Quantile::new(0.95),
+        ];
+        for _ in 0..10_000 {
+            let x: f64 = rng.gen();
+            for estimate in &mut estimates {
+                estimate.push(x);
+            }
+        }
+        for estimate in &estimates {
+            let error = (estimate.quantile().unwrap() - estimate.p()).abs();
+            assert!(error < 0.01, "p = {}; error = {error}", estimate.p());
+        }
+    }
+
+    #[test]
+    fn p2_quantile_sorted_input() {
+        let mut median = P2Quantile::new(0.5);
+        for i in 0..1001 {
+            median.push(f64::from(i));
+        }
+        assert!((median.quantile().unwrap() - 500.0).abs() < 5.0);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn streaming_histogram_exact_within_max_bins() {
+        let mut histogram = StreamingHistogram::new(4);
+        for x in [2.0, 1.0, 2.0, 3.0] {
+            histogram.push(x);
+        }
+        assert_eq!(histogram.bins(), [(1.0, 1), (2.0, 2), (3.0, 1)]);
+        assert_eq!(histogram.count(), 4);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn streaming_histogram_merges_closest() {
+        let mut histogram = StreamingHistogram::new(3);
+        for x in [0.0, 10.0, 20.0, 11.0] {
+            histogram.push(x);
+        }
+        assert_eq!(histogram.bins(), [(0.0, 1), (10.5, 2), (20.0, 1)]);
+    }
+
+    #[test]
+    fn streaming_histogram_bounded() {
+        let mut histogram = StreamingHistogram::new(8);
+        for i in 0..1000 {
+            histogram.push(f64::from(i));
+        }
+        assert_eq!(histogram.bins().len(), 8);
+        assert_eq!(histogram.count(), 1000);
+        let mean: f64 = histogram
+            .bins()
+            .iter()
+            .map(|&(centroid, count)| centroid * count as f64)
+            .sum::<f64>()
+            / 1000.0;
+        assert!((mean - 499.5).abs() < 1e-9);
+    }
 }